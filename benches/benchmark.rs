@@ -1,6 +1,91 @@
 use assert_cmd::Command;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tempfile::tempdir;
+use tuni::{UnifyOptions, Warnings};
+
+/// Number of samples in the synthetic cohort.
+const N_SAMPLES: usize = 8;
+
+/// Number of transcripts in each sample of the synthetic cohort.
+const N_TRANSCRIPTS: usize = 100_000;
+
+/// Number of exons in each transcript of the synthetic cohort.
+const N_EXONS: usize = 8;
+
+/// Bytes currently allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Most bytes allocated at once since the last reset.
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Track allocated bytes, so the peak memory of unification can be reported.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() {
+            let allocated = ALLOCATED.fetch_add(new_size - layout.size(), Ordering::Relaxed)
+                + new_size
+                - layout.size();
+            PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+        } else {
+            ALLOCATED.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Write a synthetic GTF per sample. Every fourth transcript has a structure
+/// private to its sample, the rest are shared by every sample.
+fn write_synthetic_cohort(output_dir: &Path) -> Vec<PathBuf> {
+    (0..N_SAMPLES)
+        .map(|sample| {
+            let gtf_path = output_dir.join(format!("sample_{}.gtf", sample));
+            let mut writer = BufWriter::new(File::create(&gtf_path).unwrap());
+
+            for transcript in 0..N_TRANSCRIPTS {
+                let private_offset = match transcript % 4 {
+                    0 => sample + 1,
+                    _ => 0,
+                };
+                let start = transcript * 10_000;
+                for exon in 0..N_EXONS {
+                    let exon_start = start + exon * 1_000 + private_offset;
+                    writeln!(
+                        writer,
+                        "chr{}\tbench\texon\t{}\t{}\t.\t+\t.\ttranscript_id \"T{}\";",
+                        transcript % 22 + 1,
+                        exon_start,
+                        exon_start + 500,
+                        transcript
+                    )
+                    .unwrap();
+                }
+            }
+
+            gtf_path
+        })
+        .collect()
+}
 
 /// Benchmark tuni performance across large samples.
 pub fn benchmark_tuni(c: &mut Criterion) {
@@ -15,6 +100,35 @@ pub fn benchmark_tuni(c: &mut Criterion) {
     c.bench_function("tuni benchmark", |b| b.iter(|| cmd.assert().success()));
 }
 
+/// Benchmark the speed and peak memory of reading and unifying a synthetic
+/// cohort through the library.
+pub fn benchmark_unify_cohort(c: &mut Criterion) {
+    let temp_dir = tempdir().unwrap();
+    let gtf_paths = write_synthetic_cohort(temp_dir.path());
+    let options = UnifyOptions::default();
+
+    // Measure the peak memory of one run, relative to before the run.
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK_ALLOCATED.store(baseline, Ordering::Relaxed);
+    let transcript_unifier =
+        tuni::unify_gtf_gffs(&gtf_paths, &options, &mut Warnings::default()).unwrap();
+    let peak_allocated = PEAK_ALLOCATED.load(Ordering::Relaxed) - baseline;
+    let retained = ALLOCATED.load(Ordering::Relaxed) - baseline;
+    println!(
+        "unify cohort: {} samples x {} transcripts, {} unified IDs, peak {:.1} MB, retained {:.1} MB",
+        N_SAMPLES,
+        N_TRANSCRIPTS,
+        transcript_unifier.unified_signatures().len(),
+        peak_allocated as f64 / 1e6,
+        retained as f64 / 1e6
+    );
+    drop(transcript_unifier);
+
+    c.bench_function("unify cohort", |b| {
+        b.iter(|| tuni::unify_gtf_gffs(black_box(&gtf_paths), &options, &mut Warnings::default()))
+    });
+}
+
 // criterion's statistics is not intended for longer benchmarks:
 // https://github.com/bheisler/criterion.rs/issues/322
 // Here, we time how long it takes to run tuni on large samples (10 iterations),
@@ -22,6 +136,6 @@ pub fn benchmark_tuni(c: &mut Criterion) {
 criterion_group! {
     name = benches;
     config = Criterion::default().significance_level(0.05).sample_size(10);
-    targets = benchmark_tuni
+    targets = benchmark_tuni, benchmark_unify_cohort
 }
 criterion_main!(benches);
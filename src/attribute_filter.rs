@@ -0,0 +1,452 @@
+//! Filter transcripts by an expression over their GTF/GFF attributes.
+//!
+//! Expressions compare attributes with numbers or strings, combined with
+//! `&&`, `||`, `!` and parentheses, e.g.
+//! `TPM >= 1 && transcript_biotype != "retained_intron"`. `&&` binds tighter
+//! than `||`.
+//!
+//! Transcripts failing the expression are filtered while reading with
+//! [`FilterReason::Attribute`](crate::filter::FilterReason), before their
+//! `TranscriptSignature`s are grouped.
+
+use crate::error::AttributeFilterError;
+use std::{cmp::Ordering, fmt, iter::Peekable, str::CharIndices};
+
+/// Comparison between an attribute and a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Operator {
+    /// Returns true if `ordering` (attribute compared with value) satisfies
+    /// the operator, otherwise false.
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Eq => ordering.is_eq(),
+            Operator::Ne => ordering.is_ne(),
+            Operator::Lt => ordering.is_lt(),
+            Operator::Le => ordering.is_le(),
+            Operator::Gt => ordering.is_gt(),
+            Operator::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// Value an attribute is compared with.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(Box<str>),
+}
+
+/// Parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare {
+        /// Index of the attribute in [`AttributeFilter::keys`].
+        key: usize,
+        operator: Operator,
+        value: Value,
+    },
+}
+
+/// Token of a filter expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Compare(Operator),
+    Word(String),
+    Text(String),
+}
+
+/// Only unify transcripts whose attributes pass an expression.
+///
+/// Each attribute takes the first value found on any line of the transcript.
+/// Transcripts without an attribute fail every comparison of it, except `!=`.
+/// Attributes compared with a number that are not numeric, e.g. `cov "NA"`,
+/// are treated as missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeFilter {
+    /// Expression as provided.
+    source: String,
+
+    /// Attributes referenced by the expression, in order of first use.
+    keys: Vec<Box<str>>,
+
+    /// Parsed expression.
+    expression: Expression,
+}
+
+impl AttributeFilter {
+    /// Parse a filter expression.
+    ///
+    /// Values are numbers, double-quoted strings or bare words. Strings can
+    /// only be compared with `==` and `!=`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FilterParseError`](AttributeFilterError::FilterParseError)
+    /// if the expression is malformed.
+    pub fn parse(source: &str) -> Result<AttributeFilter, AttributeFilterError> {
+        let parse_error =
+            |detail: String| AttributeFilterError::FilterParseError(source.to_string(), detail);
+
+        let tokens = tokenise(source).map_err(parse_error)?;
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+            keys: Vec::new(),
+        };
+        let expression = parser.parse_or().map_err(parse_error)?;
+        if let Some(token) = parser.tokens.next() {
+            return Err(parse_error(format!("unexpected {:?}", token)));
+        }
+
+        Ok(AttributeFilter {
+            source: source.to_string(),
+            keys: parser.keys,
+            expression,
+        })
+    }
+
+    /// Attributes referenced by the expression, in the order values are passed
+    /// to [`AttributeFilter::passes`].
+    pub fn keys(&self) -> &[Box<str>] {
+        &self.keys
+    }
+
+    /// Returns true if a transcript with the `values` of each of
+    /// [`AttributeFilter::keys`] passes, otherwise false.
+    pub fn passes(&self, values: &[Option<&str>]) -> bool {
+        self.evaluate(&self.expression, values)
+    }
+
+    /// Attributes compared with a number whose value is not numeric, with
+    /// that value, in the order of [`AttributeFilter::keys`].
+    ///
+    /// These comparisons fail as if the attribute were missing.
+    pub fn non_numeric_values<'a>(&self, values: &[Option<&'a str>]) -> Vec<(&str, &'a str)> {
+        let mut keys = Vec::new();
+        collect_number_keys(&self.expression, &mut keys);
+        keys.sort_unstable();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let attribute = values.get(key).copied().flatten()?;
+                parse_number(attribute)
+                    .is_none()
+                    .then_some((&*self.keys[key], attribute))
+            })
+            .collect()
+    }
+
+    /// Evaluate `expression` for the `values` of each key.
+    fn evaluate(&self, expression: &Expression, values: &[Option<&str>]) -> bool {
+        match expression {
+            Expression::And(left, right) => {
+                self.evaluate(left, values) && self.evaluate(right, values)
+            }
+            Expression::Or(left, right) => {
+                self.evaluate(left, values) || self.evaluate(right, values)
+            }
+            Expression::Not(inner) => !self.evaluate(inner, values),
+            Expression::Compare {
+                key,
+                operator,
+                value,
+            } => {
+                let Some(attribute) = values.get(*key).copied().flatten() else {
+                    return *operator == Operator::Ne;
+                };
+                let ordering = match value {
+                    Value::Number(number) => {
+                        match parse_number(attribute).and_then(|x| x.partial_cmp(number)) {
+                            Some(ordering) => ordering,
+                            None => return *operator == Operator::Ne,
+                        }
+                    }
+                    Value::Text(text) => attribute.cmp(text),
+                };
+                operator.accepts(ordering)
+            }
+        }
+    }
+}
+
+/// Parse an attribute compared with a number, or None if it is not numeric.
+fn parse_number(attribute: &str) -> Option<f64> {
+    attribute.parse::<f64>().ok().filter(|x| !x.is_nan())
+}
+
+/// Collect the keys of every attribute in `expression` compared with a number.
+fn collect_number_keys(expression: &Expression, keys: &mut Vec<usize>) {
+    match expression {
+        Expression::And(left, right) | Expression::Or(left, right) => {
+            collect_number_keys(left, keys);
+            collect_number_keys(right, keys);
+        }
+        Expression::Not(inner) => collect_number_keys(inner, keys),
+        Expression::Compare {
+            key,
+            value: Value::Number(_),
+            ..
+        } => keys.push(*key),
+        Expression::Compare { .. } => {}
+    }
+}
+
+impl fmt::Display for AttributeFilter {
+    /// Write the expression as provided.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Split an expression into tokens.
+///
+/// Returns a description of the problem if any character is unexpected or a
+/// string is unterminated.
+fn tokenise(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let next_is = |expected: char, chars: &mut Peekable<CharIndices>| {
+            chars.next_if(|(_, x)| *x == expected).is_some()
+        };
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if next_is('&', &mut chars) => Token::And,
+            '|' if next_is('|', &mut chars) => Token::Or,
+            '=' if next_is('=', &mut chars) => Token::Compare(Operator::Eq),
+            '!' if next_is('=', &mut chars) => Token::Compare(Operator::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=', &mut chars) => Token::Compare(Operator::Le),
+            '<' => Token::Compare(Operator::Lt),
+            '>' if next_is('=', &mut chars) => Token::Compare(Operator::Ge),
+            '>' => Token::Compare(Operator::Gt),
+            '"' => {
+                let text = chars
+                    .by_ref()
+                    .map(|(_, x)| x)
+                    .take_while(|x| *x != '"')
+                    .collect::<String>();
+                if !source[i + 1..].contains('"') {
+                    return Err("unterminated string".to_string());
+                }
+                Token::Text(text)
+            }
+            _ if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, x)) = chars.next_if(|(_, x)| is_word_char(*x)) {
+                    word.push(x);
+                }
+                Token::Word(word)
+            }
+            _ => return Err(format!("unexpected character {:?}", c)),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Returns true if `c` can be part of an attribute name, number or bare word,
+/// otherwise false.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || ['_', '.', '-', '+', ':'].contains(&c)
+}
+
+/// Recursive descent parser over the tokens of an expression.
+struct Parser {
+    /// Remaining tokens.
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+
+    /// Attributes referenced so far.
+    keys: Vec<Box<str>>,
+}
+
+impl Parser {
+    /// Parse `and ("||" and)*`.
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    /// Parse `unary ("&&" unary)*`.
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_unary()?;
+        while self.tokens.next_if_eq(&Token::And).is_some() {
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_unary()?));
+        }
+        Ok(expression)
+    }
+
+    /// Parse `"!" unary`, `"(" or ")"` or a comparison.
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Expression::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expression = self.parse_or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(expression),
+                    _ => Err("expected \")\"".to_string()),
+                }
+            }
+            Some(Token::Word(key)) => self.parse_comparison(key),
+            Some(token) => Err(format!("expected an attribute, found {:?}", token)),
+            None => Err("expected an attribute, found the end".to_string()),
+        }
+    }
+
+    /// Parse `operator value` following the attribute `key`.
+    fn parse_comparison(&mut self, key: String) -> Result<Expression, String> {
+        let Some(Token::Compare(operator)) = self.tokens.next() else {
+            return Err(format!("expected a comparison after {:?}", key));
+        };
+        let value = match self.tokens.next() {
+            Some(Token::Text(text)) => Value::Text(text.into()),
+            Some(Token::Word(word)) => match word.parse::<f64>() {
+                Ok(number) => Value::Number(number),
+                Err(_) => Value::Text(word.into()),
+            },
+            _ => return Err(format!("expected a value after {:?}", key)),
+        };
+        if matches!(value, Value::Text(_)) && !matches!(operator, Operator::Eq | Operator::Ne) {
+            return Err(format!("{:?} must be compared with a number", key));
+        }
+
+        let key = match self.keys.iter().position(|x| **x == *key) {
+            Some(index) => index,
+            None => {
+                self.keys.push(key.into());
+                self.keys.len() - 1
+            }
+        };
+        Ok(Expression::Compare {
+            key,
+            operator,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        r#"TPM >= 1 && transcript_biotype != "retained_intron""#,
+        Some("2"),
+        Some("protein_coding"),
+        true
+    )]
+    #[case(
+        r#"TPM >= 1 && transcript_biotype != "retained_intron""#,
+        Some("0.5"),
+        Some("protein_coding"),
+        false
+    )]
+    #[case(
+        r#"TPM >= 1 && transcript_biotype != "retained_intron""#,
+        Some("2"),
+        Some("retained_intron"),
+        false
+    )]
+    #[case(
+        r#"TPM >= 1 && transcript_biotype != "retained_intron""#,
+        Some("2"),
+        None,
+        true
+    )]
+    #[case("TPM >= 1 && transcript_biotype != retained_intron", None, None, false)]
+    #[case(
+        "TPM < 1 || transcript_biotype == lncRNA",
+        Some("5"),
+        Some("lncRNA"),
+        true
+    )]
+    #[case(
+        "!(TPM < 1 || transcript_biotype == lncRNA)",
+        Some("5"),
+        Some("lncRNA"),
+        false
+    )]
+    #[case(
+        "TPM > 1e-3 && !(transcript_biotype == lncRNA)",
+        Some("0.01"),
+        Some("x"),
+        true
+    )]
+    fn test_attribute_filter(
+        #[case] source: &str,
+        #[case] tpm: Option<&str>,
+        #[case] biotype: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let attribute_filter = AttributeFilter::parse(source).unwrap();
+
+        assert_eq!(attribute_filter.to_string(), source);
+        assert_eq!(
+            attribute_filter.keys(),
+            [Box::from("TPM"), Box::from("transcript_biotype")]
+        );
+        assert_eq!(attribute_filter.passes(&[tpm, biotype]), expected);
+    }
+
+    #[rstest]
+    #[case("TPM >=", "expected a value")]
+    #[case("TPM 1", "expected a comparison")]
+    #[case("TPM >= 1 &&", "found the end")]
+    #[case("(TPM >= 1", "expected \")\"")]
+    #[case("TPM >= 1)", "unexpected Close")]
+    #[case(r#"gene_type == "lncRNA"#, "unterminated string")]
+    #[case("gene_type > lncRNA", "must be compared with a number")]
+    #[case("TPM = 1", "unexpected character")]
+    fn test_attribute_filter_parse_error(#[case] source: &str, #[case] expected: &str) {
+        assert!(AttributeFilter::parse(source).is_err_and(|e| e.to_string().contains(expected)));
+    }
+
+    #[rstest]
+    #[case("cov >= 2", Some("NA"), false, vec![("cov", "NA")])]
+    #[case("cov != 2", Some("."), true, vec![("cov", ".")])]
+    #[case("!(cov < 2)", Some("NaN"), true, vec![("cov", "NaN")])]
+    #[case("cov >= 2 || cov == NA", Some("NA"), true, vec![("cov", "NA")])]
+    #[case("cov == NA", Some("NA"), true, vec![])]
+    #[case("cov >= 2", Some("3"), true, vec![])]
+    #[case("cov >= 2", None, false, vec![])]
+    fn test_attribute_filter_non_numeric(
+        #[case] source: &str,
+        #[case] cov: Option<&str>,
+        #[case] expected: bool,
+        #[case] expected_non_numeric: Vec<(&str, &str)>,
+    ) {
+        let attribute_filter = AttributeFilter::parse(source).unwrap();
+
+        assert_eq!(attribute_filter.passes(&[cov]), expected);
+        assert_eq!(
+            attribute_filter.non_numeric_values(&[cov]),
+            expected_non_numeric
+        );
+    }
+}
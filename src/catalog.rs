@@ -0,0 +1,352 @@
+//! Read and write catalogs of unified transcripts.
+//!
+//! A catalog records the `TranscriptSignature` behind each unified ID, so
+//! that new samples can be annotated with existing unified IDs without
+//! re-unifying every previous sample.
+
+use crate::error::CatalogError;
+use crate::gtf_gff::{GtfGffTranscripts, TranscriptSignature};
+use crate::unify::{TranscriptUnifier, UnifiedId};
+use log::info;
+use std::{
+    collections::BTreeMap,
+    fs,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// File name of the catalog stored alongside the unified GTF/GFFs.
+pub const CATALOG_FILE_NAME: &str = "tuni_catalog.tsv";
+
+/// File name of the table translating unified IDs of merged catalogs.
+pub const TRANSLATION_FILE_NAME: &str = "tuni_translation.tsv";
+
+/// Header of the catalog.
+const CATALOG_HEADER: &str = "tuni_id\tchr\tstrand\texon_boundaries\tcds_boundaries";
+
+/// Header of the translation table.
+const TRANSLATION_HEADER: &str = "catalog\ttuni_id\tmerged_tuni_id";
+
+/// Read a catalog, linking each `TranscriptSignature` to its unified ID.
+///
+/// # Errors
+///
+/// Returns [`LineReadError`](CatalogError::LineReadError) if the catalog or
+/// any line in the catalog cannot be read.
+///
+/// Returns [`MalformedLineError`](CatalogError::MalformedLineError) if any
+/// line does not contain 5 tab-separated fields.
+pub fn read_catalog(
+    catalog_path: &Path,
+) -> Result<BTreeMap<TranscriptSignature, UnifiedId>, CatalogError> {
+    info!("{}", catalog_path.display());
+
+    let catalog =
+        File::open(catalog_path).map_err(|_| CatalogError::LineReadError(catalog_path.into()))?;
+    let mut unified_signatures = BTreeMap::new();
+
+    for line in BufReader::new(catalog).lines() {
+        let line = line.map_err(|_| CatalogError::LineReadError(catalog_path.to_path_buf()))?;
+
+        if line == CATALOG_HEADER {
+            continue;
+        }
+
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+        let transcript_signature = TranscriptSignature::from_fields(&line_split[1..])
+            .ok_or(CatalogError::MalformedLineError(line.clone()))?;
+
+        unified_signatures.insert(transcript_signature, Arc::from(line_split[0]));
+    }
+
+    Ok(unified_signatures)
+}
+
+/// A catalog written to a temporary file, which replaces the catalog once
+/// persisted.
+///
+/// If dropped without being persisted, the temporary file is removed and any
+/// existing catalog is left unchanged.
+#[derive(Debug)]
+pub struct StagedCatalog {
+    temp_path: PathBuf,
+    catalog_path: PathBuf,
+    persisted: bool,
+}
+
+impl StagedCatalog {
+    /// Rename the temporary file to the catalog, replacing any existing
+    /// catalog.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileCreateError`](CatalogError::FileCreateError) if the
+    /// temporary file cannot be renamed.
+    pub fn persist(mut self) -> Result<(), CatalogError> {
+        fs::rename(&self.temp_path, &self.catalog_path)
+            .map_err(|_| CatalogError::FileCreateError(self.catalog_path.clone()))?;
+        self.persisted = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedCatalog {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Write a catalog containing every unified transcript to a temporary file,
+/// which only replaces `catalog_path` once persisted.
+///
+/// Staging the catalog before writing other outputs means the catalog is only
+/// updated if every output is written.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](CatalogError::FileCreateError) if the
+/// temporary file cannot be created.
+///
+/// Returns [`FileWriteError`](CatalogError::FileWriteError) if any line in the
+/// catalog cannot be written.
+pub fn stage_catalog(
+    catalog_path: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<StagedCatalog, CatalogError> {
+    info!("{}", catalog_path.display());
+
+    let mut temp_path = PathBuf::from(catalog_path);
+    temp_path.as_mut_os_string().push(".tmp");
+
+    let catalog =
+        File::create(&temp_path).map_err(|_| CatalogError::FileCreateError(temp_path.clone()))?;
+    let staged_catalog = StagedCatalog {
+        temp_path,
+        catalog_path: catalog_path.to_path_buf(),
+        persisted: false,
+    };
+    let temp_path = &staged_catalog.temp_path;
+    let mut writer = BufWriter::new(catalog);
+
+    writeln!(writer, "{}", CATALOG_HEADER)
+        .map_err(|_| CatalogError::FileWriteError(temp_path.clone()))?;
+
+    for (transcript_signature, unified_id) in transcript_unifier.unified_signatures() {
+        writeln!(writer, "{}\t{}", unified_id, transcript_signature)
+            .map_err(|_| CatalogError::FileWriteError(temp_path.clone()))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|_| CatalogError::FileWriteError(temp_path.clone()))?;
+    drop(writer);
+
+    Ok(staged_catalog)
+}
+
+/// Write a catalog containing every unified transcript.
+///
+/// The catalog is first written to a temporary file then renamed, so an
+/// existing catalog is only replaced once the new catalog is complete.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](CatalogError::FileCreateError) if the catalog
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](CatalogError::FileWriteError) if any line in the
+/// catalog cannot be written.
+pub fn write_catalog(
+    catalog_path: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), CatalogError> {
+    stage_catalog(catalog_path, transcript_unifier)?.persist()
+}
+
+/// Merge catalogs, creating a new unified ID for each unique
+/// `TranscriptSignature` across all catalogs.
+///
+/// Each catalog is treated as a sample, with the unified IDs of the catalog
+/// acting as its transcript IDs. Therefore, the returned `TranscriptUnifier`
+/// links each (catalog name, unified ID) to a merged unified ID.
+pub fn merge_catalogs(
+    catalogs: &[(Arc<str>, BTreeMap<TranscriptSignature, UnifiedId>)],
+) -> TranscriptUnifier {
+    let mut transcript_unifier = TranscriptUnifier::new();
+
+    for (catalog_name, unified_signatures) in catalogs {
+        let mut catalog_transcripts = GtfGffTranscripts {
+            signatures: unified_signatures
+                .iter()
+                .map(|(signature, unified_id)| (Arc::clone(unified_id), signature.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        transcript_unifier.group_transcripts(Arc::clone(catalog_name), &mut catalog_transcripts);
+    }
+
+    transcript_unifier.unify_transcripts();
+    transcript_unifier
+}
+
+/// Write a table translating the unified IDs of each merged catalog into the
+/// merged unified IDs.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](CatalogError::FileCreateError) if the table
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](CatalogError::FileWriteError) if any line in the
+/// table cannot be written.
+pub fn write_translation_table(
+    translation_path: &Path,
+    catalogs: &[(Arc<str>, BTreeMap<TranscriptSignature, UnifiedId>)],
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), CatalogError> {
+    info!("{}", translation_path.display());
+
+    let translation_table = File::create(translation_path)
+        .map_err(|_| CatalogError::FileCreateError(translation_path.to_path_buf()))?;
+    let mut writer = BufWriter::new(translation_table);
+
+    writeln!(writer, "{}", TRANSLATION_HEADER)
+        .map_err(|_| CatalogError::FileWriteError(translation_path.to_path_buf()))?;
+
+    for (catalog_name, unified_signatures) in catalogs {
+        for unified_id in unified_signatures.values() {
+            // Every catalog transcript is unified in merge_catalogs().
+            let merged_id = transcript_unifier
+                .get_unified_id(catalog_name, unified_id)
+                .unwrap();
+
+            writeln!(writer, "{}\t{}\t{}", catalog_name, unified_id, merged_id)
+                .map_err(|_| CatalogError::FileWriteError(translation_path.to_path_buf()))?;
+        }
+    }
+
+    writer
+        .flush()
+        .map_err(|_| CatalogError::FileWriteError(translation_path.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gtf_gff::{self, ReadOptions};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_read_catalog() {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
+        let mut gtf_gff_transcripts =
+            gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        let temp_dir = tempdir().unwrap();
+        let catalog_path = temp_dir.path().join(CATALOG_FILE_NAME);
+        write_catalog(&catalog_path, &transcript_unifier).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&catalog_path).unwrap(),
+            format!(
+                "{}\n{}\n{}\n",
+                CATALOG_HEADER, "tuni_0\tchr1\t-\t1,2,11,12\t", "tuni_1\tchr2\t+\t20,30\t25,29"
+            )
+        );
+        assert_eq!(
+            &read_catalog(&catalog_path).unwrap(),
+            transcript_unifier.unified_signatures()
+        );
+    }
+
+    #[test]
+    fn test_stage_catalog() {
+        let temp_dir = tempdir().unwrap();
+        let catalog_path = temp_dir.path().join(CATALOG_FILE_NAME);
+        let temp_path = temp_dir.path().join(format!("{}.tmp", CATALOG_FILE_NAME));
+        fs::write(&catalog_path, "existing").unwrap();
+
+        // Dropping a staged catalog leaves the existing catalog unchanged.
+        let staged_catalog = stage_catalog(&catalog_path, &TranscriptUnifier::new()).unwrap();
+        assert!(temp_path.exists());
+        drop(staged_catalog);
+        assert!(!temp_path.exists());
+        assert_eq!(fs::read_to_string(&catalog_path).unwrap(), "existing");
+
+        // Persisting a staged catalog replaces the existing catalog.
+        stage_catalog(&catalog_path, &TranscriptUnifier::new())
+            .unwrap()
+            .persist()
+            .unwrap();
+        assert!(!temp_path.exists());
+        assert_eq!(
+            fs::read_to_string(&catalog_path).unwrap(),
+            format!("{}\n", CATALOG_HEADER)
+        );
+    }
+
+    #[test]
+    fn test_merge_catalogs() {
+        let signature = |exon_boundaries: [u64; 2]| {
+            TranscriptSignature::from(Arc::from("chr1"), Arc::from("+"), exon_boundaries, [])
+        };
+
+        // Both sites share transcript (1, 10), but assign it different IDs.
+        let catalogs = [
+            (
+                Arc::from("site_1.tsv"),
+                BTreeMap::from([
+                    (signature([1, 10]), Arc::from("tuni_0")),
+                    (signature([5, 10]), Arc::from("tuni_1")),
+                ]),
+            ),
+            (
+                Arc::from("site_2.tsv"),
+                BTreeMap::from([
+                    (signature([3, 10]), Arc::from("tuni_0")),
+                    (signature([1, 10]), Arc::from("tuni_1")),
+                ]),
+            ),
+        ];
+
+        let transcript_unifier = merge_catalogs(&catalogs);
+        assert_eq!(transcript_unifier.unified_signatures().len(), 3);
+
+        let temp_dir = tempdir().unwrap();
+        let translation_path = temp_dir.path().join(TRANSLATION_FILE_NAME);
+        write_translation_table(&translation_path, &catalogs, &transcript_unifier).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&translation_path)
+                .unwrap()
+                .lines()
+                .collect::<Vec<&str>>(),
+            [
+                TRANSLATION_HEADER,
+                "site_1.tsv\ttuni_0\ttuni_0",
+                "site_1.tsv\ttuni_1\ttuni_2",
+                "site_2.tsv\ttuni_1\ttuni_0",
+                "site_2.tsv\ttuni_0\ttuni_1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_catalog_malformed() {
+        let temp_dir = tempdir().unwrap();
+        let catalog_path = temp_dir.path().join(CATALOG_FILE_NAME);
+        fs::write(&catalog_path, "tuni_0\tchr1\t-\n").unwrap();
+
+        assert!(read_catalog(&catalog_path)
+            .is_err_and(|e| e.to_string().contains("must have 5 tab-separated fields")));
+    }
+}
@@ -0,0 +1,100 @@
+//! Normalise chromosome names, so that transcripts from GTF/GFFs using
+//! different naming conventions (e.g. "chr1" vs "1") can be unified.
+
+use clap::ValueEnum;
+use std::{collections::HashMap, sync::Arc};
+
+/// Built-in chromosome naming conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChrStyle {
+    /// "chr1", "chrX", "chrM".
+    Ucsc,
+
+    /// "1", "X", "MT".
+    Ensembl,
+}
+
+/// Maps chromosome names onto a single naming convention.
+///
+/// User-supplied aliases take priority over the built-in `ChrStyle`.
+#[derive(Debug, Default)]
+pub struct ChrAliases {
+    /// Built-in naming convention to convert to.
+    style: Option<ChrStyle>,
+
+    /// Link each user-supplied alias to its normalised chromosome name.
+    aliases: HashMap<Arc<str>, Arc<str>>,
+}
+
+impl ChrAliases {
+    /// Create `ChrAliases`.
+    pub fn from(style: Option<ChrStyle>, aliases: HashMap<Arc<str>, Arc<str>>) -> ChrAliases {
+        ChrAliases { style, aliases }
+    }
+
+    /// Normalise a chromosome name.
+    ///
+    /// Returns the original name if it has no alias.
+    pub fn normalise(&self, chr: Arc<str>) -> Arc<str> {
+        if let Some(normalised) = self.aliases.get(&chr) {
+            return Arc::clone(normalised);
+        }
+
+        match self.style {
+            Some(ChrStyle::Ucsc) if chr.as_ref() == "MT" => Arc::from("chrM"),
+            Some(ChrStyle::Ucsc) if is_primary_chr(&chr) => Arc::from(format!("chr{}", chr)),
+            Some(ChrStyle::Ensembl) if chr.as_ref() == "chrM" => Arc::from("MT"),
+            Some(ChrStyle::Ensembl) => match chr.strip_prefix("chr") {
+                Some(stripped) if is_primary_chr(stripped) => Arc::from(stripped),
+                _ => chr,
+            },
+            _ => chr,
+        }
+    }
+}
+
+/// Returns true if the chromosome is an autosome or sex chromosome without a
+/// "chr" prefix (e.g. "1", "X"), otherwise false.
+///
+/// Other contigs (e.g. scaffolds) are named differently between UCSC and
+/// Ensembl, so require user-supplied aliases.
+fn is_primary_chr(chr: &str) -> bool {
+    chr == "X" || chr == "Y" || (!chr.is_empty() && chr.bytes().all(|x| x.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, "1", "1")]
+    #[case(Some(ChrStyle::Ucsc), "1", "chr1")]
+    #[case(Some(ChrStyle::Ucsc), "chr1", "chr1")]
+    #[case(Some(ChrStyle::Ucsc), "X", "chrX")]
+    #[case(Some(ChrStyle::Ucsc), "MT", "chrM")]
+    #[case(Some(ChrStyle::Ucsc), "KI270728.1", "KI270728.1")]
+    #[case(Some(ChrStyle::Ensembl), "chr1", "1")]
+    #[case(Some(ChrStyle::Ensembl), "chrM", "MT")]
+    #[case(Some(ChrStyle::Ensembl), "chrUn_KI270728v1", "chrUn_KI270728v1")]
+    #[case(Some(ChrStyle::Ensembl), "2", "2")]
+    fn test_normalise(#[case] style: Option<ChrStyle>, #[case] chr: &str, #[case] expected: &str) {
+        let chr_aliases = ChrAliases::from(style, HashMap::new());
+
+        assert_eq!(chr_aliases.normalise(Arc::from(chr)).as_ref(), expected);
+    }
+
+    #[test]
+    fn test_normalise_user_aliases() {
+        let chr_aliases = ChrAliases::from(
+            Some(ChrStyle::Ucsc),
+            HashMap::from([(Arc::from("KI270728.1"), Arc::from("chrUn_KI270728v1"))]),
+        );
+
+        assert_eq!(
+            chr_aliases.normalise(Arc::from("KI270728.1")).as_ref(),
+            "chrUn_KI270728v1"
+        );
+        assert_eq!(chr_aliases.normalise(Arc::from("1")).as_ref(), "chr1");
+    }
+}
@@ -1,16 +1,37 @@
 //! Module containing cli that parses and checks input arguments.
 
-use crate::error::CliError;
-use clap::{ArgAction, Parser};
-use std::{fs, fs::File, path::PathBuf};
+use crate::cli_error::CliError;
+use crate::validate::ValidateFormat;
+use clap::{ArgAction, Args, Parser, Subcommand};
+use std::{
+    collections::HashMap,
+    fs,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tuni::{
+    Aggregate, AttributeFilter, ChrAliases, ChrStyle, CodingFilter, FilterAction, LocusConflict,
+    ReadOptions, Region, RegionFilter, StructureFilter, UnifyOptions, WarningCategory,
+};
 
 /// Parse and check input arguments.
+// Without a subcommand, tuni unifies the GTF/GFFs provided, as `tuni unify`.
 #[derive(Parser)]
-#[command(version, about = "tuni: Unify transcripts across different samples")]
+#[command(
+    version,
+    about = "tuni: Unify transcripts across different samples",
+    args_conflicts_with_subcommands = true,
+    args_override_self = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// A text file containing GTF/GFF paths.
     #[arg(short, long, value_name = "*.txt", required = true)]
-    pub gtf_gff_path: PathBuf,
+    pub gtf_gff_path: Option<PathBuf>,
 
     /// Directory where outputted GTF/GFFs will be stored.
     #[arg(
@@ -20,18 +41,528 @@ pub struct Cli {
         required = true,
         value_parser = Cli::parse_output_dir
     )]
-    pub output_dir: PathBuf,
+    pub output_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub read_args: ReadArgs,
+
+    #[command(flatten)]
+    pub filter_args: FilterArgs,
 
-    /// Print log messages.
+    #[command(flatten)]
+    pub output_args: OutputArgs,
+
+    #[command(flatten)]
+    pub warning_args: WarningArgs,
+
+    #[command(flatten)]
+    pub global_args: GlobalArgs,
+}
+
+/// Arguments shared by every subcommand.
+#[derive(Args, Clone)]
+pub struct GlobalArgs {
+    /// Print log messages and the run summary table.
     #[arg(
         short,
         long,
+        global = true,
         action = ArgAction::SetTrue,
     )]
     pub verbose: bool,
+
+    /// Number of threads used to read and write GTF/GFFs, 0 uses every available
+    /// core.
+    #[arg(long, value_name = "N", global = true, default_value_t = 1)]
+    pub threads: usize,
+
+    /// Approximate memory (e.g. "512M", "4G") used to hold transcripts while
+    /// unifying, spilling the rest to temporary files in the output directory.
+    /// By default, every transcript is held in memory.
+    #[arg(long, value_name = "SIZE", global = true, value_parser = Cli::parse_memory)]
+    pub max_memory: Option<usize>,
+
+    /// Directory (e.g. on local disk) in which the lines of each GTF/GFF are
+    /// cached while unifying, so that GTF/GFFs are only read once. By default,
+    /// GTF/GFFs are read again when writing the unified GTF/GFFs.
+    #[arg(long, value_name = "/cache/dir/", global = true, value_parser = Cli::parse_output_dir)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// A TOML file of default argument values.
+    #[arg(long, value_name = "tuni.toml", global = true)]
+    pub config: Option<PathBuf>,
+}
+
+/// Subcommands supported by tuni.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Unify transcripts across GTF/GFFs, the default without a subcommand.
+    Unify(UnifyArgs),
+
+    /// Annotate new GTF/GFFs using the unified IDs of an existing catalog.
+    Annotate(AnnotateArgs),
+
+    /// Merge catalogs produced by separate runs of tuni.
+    MergeCatalogs(MergeCatalogsArgs),
+
+    /// Merge the outputs of runs over disjoint contigs (see --shard-contigs)
+    /// into one set of unified IDs.
+    MergeShards(MergeShardsArgs),
+
+    /// Translate salmon, kallisto or RSEM quantifications to unified IDs.
+    Quant(QuantArgs),
+
+    /// Check GTF/GFFs for problems without unifying.
+    ///
+    /// Exits with 0 if no problems are found, 2 if only warnings are found and
+    /// 3 if any errors are found.
+    Validate(ValidateArgs),
+}
+
+/// Arguments of the unify subcommand.
+#[derive(Args)]
+pub struct UnifyArgs {
+    /// A text file containing GTF/GFF paths.
+    #[arg(short, long, value_name = "*.txt", required = true)]
+    pub gtf_gff_path: PathBuf,
+
+    /// Directory where outputted GTF/GFFs will be stored.
+    #[arg(
+        short,
+        long,
+        value_name = "/output/dir/",
+        required = true,
+        value_parser = Cli::parse_output_dir
+    )]
+    pub output_dir: PathBuf,
+
+    #[command(flatten)]
+    pub read_args: ReadArgs,
+
+    #[command(flatten)]
+    pub filter_args: FilterArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs,
+
+    #[command(flatten)]
+    pub warning_args: WarningArgs,
+}
+
+/// Arguments of the annotate subcommand.
+#[derive(Args)]
+pub struct AnnotateArgs {
+    /// Catalog produced by a previous run of tuni, updated with new transcripts.
+    #[arg(short, long, value_name = "tuni_catalog.tsv", required = true)]
+    pub catalog: PathBuf,
+
+    /// A text file containing paths of the new GTF/GFFs.
+    #[arg(short, long, value_name = "*.txt", required = true)]
+    pub gtf_gff_path: PathBuf,
+
+    /// Directory where outputted GTF/GFFs will be stored.
+    #[arg(
+        short,
+        long,
+        value_name = "/output/dir/",
+        required = true,
+        value_parser = Cli::parse_output_dir
+    )]
+    pub output_dir: PathBuf,
+
+    #[command(flatten)]
+    pub read_args: ReadArgs,
+
+    #[command(flatten)]
+    pub filter_args: FilterArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs,
+
+    #[command(flatten)]
+    pub warning_args: WarningArgs,
+}
+
+/// Arguments of the merge-catalogs subcommand.
+#[derive(Args)]
+pub struct MergeCatalogsArgs {
+    /// Two or more catalogs produced by separate runs of tuni.
+    #[arg(short, long, value_name = "tuni_catalog.tsv", required = true, num_args = 2..)]
+    pub catalogs: Vec<PathBuf>,
+
+    /// Directory where the merged catalog and translation table will be stored.
+    #[arg(
+        short,
+        long,
+        value_name = "/output/dir/",
+        required = true,
+        value_parser = Cli::parse_output_dir
+    )]
+    pub output_dir: PathBuf,
+}
+
+/// Arguments of the merge-shards subcommand.
+#[derive(Args)]
+pub struct MergeShardsArgs {
+    /// Two or more output directories of tuni runs over disjoint contigs.
+    #[arg(short, long, value_name = "/shard/dir/", required = true, num_args = 2..)]
+    pub shards: Vec<PathBuf>,
+
+    /// Directory where the merged GTF/GFFs, catalog, side outputs, run summary
+    /// and translation table will be stored.
+    #[arg(
+        short,
+        long,
+        value_name = "/output/dir/",
+        required = true,
+        value_parser = Cli::parse_output_dir
+    )]
+    pub output_dir: PathBuf,
+}
+
+/// Arguments of the quant subcommand.
+#[derive(Args)]
+pub struct QuantArgs {
+    /// A text file containing the GTF/GFF paths each sample was quantified against.
+    #[arg(short, long, value_name = "*.txt", required = true)]
+    pub gtf_gff_path: PathBuf,
+
+    /// A text file containing quantification paths, in the same order as the
+    /// GTF/GFF paths.
+    #[arg(short, long, value_name = "*.txt", required = true)]
+    pub quant_path: PathBuf,
+
+    /// Catalog produced by a previous run of tuni, used to reuse its unified IDs.
+    #[arg(short, long, value_name = "tuni_catalog.tsv")]
+    pub catalog: Option<PathBuf>,
+
+    /// Directory where the count and TPM matrices will be stored.
+    #[arg(
+        short,
+        long,
+        value_name = "/output/dir/",
+        required = true,
+        value_parser = Cli::parse_output_dir
+    )]
+    pub output_dir: PathBuf,
+
+    /// How values are combined when transcripts from one sample share a tuni_id.
+    #[arg(long, value_enum, default_value_t = Aggregate::Sum)]
+    pub aggregate: Aggregate,
+
+    #[command(flatten)]
+    pub read_args: ReadArgs,
+}
+
+/// Arguments of the validate subcommand.
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// A text file containing GTF/GFF paths.
+    #[arg(short, long, value_name = "*.txt", required = true)]
+    pub gtf_gff_path: PathBuf,
+
+    /// Format in which problems are printed.
+    #[arg(long, value_enum, default_value_t = ValidateFormat::Text)]
+    pub format: ValidateFormat,
+}
+
+/// Arguments that control how transcripts are read from GTF/GFFs.
+#[derive(Args)]
+pub struct ReadArgs {
+    /// Convert chromosome names to a built-in naming convention before
+    /// unifying (e.g. "1" -> "chr1" for ucsc).
+    #[arg(long, value_enum, value_name = "STYLE")]
+    pub chr_style: Option<ChrStyle>,
+
+    /// A tab-separated file of chromosome aliases, with the alias in the first
+    /// column and the name to convert to in the second.
+    #[arg(long, value_name = "aliases.tsv")]
+    pub chr_aliases: Option<PathBuf>,
+
+    /// How transcripts with exon/CDS lines on more than one chromosome or
+    /// strand are handled.
+    #[arg(long, value_enum, default_value_t = LocusConflict::Flag)]
+    pub locus_conflict: LocusConflict,
+
+    /// Only unify transcripts overlapping these regions, in the format
+    /// chr:start-end (1-based, inclusive).
+    #[arg(long, value_name = "CHR:START-END", num_args = 1.., value_parser = Cli::parse_region)]
+    pub region: Option<Vec<Region>>,
+
+    /// Only unify transcripts overlapping the regions of a BED file.
+    #[arg(long, value_name = "regions.bed")]
+    pub regions_bed: Option<PathBuf>,
+
+    /// Only read and write lines on these contigs. Runs over disjoint contigs
+    /// can be combined with merge-shards.
+    #[arg(long, value_name = "CONTIG", num_args = 1.., value_delimiter = ',')]
+    pub shard_contigs: Option<Vec<String>>,
+
+    /// Only unify transcripts on these contigs.
+    #[arg(long, value_name = "CONTIG", num_args = 1.., value_delimiter = ',')]
+    pub include_contigs: Option<Vec<String>>,
+
+    /// Do not unify transcripts on these contigs (e.g. chrM).
+    #[arg(long, value_name = "CONTIG", num_args = 1.., value_delimiter = ',')]
+    pub exclude_contigs: Option<Vec<String>>,
+}
+
+impl ReadArgs {
+    /// Create `ReadOptions` from the arguments.
+    ///
+    /// # Errors
+    ///
+    /// See [`Cli::parse_chr_aliases`] and [`tuni::read_regions_bed`].
+    pub fn read_options(&self) -> Result<ReadOptions, CliError> {
+        let aliases = match &self.chr_aliases {
+            Some(chr_aliases) => Cli::parse_chr_aliases(chr_aliases)?,
+            None => HashMap::new(),
+        };
+
+        let chr_aliases = ChrAliases::from(self.chr_style, aliases);
+        let region_filter = self.region_filter(&chr_aliases)?;
+        let contigs = self.shard_contigs.as_ref().map(|x| {
+            x.iter()
+                .map(|x| chr_aliases.normalise(Arc::from(x.as_str())))
+                .collect()
+        });
+
+        Ok(ReadOptions {
+            chr_aliases,
+            locus_conflict: self.locus_conflict,
+            contigs,
+            line_cache: None,
+            region_filter,
+            structure_filter: StructureFilter::default(),
+            attribute_filter: None,
+        })
+    }
+
+    /// Create `RegionFilter` from the region and contig arguments,
+    /// converting chromosome names with `chr_aliases`.
+    ///
+    /// # Errors
+    ///
+    /// See [`tuni::read_regions_bed`].
+    fn region_filter(&self, chr_aliases: &ChrAliases) -> Result<RegionFilter, CliError> {
+        let to_contigs = |x: &Vec<String>| {
+            x.iter()
+                .map(|x| chr_aliases.normalise(Arc::from(x.as_str())))
+                .collect::<Vec<Arc<str>>>()
+        };
+        let normalise_regions = |regions: Vec<Region>| {
+            regions.into_iter().map(|x| Region {
+                chr: chr_aliases.normalise(x.chr),
+                ..x
+            })
+        };
+        let mut region_filter = RegionFilter::new();
+
+        if let Some(include_contigs) = &self.include_contigs {
+            region_filter = region_filter.with_include_contigs(to_contigs(include_contigs));
+        }
+        if let Some(exclude_contigs) = &self.exclude_contigs {
+            region_filter = region_filter.with_exclude_contigs(to_contigs(exclude_contigs));
+        }
+        if let Some(regions) = &self.region {
+            region_filter = region_filter.with_regions(normalise_regions(regions.clone()));
+        }
+        if let Some(regions_bed) = &self.regions_bed {
+            let regions = tuni::read_regions_bed(regions_bed)?;
+            region_filter = region_filter.with_regions(normalise_regions(regions));
+        }
+
+        Ok(region_filter)
+    }
+}
+
+/// Arguments that filter which transcripts are unified.
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Minimum number of samples a transcript must be found in to be unified.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub min_samples: usize,
+
+    /// Minimum fraction of samples a transcript must be found in to be unified.
+    #[arg(
+        long,
+        value_name = "F",
+        default_value_t = 0.0,
+        value_parser = Cli::parse_fraction
+    )]
+    pub min_fraction: f64,
+
+    /// A tab-separated file with a header, containing GTF/GFF paths in the first
+    /// column and sample metadata in the remaining columns.
+    #[arg(long, value_name = "manifest.tsv", requires = "group_column")]
+    pub manifest: Option<PathBuf>,
+
+    /// Manifest column that assigns samples to groups (e.g. tissue).
+    #[arg(long, value_name = "COLUMN", requires = "manifest")]
+    pub group_column: Option<String>,
+
+    /// Minimum fraction of the samples in any one group a transcript must be
+    /// found in to be unified.
+    #[arg(
+        long,
+        value_name = "F",
+        requires = "group_column",
+        value_parser = Cli::parse_fraction
+    )]
+    pub min_group_fraction: Option<f64>,
+
+    /// Only unify one transcript (the first alphabetically) of each set of
+    /// transcripts within a sample that share the same structure.
+    #[arg(long)]
+    pub collapse_redundant: bool,
+
+    /// Minimum total length of a transcript's exons for it to be unified.
+    #[arg(long, value_name = "N")]
+    pub min_spliced_length: Option<u64>,
+
+    /// Minimum number of exons of a transcript for it to be unified.
+    #[arg(long, value_name = "N")]
+    pub min_exons: Option<usize>,
+
+    /// Maximum number of exons of a transcript for it to be unified.
+    #[arg(long, value_name = "N")]
+    pub max_exons: Option<usize>,
+
+    /// Minimum length of every intron of a transcript for it to be unified.
+    #[arg(long, value_name = "N")]
+    pub min_intron_length: Option<u64>,
+
+    /// Only unify transcripts with CDS regions.
+    #[arg(long, conflicts_with = "noncoding_only")]
+    pub coding_only: bool,
+
+    /// Only unify transcripts without CDS regions.
+    #[arg(long)]
+    pub noncoding_only: bool,
+
+    /// Only unify transcripts whose attributes pass an expression, e.g.
+    /// 'TPM >= 1 && transcript_biotype != "retained_intron"'.
+    #[arg(long, value_name = "EXPRESSION", value_parser = Cli::parse_attribute_filter)]
+    pub filter: Option<AttributeFilter>,
+
+    /// Whether lines of filtered transcripts are tagged or dropped.
+    #[arg(long, value_enum, default_value_t = FilterAction::Tag)]
+    pub filter_action: FilterAction,
+}
+
+impl FilterArgs {
+    /// Parse the manifest, if provided, linking each sample to its group.
+    ///
+    /// Returns an empty map if no manifest is provided.
+    ///
+    /// # Errors
+    ///
+    /// See [`Cli::parse_manifest`].
+    pub fn sample_groups(&self) -> Result<HashMap<Arc<str>, Arc<str>>, CliError> {
+        match (&self.manifest, &self.group_column) {
+            (Some(manifest), Some(group_column)) => Cli::parse_manifest(manifest, group_column),
+            _ => Ok(HashMap::new()),
+        }
+    }
+
+    /// Create `StructureFilter` from the structure arguments.
+    pub fn structure_filter(&self) -> StructureFilter {
+        let coding = match (self.coding_only, self.noncoding_only) {
+            (true, _) => CodingFilter::Coding,
+            (_, true) => CodingFilter::Noncoding,
+            _ => CodingFilter::All,
+        };
+
+        StructureFilter {
+            min_spliced_length: self.min_spliced_length,
+            min_exons: self.min_exons,
+            max_exons: self.max_exons,
+            coding,
+            min_intron_length: self.min_intron_length,
+        }
+    }
+
+    /// Create `UnifyOptions` from the arguments and `read_options`.
+    ///
+    /// # Errors
+    ///
+    /// See [`Cli::parse_manifest`].
+    pub fn unify_options(&self, read_options: ReadOptions) -> Result<UnifyOptions, CliError> {
+        Ok(UnifyOptions::builder()
+            .chr_aliases(read_options.chr_aliases)
+            .locus_conflict(read_options.locus_conflict)
+            .contigs(read_options.contigs)
+            .line_cache(read_options.line_cache)
+            .region_filter(read_options.region_filter)
+            .structure_filter(self.structure_filter())
+            .attribute_filter(self.filter.clone())
+            .min_samples(self.min_samples)
+            .min_fraction(self.min_fraction)
+            .min_group_fraction(self.min_group_fraction)
+            .sample_groups(self.sample_groups()?)
+            .collapse_redundant(self.collapse_redundant)
+            .build())
+    }
+}
+
+/// Arguments that control additional outputs.
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Numeric attribute of "transcript" lines (e.g. "TPM") used to write a
+    /// tuni_id × sample expression matrix.
+    #[arg(long, value_name = "ATTRIBUTE")]
+    pub expression_attribute: Option<String>,
+
+    /// How expression values are combined when transcripts from one sample
+    /// share a tuni_id.
+    #[arg(long, value_enum, default_value_t = Aggregate::Sum)]
+    pub expression_aggregate: Aggregate,
+
+    /// Write a tuni_id to gene table for tximport/tximeta.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub tx2gene: bool,
+
+    /// Also write a tx2gene table including the original sample transcript IDs.
+    #[arg(long, action = ArgAction::SetTrue, requires = "tx2gene")]
+    pub tx2gene_sample_ids: bool,
+
+    /// Attribute holding the gene of each transcript.
+    #[arg(long, value_name = "ATTRIBUTE", default_value = "gene_id")]
+    pub gene_attribute: String,
+}
+
+/// Arguments that control how warnings are reported.
+#[derive(Args)]
+pub struct WarningArgs {
+    /// Write every warning to a tab-separated file, rather than only logging
+    /// counts and examples. Without any warnings, only the header is written.
+    #[arg(long, value_name = "warnings.tsv")]
+    pub warnings_file: Option<PathBuf>,
+
+    /// Exit with an error if any warnings of the given categories are found,
+    /// or of any category if none are given.
+    #[arg(long, value_name = "CATEGORY", value_enum, num_args = 0.., value_delimiter = ',')]
+    pub strict: Option<Vec<WarningCategory>>,
 }
 
 impl Cli {
+    /// Obtain the subcommand to run, treating top-level arguments as the unify
+    /// subcommand.
+    pub fn into_command(self) -> Command {
+        match self.command {
+            Some(command) => command,
+            // Without a subcommand, clap requires gtf_gff_path and output_dir.
+            None => Command::Unify(UnifyArgs {
+                gtf_gff_path: self.gtf_gff_path.unwrap(),
+                output_dir: self.output_dir.unwrap(),
+                read_args: self.read_args,
+                filter_args: self.filter_args,
+                output_args: self.output_args,
+                warning_args: self.warning_args,
+            }),
+        }
+    }
+
     /// Parse file containing GTF/GFFs paths.
     ///
     /// Returns GTF/GFF paths on success, otherwise returns an error.
@@ -68,9 +599,9 @@ impl Cli {
         for gtf_gff_path in &gtf_gff_paths {
             // Make sure all GTF/GFFs have the same extension.
             if !gtf_gff_path.is_file()
-                || !gtf_gff_path
+                || gtf_gff_path
                     .extension()
-                    .is_some_and(|x| x == gtf_gff_extension)
+                    .is_none_or(|x| x != gtf_gff_extension)
             {
                 return Err(CliError::GtfGffParseError(gtf_gff_path.clone()));
             }
@@ -85,6 +616,115 @@ impl Cli {
         ))
     }
 
+    /// Parse file containing quantification paths.
+    ///
+    /// Returns quantification paths on success, otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileReadError`](CliError::FileReadError) if the file
+    /// containing quantifications or any of the quantifications cannot be read.
+    ///
+    /// Returns [`PathCountError`](CliError::PathCountError) if the number of
+    /// quantifications differs from `n_gtf_gffs`.
+    pub fn parse_quant_paths(
+        quant_path: PathBuf,
+        n_gtf_gffs: usize,
+    ) -> Result<Vec<PathBuf>, CliError> {
+        let quant_paths = fs::read_to_string(&quant_path)
+            .map_err(|_| CliError::FileReadError(quant_path.clone()))?
+            .lines()
+            .map(PathBuf::from)
+            .collect::<Vec<PathBuf>>();
+
+        if quant_paths.len() != n_gtf_gffs {
+            return Err(CliError::PathCountError(n_gtf_gffs, quant_paths.len()));
+        }
+
+        for quant_path in &quant_paths {
+            File::open(quant_path).map_err(|_| CliError::FileReadError(quant_path.clone()))?;
+        }
+
+        Ok(quant_paths)
+    }
+
+    /// Parse manifest of sample metadata.
+    ///
+    /// Returns a map linking each GTF/GFF file name to the value of the
+    /// `group_column`, otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileReadError`](CliError::FileReadError) if the manifest
+    /// cannot be read.
+    ///
+    /// Returns [`FileEmptyError`](CliError::FileEmptyError) if the manifest is
+    /// empty.
+    ///
+    /// Returns [`MissingColumnError`](CliError::MissingColumnError) if the
+    /// manifest header does not contain `group_column`.
+    ///
+    /// Returns [`ManifestParseError`](CliError::ManifestParseError) if any
+    /// line has fewer fields than the header.
+    pub fn parse_manifest(
+        manifest_path: &Path,
+        group_column: &str,
+    ) -> Result<HashMap<Arc<str>, Arc<str>>, CliError> {
+        let manifest = fs::read_to_string(manifest_path)
+            .map_err(|_| CliError::FileReadError(manifest_path.to_path_buf()))?;
+        let mut lines = manifest.lines();
+
+        let header = lines
+            .next()
+            .ok_or(CliError::FileEmptyError(manifest_path.to_path_buf()))?;
+        let group_index = header
+            .split('\t')
+            .position(|x| x == group_column)
+            .ok_or(CliError::MissingColumnError(group_column.to_string()))?;
+
+        let mut sample_groups = HashMap::new();
+
+        for line in lines.filter(|x| !x.is_empty()) {
+            let line_split = line.split('\t').collect::<Vec<&str>>();
+            let group = line_split
+                .get(group_index)
+                .ok_or(CliError::ManifestParseError(line.to_string()))?;
+            let file_name = Path::new(line_split[0])
+                .file_name()
+                .ok_or(CliError::ManifestParseError(line.to_string()))?;
+
+            sample_groups.insert(Arc::from(file_name.to_string_lossy()), Arc::from(*group));
+        }
+
+        Ok(sample_groups)
+    }
+
+    /// Parse file of chromosome aliases.
+    ///
+    /// Returns a map linking each alias to the chromosome name to convert to,
+    /// otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileReadError`](CliError::FileReadError) if the file cannot
+    /// be read.
+    ///
+    /// Returns [`AliasParseError`](CliError::AliasParseError) if any line does
+    /// not contain 2 tab-separated fields.
+    pub fn parse_chr_aliases(
+        chr_aliases_path: &Path,
+    ) -> Result<HashMap<Arc<str>, Arc<str>>, CliError> {
+        fs::read_to_string(chr_aliases_path)
+            .map_err(|_| CliError::FileReadError(chr_aliases_path.to_path_buf()))?
+            .lines()
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .map(|line| match line.split('\t').collect::<Vec<&str>>()[..] {
+                [alias, chr] => Ok((Arc::from(alias), Arc::from(chr))),
+                _ => Err(CliError::AliasParseError(line.to_string())),
+            })
+            .collect()
+    }
+
     /// Parse output directory.
     ///
     /// Returns output directory path on success, otherwise returns an error.
@@ -100,6 +740,67 @@ impl Cli {
         };
         Ok(output_dir)
     }
+
+    /// Parse fraction.
+    ///
+    /// Returns fraction on success, otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotAFractionError`](CliError::NotAFractionError) if the value
+    /// is not a number between 0 and 1.
+    fn parse_fraction(s: &str) -> Result<f64, CliError> {
+        s.parse::<f64>()
+            .ok()
+            .filter(|x| (0.0..=1.0).contains(x))
+            .ok_or(CliError::NotAFractionError(s.to_string()))
+    }
+
+    /// Parse region in the format "chr:start-end".
+    ///
+    /// Returns region on success, otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegionParseError`](CliError::RegionParseError) if the region
+    /// is malformed or its start is after its end.
+    fn parse_region(s: &str) -> Result<Region, CliError> {
+        Region::parse(s).ok_or(CliError::RegionParseError(s.to_string()))
+    }
+
+    /// Parse an attribute filter expression, see [`AttributeFilter::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AttributeFilterError`](CliError::AttributeFilterError) if
+    /// the expression is malformed.
+    fn parse_attribute_filter(s: &str) -> Result<AttributeFilter, CliError> {
+        Ok(AttributeFilter::parse(s)?)
+    }
+
+    /// Parse memory size, either in bytes or with a "K", "M" or "G" suffix
+    /// (powers of 1024).
+    ///
+    /// Returns the number of bytes on success, otherwise returns an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotAMemorySizeError`](CliError::NotAMemorySizeError) if the
+    /// value is not a positive integer with an optional suffix.
+    fn parse_memory(s: &str) -> Result<usize, CliError> {
+        let (size, multiplier) = match s.to_ascii_uppercase().trim_end_matches('B') {
+            x if x.ends_with('K') => (x[..x.len() - 1].to_string(), 1 << 10),
+            x if x.ends_with('M') => (x[..x.len() - 1].to_string(), 1 << 20),
+            x if x.ends_with('G') => (x[..x.len() - 1].to_string(), 1 << 30),
+            x => (x.to_string(), 1),
+        };
+
+        size.parse::<usize>()
+            .ok()
+            .and_then(|x| x.checked_mul(multiplier))
+            .filter(|x| *x > 0)
+            .ok_or(CliError::NotAMemorySizeError(s.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -147,4 +848,168 @@ mod tests {
         let result = Cli::parse_output_dir("tests/data/unit/");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_quant_paths() {
+        let quant_path = PathBuf::from("tests/data/integration/quant/quant_paths.txt");
+
+        assert_eq!(
+            Cli::parse_quant_paths(quant_path.clone(), 2).unwrap().len(),
+            2
+        );
+        assert!(Cli::parse_quant_paths(quant_path, 3)
+            .is_err_and(|e| e.to_string().contains("Expected 3 paths, found 2")));
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest_path = PathBuf::from("tests/data/unit/manifest.tsv");
+
+        assert_eq!(
+            Cli::parse_manifest(&manifest_path, "tissue").unwrap(),
+            HashMap::from([
+                (Arc::from("sample_1.gtf"), Arc::from("liver")),
+                (Arc::from("sample_2.gtf"), Arc::from("brain")),
+            ])
+        );
+
+        let result = Cli::parse_manifest(&manifest_path, "condition");
+        assert!(result.is_err_and(|e| e.to_string().contains("No column named \"condition\"")));
+    }
+
+    #[test]
+    fn test_parse_chr_aliases() {
+        assert_eq!(
+            Cli::parse_chr_aliases(&PathBuf::from("tests/data/unit/chr_aliases.tsv")).unwrap(),
+            HashMap::from([
+                (Arc::from("1"), Arc::from("chr1")),
+                (Arc::from("MT"), Arc::from("chrM")),
+            ])
+        );
+        assert!(
+            Cli::parse_chr_aliases(&PathBuf::from("tests/data/unit/sample_1.gtf"))
+                .is_err_and(|e| e.to_string().contains("2 tab-separated fields"))
+        );
+    }
+
+    #[test]
+    fn test_parse_fraction() {
+        assert_eq!(Cli::parse_fraction("0.5").unwrap(), 0.5);
+        assert!(Cli::parse_fraction("1.5")
+            .is_err_and(|e| e.to_string().contains("must be a number between 0 and 1")));
+        assert!(Cli::parse_fraction("a").is_err());
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(
+            Cli::parse_region("chr1:1-100").unwrap(),
+            Region::parse("chr1:1-100").unwrap()
+        );
+        assert!(Cli::parse_region("chr1:100-1")
+            .is_err_and(|e| e.to_string().contains("format chr:start-end")));
+    }
+
+    #[test]
+    fn test_parse_attribute_filter() {
+        assert_eq!(
+            Cli::parse_attribute_filter("TPM >= 1").unwrap(),
+            AttributeFilter::parse("TPM >= 1").unwrap()
+        );
+        assert!(Cli::parse_attribute_filter("TPM >=")
+            .is_err_and(|e| e.to_string().contains("Unable to parse filter")));
+    }
+
+    #[test]
+    fn test_parse_memory() {
+        assert_eq!(Cli::parse_memory("1024").unwrap(), 1024);
+        assert_eq!(Cli::parse_memory("512K").unwrap(), 512 << 10);
+        assert_eq!(Cli::parse_memory("4g").unwrap(), 4 << 30);
+        assert_eq!(Cli::parse_memory("2MB").unwrap(), 2 << 20);
+        assert!(Cli::parse_memory("0")
+            .is_err_and(|e| e.to_string().contains("must be a positive number of bytes")));
+        assert!(Cli::parse_memory("1T").is_err());
+        assert!(Cli::parse_memory("-1G").is_err());
+    }
+
+    /// Test that the annotate subcommand does not require top-level arguments.
+    #[test]
+    fn test_parse_annotate() {
+        let cli = Cli::try_parse_from([
+            "tuni",
+            "annotate",
+            "--catalog",
+            "tuni_catalog.tsv",
+            "--gtf-gff-path",
+            "tests/data/unit/gtf_paths.txt",
+            "--output-dir",
+            "tests/data/unit/",
+            "--verbose",
+        ])
+        .unwrap();
+
+        assert!(cli.global_args.verbose);
+        assert!(matches!(cli.command, Some(Command::Annotate(_))));
+
+        let result = Cli::try_parse_from(["tuni", "--gtf-gff-path", "gtf_paths.txt"]);
+        assert!(result.is_err());
+    }
+
+    // Without a subcommand, tuni behaves as the unify subcommand.
+    #[test]
+    fn test_parse_unify() {
+        let args = [
+            "--gtf-gff-path",
+            "gtf_paths.txt",
+            "--output-dir",
+            "tests/data/unit/",
+        ];
+
+        for subcommand in [None, Some("unify")] {
+            let cli = Cli::try_parse_from(
+                ["tuni"]
+                    .into_iter()
+                    .chain(subcommand)
+                    .chain(args)
+                    .chain(["--threads", "4"]),
+            )
+            .unwrap();
+
+            assert_eq!(cli.global_args.threads, 4);
+            match cli.into_command() {
+                Command::Unify(unify_args) => {
+                    assert_eq!(unify_args.gtf_gff_path, PathBuf::from("gtf_paths.txt"))
+                }
+                _ => panic!("Expected unify subcommand"),
+            }
+        }
+    }
+
+    // Structure arguments form a `StructureFilter`, and coding filters conflict.
+    #[test]
+    fn test_parse_structure_filter() {
+        let args = [
+            "tuni",
+            "--gtf-gff-path",
+            "gtf_paths.txt",
+            "--output-dir",
+            "tests/data/unit/",
+            "--min-exons",
+            "2",
+            "--coding-only",
+        ];
+
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(
+            cli.filter_args.structure_filter(),
+            StructureFilter {
+                min_exons: Some(2),
+                coding: CodingFilter::Coding,
+                ..Default::default()
+            }
+        );
+
+        let result = Cli::try_parse_from(args.into_iter().chain(["--noncoding-only"]));
+        assert!(result.is_err());
+    }
 }
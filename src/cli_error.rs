@@ -0,0 +1,172 @@
+//! Custom error types returned by the tuni binary.
+
+use std::path::PathBuf;
+use thiserror::Error;
+use tuni::error::{AttributeFilterError, RegionError};
+
+/// Errors resulting from cli parsing.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum CliError {
+    /// The file provided is unreadable.
+    #[error("FileReadError: Unable to read file {0:?}")]
+    FileReadError(PathBuf),
+
+    /// The file containing GTF/GFF paths is empty.
+    #[error("FileEmptyError: Provided file {0:?} is empty")]
+    FileEmptyError(PathBuf),
+
+    /// The GTF/GFFs include a file which is
+    /// 1. not readable, 2. does not have a ".gtf"/".gff" extension or
+    /// 3. has an extension distinct from the remaining GTF/GFFs.
+    #[error(
+        "GtfGffParseError: GTF/GFFs must be readable and all have the same extension ('.gtf' or '.gff'), found {0:?}"
+    )]
+    GtfGffParseError(PathBuf),
+
+    /// The path does not point to a directory (e.g. it is a file).
+    #[error("NotADirectoryError: output_dir must be an existing directory {0:?}")]
+    NotADirectoryError(PathBuf),
+
+    /// The number of paths differs from the number of GTF/GFFs.
+    #[error("PathCountError: Expected {0} paths, found {1}, one for each GTF/GFF")]
+    PathCountError(usize, usize),
+
+    /// The manifest header does not contain the requested column.
+    #[error("MissingColumnError: No column named {0:?} found in manifest")]
+    MissingColumnError(String),
+
+    /// The manifest line has fewer fields than the header or an invalid path.
+    #[error("ManifestParseError: Unable to parse manifest line {0:?}")]
+    ManifestParseError(String),
+
+    /// The chromosome alias line does not contain 2 tab-separated fields.
+    #[error(
+        "AliasParseError: Chromosome alias lines must have 2 tab-separated fields, found {0:?}"
+    )]
+    AliasParseError(String),
+
+    /// The value is not a number between 0 and 1.
+    #[error("NotAFractionError: Fraction must be a number between 0 and 1, found {0:?}")]
+    NotAFractionError(String),
+
+    /// The value is not a memory size.
+    #[error(
+        "NotAMemorySizeError: Memory size must be a positive number of bytes, optionally suffixed by K, M or G, found {0:?}"
+    )]
+    NotAMemorySizeError(String),
+
+    /// The value is not a region.
+    #[error("RegionParseError: Region must be in the format chr:start-end, found {0:?}")]
+    RegionParseError(String),
+
+    /// The regions could not be read.
+    #[error(transparent)]
+    RegionError(#[from] RegionError),
+
+    /// The attribute filter could not be parsed.
+    #[error(transparent)]
+    AttributeFilterError(#[from] AttributeFilterError),
+}
+
+/// Errors resulting from reading or writing configs.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    /// The config could not be read.
+    #[error("FileReadError: Unable to read config {0:?}")]
+    FileReadError(PathBuf),
+
+    /// The config is not valid TOML.
+    #[error("TomlParseError: Unable to parse config {0:?}: {1}")]
+    TomlParseError(PathBuf, String),
+
+    /// The config key is not an argument of tuni.
+    #[error("UnknownKeyError: Config key must be an argument of tuni, found {0:?}")]
+    UnknownKeyError(String),
+
+    /// The config value is a table, a nested array or, for a flag, not a boolean.
+    #[error("InvalidValueError: Invalid value for config key {0:?}")]
+    InvalidValueError(String),
+
+    /// The resolved config could not be written.
+    #[error("FileWriteError: Unable to write config {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from validating GTF/GFFs.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ValidateError {
+    /// The GTF/GFF or a line within it could not be read.
+    #[error("FileReadError: Unable to read {0:?}")]
+    FileReadError(PathBuf),
+
+    /// The validation results could not be written.
+    #[error("OutputWriteError: Unable to write validation results")]
+    OutputWriteError,
+}
+
+/// Errors resulting from writing the run summary.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum SummaryError {
+    /// The file could not be read.
+    #[error("FileReadError: Unable to read run summary {0:?}")]
+    FileReadError(PathBuf),
+
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create run summary {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write run summary {0:?}")]
+    FileWriteError(PathBuf),
+
+    /// The summary table could not be printed.
+    #[error("OutputWriteError: Unable to print run summary")]
+    OutputWriteError,
+}
+
+/// Errors resulting from merging the outputs of shards.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ShardError {
+    /// The shard directory could not be read.
+    #[error("DirReadError: Unable to read shard directory {0:?}")]
+    DirReadError(PathBuf),
+
+    /// The line from a shard's GTF/GFF could not be read.
+    #[error("LineReadError: Unable to read line in {0:?}")]
+    LineReadError(PathBuf),
+
+    /// The footer of a shard's GTF/GFF does not match its lines.
+    #[error("ShardLinesError: Line numbers in the footer do not match the lines of {0:?}")]
+    ShardLinesError(PathBuf),
+
+    /// The unified ID of a line is not found in its shard's catalog.
+    #[error(
+        "UnknownUnifiedIdError: Unified ID not found in the shard's catalog, found in line {0:?}"
+    )]
+    UnknownUnifiedIdError(String),
+
+    /// The line of a shard's table does not contain a unified ID.
+    #[error("MalformedLineError: Unified ID column not found in line {0:?}")]
+    MalformedLineError(String),
+
+    /// An output is found in some shards, but not this shard.
+    #[error("MissingOutputError: Output found in other shards is missing {0:?}")]
+    MissingOutputError(PathBuf),
+
+    /// The header of a shard's table differs from that of the first shard.
+    #[error("HeaderMismatchError: Header differs from the first shard in {0:?}")]
+    HeaderMismatchError(PathBuf),
+
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create merged output {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
@@ -0,0 +1,385 @@
+//! Read argument values from a TOML config file and record the resolved
+//! arguments of each run.
+//!
+//! Config keys are argument names in snake case (e.g. `min_samples`). Values
+//! from the config are only used for arguments not given on the command line,
+//! so that arguments given on the command line take priority.
+
+use crate::cli_error::ConfigError;
+use clap::{parser::ValueSource, ArgAction, ArgMatches, Command};
+use std::{ffi::OsString, fs, path::Path};
+use toml::{Table, Value};
+
+/// File name of the resolved config stored alongside the outputs.
+pub const CONFIG_FILE_NAME: &str = "tuni_config.toml";
+
+/// Arguments that are never read from or written to a config.
+const SKIPPED_ARGS: [&str; 3] = ["help", "version", "config"];
+
+/// Global arguments that take a value, used to find the subcommand before
+/// parsing.
+const GLOBAL_VALUE_ARGS: [&str; 4] = ["--config", "--threads", "--max-memory", "--cache-dir"];
+
+/// Find the config path given on the command line, if any.
+pub fn find_config_path(args: &[OsString]) -> Option<&Path> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        let arg = arg.to_str()?;
+        match arg.strip_prefix("--config=") {
+            Some(path) => Some(Path::new(path)),
+            None if arg == "--config" => args.get(i + 1).map(Path::new),
+            None => None,
+        }
+    })
+}
+
+/// Read a TOML config.
+///
+/// # Errors
+///
+/// Returns [`FileReadError`](ConfigError::FileReadError) if the config cannot
+/// be read.
+///
+/// Returns [`TomlParseError`](ConfigError::TomlParseError) if the config is
+/// not valid TOML.
+pub fn read_config(config_path: &Path) -> Result<Table, ConfigError> {
+    let config = fs::read_to_string(config_path)
+        .map_err(|_| ConfigError::FileReadError(config_path.to_path_buf()))?;
+
+    config
+        .parse::<Table>()
+        .map_err(|e| ConfigError::TomlParseError(config_path.to_path_buf(), e.to_string()))
+}
+
+/// Insert the values of `config` into the command line arguments, directly
+/// after the subcommand. Keys of arguments given on the command line are
+/// skipped, so that arguments given on the command line take priority.
+///
+/// Keys that are arguments of a different subcommand are skipped, allowing
+/// one config to be shared across subcommands.
+///
+/// # Errors
+///
+/// Returns [`UnknownKeyError`](ConfigError::UnknownKeyError) if a key is not
+/// an argument of any subcommand.
+///
+/// Returns [`InvalidValueError`](ConfigError::InvalidValueError) if a value is
+/// a table or a nested array.
+pub fn merge_config(
+    args: &[OsString],
+    config: &Table,
+    command: &mut Command,
+) -> Result<Vec<OsString>, ConfigError> {
+    // Parse the command line alone to find the arguments given, ignoring
+    // errors such as missing required arguments that the config may provide.
+    let command_line_matches = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok();
+    command.build();
+
+    let subcommand_index = find_subcommand_index(args, command);
+    let command_line_matches = match subcommand_index {
+        Some(_) => command_line_matches
+            .as_ref()
+            .and_then(|x| x.subcommand())
+            .map(|(_, x)| x),
+        None => command_line_matches.as_ref(),
+    };
+    let subcommand = match subcommand_index {
+        Some(i) => command
+            .find_subcommand(&args[i])
+            .expect("subcommand index should point to a subcommand"),
+        None => &*command,
+    };
+
+    let mut config_args = Vec::new();
+
+    for (key, value) in config {
+        let Some(arg) = subcommand
+            .get_arguments()
+            .find(|x| x.get_id() == key.as_str())
+            .filter(|_| !SKIPPED_ARGS.contains(&key.as_str()))
+        else {
+            if is_any_argument(command, key) {
+                continue;
+            }
+            return Err(ConfigError::UnknownKeyError(key.clone()));
+        };
+
+        if command_line_matches.is_some_and(|x| is_given(x, key)) {
+            continue;
+        }
+
+        let long = format!("--{}", arg.get_long().unwrap_or(key));
+        let invalid_value = || ConfigError::InvalidValueError(key.clone());
+
+        match (arg.get_action(), value) {
+            (ArgAction::SetTrue, Value::Boolean(true)) => config_args.push(OsString::from(long)),
+            (ArgAction::SetTrue, Value::Boolean(false)) => (),
+            (ArgAction::SetTrue, _) => return Err(invalid_value()),
+            (_, Value::Array(values)) => {
+                config_args.push(OsString::from(long));
+                for value in values {
+                    config_args.push(OsString::from(
+                        value_to_string(value).ok_or_else(invalid_value)?,
+                    ));
+                }
+            }
+            (_, value) => {
+                config_args.push(OsString::from(long));
+                config_args.push(OsString::from(
+                    value_to_string(value).ok_or_else(invalid_value)?,
+                ));
+            }
+        }
+    }
+
+    let insert_index = subcommand_index.map_or(1, |i| i + 1);
+    let mut merged_args = args[..insert_index].to_vec();
+    merged_args.extend(config_args);
+    merged_args.extend_from_slice(&args[insert_index..]);
+
+    Ok(merged_args)
+}
+
+/// Create a config containing the resolved value of every argument of the
+/// subcommand run, which reproduces the run when passed to `--config`.
+pub fn resolve_config(matches: &ArgMatches, command: &mut Command) -> Table {
+    command.build();
+
+    let (command, matches) = match matches.subcommand() {
+        Some((name, sub_matches)) => (
+            command
+                .find_subcommand(name)
+                .expect("matched subcommand should exist"),
+            sub_matches,
+        ),
+        None => (&*command, matches),
+    };
+
+    let mut config = Table::new();
+
+    for arg in command.get_arguments() {
+        let key = arg.get_id().as_str();
+        if SKIPPED_ARGS.contains(&key) {
+            continue;
+        }
+        let Some(raw_values) = matches.get_raw(key) else {
+            continue;
+        };
+        let values = raw_values
+            .map(|x| x.to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+
+        let value = match (arg.get_action(), values.as_slice()) {
+            (ArgAction::SetTrue, [value]) => Value::Boolean(value == "true"),
+            (ArgAction::Append, values) => {
+                Value::Array(values.iter().map(|x| string_to_value(x)).collect())
+            }
+            (_, [value]) => string_to_value(value),
+            (_, values) => Value::Array(values.iter().map(|x| string_to_value(x)).collect()),
+        };
+
+        config.insert(key.to_string(), value);
+    }
+
+    config
+}
+
+/// Write the resolved config.
+///
+/// # Errors
+///
+/// Returns [`FileWriteError`](ConfigError::FileWriteError) if the config
+/// cannot be written.
+pub fn write_config(config_path: &Path, config: &Table) -> Result<(), ConfigError> {
+    fs::write(config_path, config.to_string())
+        .map_err(|_| ConfigError::FileWriteError(config_path.to_path_buf()))
+}
+
+/// Find the position of the subcommand within the command line arguments.
+///
+/// Returns `None` if no subcommand is given.
+fn find_subcommand_index(args: &[OsString], command: &Command) -> Option<usize> {
+    let mut i = 1;
+    while let Some(arg) = args.get(i).and_then(|x| x.to_str()) {
+        if GLOBAL_VALUE_ARGS.contains(&arg) {
+            i += 2;
+            continue;
+        }
+        if arg.starts_with('-') {
+            i += 1;
+            continue;
+        }
+        return command.find_subcommand(arg).map(|_| i);
+    }
+    None
+}
+
+/// Returns true if the argument `key` is given on the command line, rather
+/// than taking its default value, otherwise false.
+fn is_given(matches: &ArgMatches, key: &str) -> bool {
+    matches.value_source(key) == Some(ValueSource::CommandLine)
+}
+
+/// Returns true if `key` is an argument of the command or any subcommand,
+/// otherwise false.
+fn is_any_argument(command: &Command, key: &str) -> bool {
+    command
+        .get_subcommands()
+        .chain([command])
+        .flat_map(|x| x.get_arguments())
+        .any(|x| x.get_id() == key)
+}
+
+/// Convert a TOML value to a command line value.
+///
+/// Returns `None` if the value is a table or an array.
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Integer(value) => Some(value.to_string()),
+        Value::Float(value) => Some(value.to_string()),
+        Value::Boolean(value) => Some(value.to_string()),
+        Value::Datetime(value) => Some(value.to_string()),
+        Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+/// Convert a command line value to a TOML value, keeping numbers numeric.
+fn string_to_value(value: &str) -> Value {
+    if let Ok(value) = value.parse::<i64>() {
+        return Value::Integer(value);
+    }
+    match value.parse::<f64>() {
+        Ok(float) if float.is_finite() => Value::Float(float),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_find_config_path() {
+        let args = os_args(&["tuni", "validate", "--config", "tuni.toml"]);
+        assert_eq!(find_config_path(&args), Some(Path::new("tuni.toml")));
+
+        let args = os_args(&["tuni", "--config=tuni.toml"]);
+        assert_eq!(find_config_path(&args), Some(Path::new("tuni.toml")));
+
+        assert_eq!(find_config_path(&os_args(&["tuni"])), None);
+    }
+
+    #[test]
+    fn test_merge_config() {
+        let config = "min_samples = 2\ntx2gene = true\ncatalogs = [\"a.tsv\", \"b.tsv\"]"
+            .parse::<Table>()
+            .unwrap();
+
+        // Config keys given on the command line are skipped.
+        let args = os_args(&["tuni", "unify", "--threads", "2", "--min-samples", "3"]);
+        assert_eq!(
+            merge_config(&args, &config, &mut Cli::command()).unwrap(),
+            os_args(&[
+                "tuni",
+                "unify",
+                "--tx2gene",
+                "--threads",
+                "2",
+                "--min-samples",
+                "3"
+            ])
+        );
+
+        // Arguments of other subcommands are skipped.
+        let args = os_args(&["tuni", "merge-catalogs"]);
+        assert_eq!(
+            merge_config(&args, &config, &mut Cli::command()).unwrap(),
+            os_args(&["tuni", "merge-catalogs", "--catalogs", "a.tsv", "b.tsv"])
+        );
+
+        let config = "min_sample = 2".parse::<Table>().unwrap();
+        assert!(matches!(
+            merge_config(&args, &config, &mut Cli::command()),
+            Err(ConfigError::UnknownKeyError(_))
+        ));
+    }
+
+    // Non-repeatable arguments given both in the config and on the command
+    // line, by long or (combined) short name, take the command line value.
+    #[test]
+    fn test_merge_config_conflict() {
+        let config = "output_dir = \"tests/\"\ngtf_gff_path = \"config.txt\"\ntx2gene = true\nfilter_action = \"drop\""
+            .parse::<Table>()
+            .unwrap();
+        let args = os_args(&[
+            "tuni",
+            "unify",
+            "-vo",
+            "tests/data/unit/",
+            "--gtf-gff-path=gtf_paths.txt",
+            "--tx2gene",
+            "--filter-action",
+            "tag",
+        ]);
+
+        let merged_args = merge_config(&args, &config, &mut Cli::command()).unwrap();
+        assert_eq!(merged_args, args);
+
+        let matches = Cli::command().try_get_matches_from(merged_args).unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+        assert_eq!(
+            matches.get_one::<PathBuf>("output_dir"),
+            Some(&PathBuf::from("tests/data/unit/"))
+        );
+        assert_eq!(
+            matches.get_one::<PathBuf>("gtf_gff_path"),
+            Some(&PathBuf::from("gtf_paths.txt"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_config() {
+        let matches = Cli::command().get_matches_from([
+            "tuni",
+            "--gtf-gff-path",
+            "gtf_paths.txt",
+            "--output-dir",
+            "tests/data/unit/",
+            "--min-fraction",
+            "0.5",
+        ]);
+        let config = resolve_config(&matches, &mut Cli::command());
+
+        assert_eq!(config["gtf_gff_path"].as_str(), Some("gtf_paths.txt"));
+        assert_eq!(config["min_samples"].as_integer(), Some(1));
+        assert_eq!(config["min_fraction"].as_float(), Some(0.5));
+        assert_eq!(config["tx2gene"].as_bool(), Some(false));
+        assert!(!config.contains_key("config"));
+
+        // The resolved config reproduces the run.
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join(CONFIG_FILE_NAME);
+        write_config(&config_path, &config).unwrap();
+
+        let args = merge_config(
+            &os_args(&["tuni"]),
+            &read_config(&config_path).unwrap(),
+            &mut Cli::command(),
+        )
+        .unwrap();
+        let matches = Cli::command().get_matches_from(args);
+        assert_eq!(resolve_config(&matches, &mut Cli::command()), config);
+    }
+}
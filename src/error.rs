@@ -3,31 +3,6 @@
 use std::path::PathBuf;
 use thiserror::Error;
 
-/// Errors resulting from cli parsing.
-#[allow(clippy::enum_variant_names)]
-#[derive(Error, Debug)]
-pub enum CliError {
-    /// The file provided is unreadable.
-    #[error("FileReadError: Unable to read file {0:?}")]
-    FileReadError(PathBuf),
-
-    /// The file containing GTF/GFF paths is empty.
-    #[error("FileEmptyError: Provided file {0:?} is empty")]
-    FileEmptyError(PathBuf),
-
-    /// The GTF/GFFs include a file which is
-    /// 1. not readable, 2. does not have a ".gtf"/".gff" extension or
-    /// 3. has an extension distinct from the remaining GTF/GFFs.
-    #[error(
-        "GtfGffParseError: GTF/GFFs must be readable and all have the same extension ('.gtf' or '.gff'), found {0:?}"
-    )]
-    GtfGffParseError(PathBuf),
-
-    /// The path does not point to a directory (e.g. it is a file).
-    #[error("NotADirectoryError: output_dir must be an existing directory {0:?}")]
-    NotADirectoryError(PathBuf),
-}
-
 /// Errors resulting from processing GTF/GFF lines.
 #[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug)]
@@ -47,6 +22,26 @@ pub enum GtfGffError {
     #[error("UnknownFeatureError: Feature must be 'exon' or 'CDS', found {0:?}.")]
     UnknownExtensionError(String),
 
+    /// The start or end of an exon/CDS line is not a non-negative integer.
+    #[error("InvalidCoordinateError: Start and end must be non-negative integers in line {0:?}")]
+    InvalidCoordinateError(String),
+
+    /// The transcript has exon/CDS lines on more than one chromosome or strand.
+    #[error("LocusConflictError: Transcript found on more than one chromosome or strand {0:?}")]
+    LocusConflictError(String),
+
+    /// The attribute used for expression values is not a number.
+    #[error("NonNumericAttributeError: Expression attribute must be numeric, found {0:?}")]
+    NonNumericAttributeError(String),
+
+    /// The GTF/GFF could not be opened.
+    #[error("FileOpenError: Unable to open GTF/GFF {0:?}")]
+    FileOpenError(PathBuf),
+
+    /// The GTF/GFF path does not end in a UTF-8 file name.
+    #[error("InvalidPathError: GTF/GFF path must end in a valid file name, found {0:?}")]
+    InvalidPathError(PathBuf),
+
     /// The line from the GTF/GFF could not be read.
     #[error("LineReadError: Unable to read line in {0:?}")]
     LineReadError(PathBuf),
@@ -58,4 +53,154 @@ pub enum GtfGffError {
     /// Could not write to the file.
     #[error("FileWriteError: Unable to write line to {0:?}")]
     FileWriteError(PathBuf),
+
+    /// Writing the file was stopped as another output failed.
+    #[error("CancelledError: Writing {0:?} was cancelled as another output failed")]
+    CancelledError(PathBuf),
+}
+
+/// Errors resulting from reading or writing catalogs.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum CatalogError {
+    /// The line from the catalog could not be read.
+    #[error("LineReadError: Unable to read line in {0:?}")]
+    LineReadError(PathBuf),
+
+    /// The line from the catalog does not contain a unified ID followed by a
+    /// transcript signature.
+    #[error("MalformedLineError: Catalog line must have 5 tab-separated fields, found {0:?}")]
+    MalformedLineError(String),
+
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create catalog {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from writing expression matrices.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ExpressionError {
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create expression matrix {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from reading quantifications.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum QuantError {
+    /// The line from the quantification could not be read.
+    #[error("LineReadError: Unable to read line in {0:?}")]
+    LineReadError(PathBuf),
+
+    /// The header does not match any supported quantification format.
+    #[error(
+        "UnknownFormatError: Quantification must be from salmon, kallisto or RSEM, found {0:?}"
+    )]
+    UnknownFormatError(PathBuf),
+
+    /// The line is missing a column or contains a non-numeric value.
+    #[error("MalformedLineError: Unable to parse quantification line {0:?}")]
+    MalformedLineError(String),
+}
+
+/// Errors resulting from writing the redundancy report.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum RedundancyError {
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create redundancy report {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from reporting warnings.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum WarningError {
+    /// Warnings were found of a category passed to `--strict`.
+    #[error("StrictError: Found {1} {0} warning(s) with --strict")]
+    StrictError(&'static str, usize),
+
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create warnings file {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from writing tx2gene tables.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum Tx2GeneError {
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create tx2gene table {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+}
+
+/// Errors resulting from unifying transcripts using temporary files.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ExternalError {
+    /// The temporary directory could not be created.
+    #[error("TempDirError: Unable to create temporary directory in {0:?}")]
+    TempDirError(PathBuf),
+
+    /// The file could not be created.
+    #[error("FileCreateError: Unable to create temporary file {0:?}")]
+    FileCreateError(PathBuf),
+
+    /// Could not write to the file.
+    #[error("FileWriteError: Unable to write line to {0:?}")]
+    FileWriteError(PathBuf),
+
+    /// The file could not be read.
+    #[error("FileReadError: Unable to read temporary file {0:?}")]
+    FileReadError(PathBuf),
+
+    /// A line of the file is malformed.
+    #[error("RunParseError: Unable to parse line of temporary file {0:?}")]
+    RunParseError(PathBuf),
+}
+
+/// Errors resulting from reading regions.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum RegionError {
+    /// The line from the BED file could not be read.
+    #[error("LineReadError: Unable to read line in {0:?}")]
+    LineReadError(PathBuf),
+
+    /// The BED line does not start with a chromosome, start and end.
+    #[error(
+        "BedParseError: BED lines must start with a chromosome, start and end (start < end), found {0:?}"
+    )]
+    BedParseError(String),
+}
+
+/// Errors resulting from parsing attribute filters.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum AttributeFilterError {
+    /// The filter expression is malformed.
+    #[error("FilterParseError: Unable to parse filter {0:?}, {1}")]
+    FilterParseError(String, String),
 }
@@ -0,0 +1,183 @@
+//! Build expression matrices keyed by unified ID.
+
+use crate::error::ExpressionError;
+use crate::gtf_gff::TranscriptId;
+use crate::unify::{TranscriptUnifier, UnifiedId};
+use clap::ValueEnum;
+use log::info;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+/// File name of the expression matrix stored alongside the unified GTF/GFFs.
+pub const EXPRESSION_FILE_NAME: &str = "tuni_expression.tsv";
+
+/// How values are combined when several transcripts from one sample share a
+/// unified ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Aggregate {
+    /// Add values together.
+    #[default]
+    Sum,
+
+    /// Keep the largest value.
+    Max,
+}
+
+impl Aggregate {
+    /// Combine an existing value with a new value.
+    fn apply(&self, existing: f64, new: f64) -> f64 {
+        match self {
+            Aggregate::Sum => existing + new,
+            Aggregate::Max => existing.max(new),
+        }
+    }
+}
+
+/// A unified ID × sample matrix of expression values.
+pub struct ExpressionMatrix {
+    /// How values sharing a unified ID within a sample are combined.
+    aggregate: Aggregate,
+
+    /// Samples, in the order they were added.
+    samples: Vec<Arc<str>>,
+
+    /// Link each unified ID to the expression value in each sample.
+    values: HashMap<UnifiedId, HashMap<Arc<str>, f64>>,
+}
+
+impl ExpressionMatrix {
+    /// Initialise `ExpressionMatrix`.
+    pub fn new(aggregate: Aggregate) -> ExpressionMatrix {
+        ExpressionMatrix {
+            aggregate,
+            samples: Vec::new(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Add the expression values of a sample, keyed by the sample's own
+    /// transcript IDs.
+    ///
+    /// Transcripts without a unified ID (e.g. filtered transcripts) are
+    /// skipped.
+    pub fn add_sample(
+        &mut self,
+        sample: Arc<str>,
+        transcript_values: HashMap<TranscriptId, f64>,
+        transcript_unifier: &TranscriptUnifier,
+    ) {
+        for (transcript_id, value) in transcript_values {
+            let Some(unified_id) = transcript_unifier.get_unified_id(&sample, &transcript_id)
+            else {
+                continue;
+            };
+
+            self.values
+                .entry(Arc::clone(unified_id))
+                .or_default()
+                .entry(Arc::clone(&sample))
+                .and_modify(|x| *x = self.aggregate.apply(*x, value))
+                .or_insert(value);
+        }
+
+        self.samples.push(sample);
+    }
+}
+
+/// Write the expression matrix.
+///
+/// Rows follow the order of the catalog, and only unified IDs with a value in
+/// at least one sample are written. Missing values are written as 0.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](ExpressionError::FileCreateError) if the
+/// matrix cannot be created.
+///
+/// Returns [`FileWriteError`](ExpressionError::FileWriteError) if any line in
+/// the matrix cannot be written.
+pub fn write_expression_matrix(
+    expression_path: &Path,
+    expression_matrix: &ExpressionMatrix,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), ExpressionError> {
+    info!("{}", expression_path.display());
+
+    let matrix = File::create(expression_path)
+        .map_err(|_| ExpressionError::FileCreateError(expression_path.to_path_buf()))?;
+    let mut writer = BufWriter::new(matrix);
+
+    let mut header = vec!["tuni_id"];
+    header.extend(expression_matrix.samples.iter().map(|x| x.as_ref()));
+    writeln!(writer, "{}", header.join("\t"))
+        .map_err(|_| ExpressionError::FileWriteError(expression_path.to_path_buf()))?;
+
+    for unified_id in transcript_unifier.unified_signatures().values() {
+        let Some(sample_values) = expression_matrix.values.get(unified_id) else {
+            continue;
+        };
+
+        let mut row = vec![unified_id.to_string()];
+        row.extend(
+            expression_matrix
+                .samples
+                .iter()
+                .map(|x| sample_values.get(x).copied().unwrap_or(0.0).to_string()),
+        );
+
+        writeln!(writer, "{}", row.join("\t"))
+            .map_err(|_| ExpressionError::FileWriteError(expression_path.to_path_buf()))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|_| ExpressionError::FileWriteError(expression_path.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gtf_gff::{self, ReadOptions};
+    use rstest::rstest;
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    // Transcripts A and A_dup share a unified ID, so their values are
+    // aggregated.
+    #[rstest]
+    #[case(Aggregate::Sum, "tuni_0\t5.5")]
+    #[case(Aggregate::Max, "tuni_0\t3")]
+    fn test_write_expression_matrix(#[case] aggregate: Aggregate, #[case] expected_row: &str) {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_expression.gtf");
+        let sample = gtf_gff::extract_file_name(&gtf_gff_path).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        let mut gtf_gff_transcripts =
+            gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+        transcript_unifier.group_transcripts(Arc::clone(&sample), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        let mut expression_matrix = ExpressionMatrix::new(aggregate);
+        let transcript_values =
+            gtf_gff::read_expression(&gtf_gff_path, "TPM", &ReadOptions::default()).unwrap();
+        expression_matrix.add_sample(sample, transcript_values, &transcript_unifier);
+
+        let temp_dir = tempdir().unwrap();
+        let expression_path = temp_dir.path().join(EXPRESSION_FILE_NAME);
+        write_expression_matrix(&expression_path, &expression_matrix, &transcript_unifier).unwrap();
+
+        assert_eq!(
+            read_to_string(expression_path)
+                .unwrap()
+                .lines()
+                .collect::<Vec<&str>>(),
+            ["tuni_id\tsample_expression.gtf", expected_row, "tuni_1\t1"]
+        );
+    }
+}
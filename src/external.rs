@@ -0,0 +1,640 @@
+//! Unify transcripts that do not fit in memory.
+//!
+//! Transcripts of each sample are buffered up to a memory limit, then sorted
+//! by `TranscriptSignature` and spilled to a temporary run on disk. Runs are
+//! k-way merged, so that transcripts sharing a `TranscriptSignature` are
+//! unified in the same order as [`TranscriptUnifier::unify_transcripts`],
+//! creating the same unified IDs. How each sample transcript was handled is
+//! spilled per sample, then loaded one sample at a time when writing outputs.
+
+use crate::error::ExternalError;
+use crate::filter::FilterReason;
+use crate::gtf_gff::{GtfGffTranscripts, TranscriptId, TranscriptSignature};
+use crate::unify::{Assignment, SampleTranscriptId, TranscriptUnifier};
+use log::info;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Lines, Write},
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tempfile::TempDir;
+
+/// Maximum number of runs merged at once, keeping the number of open files
+/// well below common limits.
+const MAX_MERGE_WIDTH: usize = 64;
+
+/// Approximate bytes of bookkeeping per allocation, e.g. `Arc` counts.
+const ALLOCATION_OVERHEAD: usize = 16;
+
+/// Transcript read from a sample, identified by its index in the sample table.
+type Record = (TranscriptSignature, usize, TranscriptId);
+
+/// Unify transcripts using temporary files, keeping memory use near
+/// `max_memory`.
+///
+/// Only the unified `TranscriptSignature`s (i.e. the catalog) and redundant
+/// transcripts are kept in memory by the `TranscriptUnifier`.
+pub struct ExternalUnifier {
+    /// Directory of the temporary files, removed when dropped.
+    temp_dir: TempDir,
+
+    /// Approximate number of bytes buffered before spilling to disk.
+    max_memory: usize,
+
+    /// Name of each sample, in the order added.
+    samples: Vec<Arc<str>>,
+
+    /// Link each sample name to its index in `samples`.
+    sample_indexes: HashMap<Arc<str>, usize>,
+
+    /// Transcripts not yet spilled to a run.
+    records: Vec<Record>,
+
+    /// Approximate bytes used by `records`.
+    records_bytes: usize,
+
+    /// Runs of transcripts, each sorted by `TranscriptSignature`.
+    runs: Vec<PathBuf>,
+
+    /// Integer used to name the next run.
+    next_run: usize,
+}
+
+impl ExternalUnifier {
+    /// Create `ExternalUnifier`, storing temporary files in a new directory
+    /// within `temp_dir_parent`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TempDirError`](ExternalError::TempDirError) if the temporary
+    /// directory cannot be created.
+    pub fn new(
+        temp_dir_parent: &Path,
+        max_memory: usize,
+    ) -> Result<ExternalUnifier, ExternalError> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix(".tuni_tmp_")
+            .tempdir_in(temp_dir_parent)
+            .map_err(|_| ExternalError::TempDirError(temp_dir_parent.to_path_buf()))?;
+
+        Ok(ExternalUnifier {
+            temp_dir,
+            max_memory,
+            samples: Vec::new(),
+            sample_indexes: HashMap::new(),
+            records: Vec::new(),
+            records_bytes: 0,
+            runs: Vec::new(),
+            next_run: 0,
+        })
+    }
+
+    /// Add the transcripts of a sample, spilling buffered transcripts to disk
+    /// once they exceed the memory limit.
+    ///
+    /// The sample is counted by `transcript_unifier` towards the recurrence
+    /// filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileCreateError`](ExternalError::FileCreateError) or
+    /// [`FileWriteError`](ExternalError::FileWriteError) if a run, or the
+    /// assignments of transcripts filtered while reading, cannot be written.
+    pub fn add_sample(
+        &mut self,
+        transcript_unifier: &mut TranscriptUnifier,
+        gtf_gff_file_name: Arc<str>,
+        gtf_gff_transcripts: &mut GtfGffTranscripts,
+    ) -> Result<(), ExternalError> {
+        transcript_unifier.add_sample(
+            Arc::clone(&gtf_gff_file_name),
+            gtf_gff_transcripts.locus_conflicts.drain(),
+        );
+
+        let sample_index = self.samples.len();
+        self.samples.push(Arc::clone(&gtf_gff_file_name));
+        self.sample_indexes
+            .insert(Arc::clone(&gtf_gff_file_name), sample_index);
+
+        // Transcripts filtered while reading are never merged, so spill their
+        // assignments straight away.
+        let filtered = transcript_unifier
+            .filter_transcripts(&gtf_gff_file_name, gtf_gff_transcripts.filtered.drain());
+        if !filtered.is_empty() {
+            let mut assignment_writer = AssignmentWriter::new(self.max_memory, self.samples.len());
+            for ([_, transcript_id], assignment) in filtered {
+                assignment_writer.push(sample_index, transcript_id, assignment);
+            }
+            assignment_writer.flush(self)?;
+        }
+
+        for (transcript_id, transcript_signature) in gtf_gff_transcripts.signatures.drain() {
+            self.records_bytes += record_bytes(&transcript_signature, &transcript_id);
+            self.records
+                .push((transcript_signature, sample_index, transcript_id));
+
+            if self.records_bytes > self.max_memory {
+                self.spill()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sort buffered transcripts by `TranscriptSignature` and write them to a
+    /// new run.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileCreateError`](ExternalError::FileCreateError) if the run
+    /// cannot be created.
+    ///
+    /// Returns [`FileWriteError`](ExternalError::FileWriteError) if the run
+    /// cannot be written.
+    fn spill(&mut self) -> Result<(), ExternalError> {
+        if self.records.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = mem::take(&mut self.records);
+        self.records_bytes = 0;
+        records.sort_unstable();
+
+        let run_path = self.new_run_path();
+        info!(
+            "Spilling {} transcripts to {}",
+            records.len(),
+            run_path.display()
+        );
+        let mut writer = RunWriter::create(&run_path)?;
+        for record in &records {
+            writer.write(record)?;
+        }
+        writer.finish()?;
+
+        self.runs.push(run_path);
+        Ok(())
+    }
+
+    /// Create the path of the next run.
+    fn new_run_path(&mut self) -> PathBuf {
+        self.next_run += 1;
+        self.temp_dir
+            .path()
+            .join(format!("run_{}.tsv", self.next_run - 1))
+    }
+
+    /// Create the path of the spilled assignments of a sample.
+    fn assignments_path(&self, sample_index: usize) -> PathBuf {
+        self.temp_dir
+            .path()
+            .join(format!("assignments_{}.tsv", sample_index))
+    }
+
+    /// Merge every run, unifying each group of transcripts sharing a
+    /// `TranscriptSignature` in order, then spill how each sample transcript
+    /// was handled.
+    ///
+    /// Must be called after every sample has been added.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileReadError`](ExternalError::FileReadError) or
+    /// [`RunParseError`](ExternalError::RunParseError) if a run cannot be read.
+    ///
+    /// Returns [`FileCreateError`](ExternalError::FileCreateError) or
+    /// [`FileWriteError`](ExternalError::FileWriteError) if a run or spilled
+    /// assignments cannot be written.
+    pub fn unify(
+        &mut self,
+        transcript_unifier: &mut TranscriptUnifier,
+    ) -> Result<(), ExternalError> {
+        self.spill()?;
+
+        // Merge runs in batches until they can all be opened at once.
+        while self.runs.len() > MAX_MERGE_WIDTH {
+            let runs = mem::take(&mut self.runs);
+            for batch in runs.chunks(MAX_MERGE_WIDTH) {
+                let run_path = self.new_run_path();
+                let mut run_merger = RunMerger::open(batch)?;
+                let mut writer = RunWriter::create(&run_path)?;
+                while let Some(record) = run_merger.next_record()? {
+                    writer.write(&record)?;
+                }
+                writer.finish()?;
+                for merged_path in batch {
+                    let _ = fs::remove_file(merged_path);
+                }
+                self.runs.push(run_path);
+            }
+        }
+
+        let mut assignment_writer = AssignmentWriter::new(self.max_memory, self.samples.len());
+        let mut run_merger = RunMerger::open(&self.runs)?;
+        let mut group: Option<(TranscriptSignature, Vec<SampleTranscriptId>)> = None;
+
+        while let Some((transcript_signature, sample_index, transcript_id)) =
+            run_merger.next_record()?
+        {
+            let sample_transcript_id = [Arc::clone(&self.samples[sample_index]), transcript_id];
+            match &mut group {
+                Some((group_signature, sample_transcript_ids))
+                    if *group_signature == transcript_signature =>
+                {
+                    sample_transcript_ids.push(sample_transcript_id);
+                }
+                _ => {
+                    if let Some((group_signature, sample_transcript_ids)) =
+                        group.replace((transcript_signature, vec![sample_transcript_id]))
+                    {
+                        self.unify_group(
+                            transcript_unifier,
+                            &mut assignment_writer,
+                            group_signature,
+                            sample_transcript_ids,
+                        )?;
+                    }
+                }
+            }
+        }
+        if let Some((group_signature, sample_transcript_ids)) = group {
+            self.unify_group(
+                transcript_unifier,
+                &mut assignment_writer,
+                group_signature,
+                sample_transcript_ids,
+            )?;
+        }
+
+        assignment_writer.flush(self)?;
+        transcript_unifier.sort_redundant_groups();
+
+        Ok(())
+    }
+
+    /// Unify one group of transcripts sharing a `TranscriptSignature`,
+    /// buffering the assignments of each sample transcript.
+    fn unify_group(
+        &self,
+        transcript_unifier: &mut TranscriptUnifier,
+        assignment_writer: &mut AssignmentWriter,
+        transcript_signature: TranscriptSignature,
+        sample_transcript_ids: Vec<SampleTranscriptId>,
+    ) -> Result<(), ExternalError> {
+        for ([sample, transcript_id], assignment) in
+            transcript_unifier.unify_group(transcript_signature, sample_transcript_ids)
+        {
+            assignment_writer.push(self.sample_indexes[&sample], transcript_id, assignment);
+        }
+        if assignment_writer.is_full() {
+            assignment_writer.flush(self)?;
+        }
+        Ok(())
+    }
+
+    /// Load how each transcript of a sample was handled into
+    /// `transcript_unifier`, so that it can be looked up by
+    /// [`TranscriptUnifier::get_unified_id`].
+    ///
+    /// Call [`TranscriptUnifier::clear_assignments`] once the sample's outputs
+    /// are written to release the memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileReadError`](ExternalError::FileReadError) if the spilled
+    /// assignments cannot be read.
+    ///
+    /// Returns [`RunParseError`](ExternalError::RunParseError) if any line of
+    /// the spilled assignments is malformed.
+    pub fn load_assignments(
+        &self,
+        transcript_unifier: &mut TranscriptUnifier,
+        gtf_gff_file_name: &Arc<str>,
+    ) -> Result<(), ExternalError> {
+        let Some(&sample_index) = self.sample_indexes.get(gtf_gff_file_name) else {
+            return Ok(());
+        };
+        let assignments_path = self.assignments_path(sample_index);
+
+        // Samples without transcripts have no assignments.
+        if !assignments_path.exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(
+            File::open(&assignments_path)
+                .map_err(|_| ExternalError::FileReadError(assignments_path.clone()))?,
+        );
+        for line in reader.lines() {
+            let line = line.map_err(|_| ExternalError::FileReadError(assignments_path.clone()))?;
+            let parse_error = || ExternalError::RunParseError(assignments_path.clone());
+
+            let (transcript_id, assignment) = line.split_once('\t').ok_or_else(parse_error)?;
+            let assignment = match assignment.split_once('\t') {
+                Some(("unified", unified_id)) => Assignment::Unified(Arc::from(unified_id)),
+                Some(("filtered", filter_reason)) => Assignment::Filtered(
+                    FilterReason::parse(filter_reason).ok_or_else(parse_error)?,
+                ),
+                _ => return Err(parse_error()),
+            };
+
+            transcript_unifier.insert_assignment(
+                [Arc::clone(gtf_gff_file_name), Arc::from(transcript_id)],
+                assignment,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Approximate bytes used by a buffered transcript.
+fn record_bytes(transcript_signature: &TranscriptSignature, transcript_id: &str) -> usize {
+    mem::size_of::<Record>()
+        + mem::size_of_val(transcript_signature.exon_boundaries())
+        + mem::size_of_val(transcript_signature.cds_boundaries())
+        + transcript_id.len()
+        + 2 * ALLOCATION_OVERHEAD
+}
+
+/// Write transcripts to a run, one per line as the `TranscriptSignature`
+/// fields, the sample index and the transcript ID.
+struct RunWriter {
+    /// Path of the run.
+    path: PathBuf,
+
+    /// Writer of the run.
+    writer: BufWriter<File>,
+}
+
+impl RunWriter {
+    /// Create a run.
+    fn create(path: &Path) -> Result<RunWriter, ExternalError> {
+        let file =
+            File::create(path).map_err(|_| ExternalError::FileCreateError(path.to_path_buf()))?;
+        Ok(RunWriter {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Write one transcript.
+    fn write(&mut self, record: &Record) -> Result<(), ExternalError> {
+        let (transcript_signature, sample_index, transcript_id) = record;
+        writeln!(
+            self.writer,
+            "{}\t{}\t{}",
+            transcript_signature, sample_index, transcript_id
+        )
+        .map_err(|_| ExternalError::FileWriteError(self.path.clone()))
+    }
+
+    /// Flush the run to disk.
+    fn finish(mut self) -> Result<(), ExternalError> {
+        self.writer
+            .flush()
+            .map_err(|_| ExternalError::FileWriteError(self.path.clone()))
+    }
+}
+
+/// Read the transcripts of a run in order.
+struct RunReader {
+    /// Path of the run.
+    path: PathBuf,
+
+    /// Lines of the run.
+    lines: Lines<BufReader<File>>,
+}
+
+impl RunReader {
+    /// Open a run.
+    fn open(path: &Path) -> Result<RunReader, ExternalError> {
+        let file =
+            File::open(path).map_err(|_| ExternalError::FileReadError(path.to_path_buf()))?;
+        Ok(RunReader {
+            path: path.to_path_buf(),
+            lines: BufReader::new(file).lines(),
+        })
+    }
+
+    /// Read the next transcript, returning `None` at the end of the run.
+    fn next_record(&mut self) -> Result<Option<Record>, ExternalError> {
+        let Some(line) = self.lines.next() else {
+            return Ok(None);
+        };
+        let line = line.map_err(|_| ExternalError::FileReadError(self.path.clone()))?;
+        let parse_error = || ExternalError::RunParseError(self.path.clone());
+
+        let fields = line.split('\t').collect::<Vec<&str>>();
+        let [signature_fields @ .., sample_index, transcript_id] = fields.as_slice() else {
+            return Err(parse_error());
+        };
+
+        Ok(Some((
+            TranscriptSignature::from_fields(signature_fields).ok_or_else(parse_error)?,
+            sample_index.parse::<usize>().map_err(|_| parse_error())?,
+            Arc::from(*transcript_id),
+        )))
+    }
+}
+
+/// Merge sorted runs into one sorted sequence of transcripts.
+struct RunMerger {
+    /// Reader of each run.
+    readers: Vec<RunReader>,
+
+    /// The next transcript of each run that has not been merged, along with
+    /// the index of its reader.
+    heap: BinaryHeap<Reverse<(Record, usize)>>,
+}
+
+impl RunMerger {
+    /// Open every run, reading the first transcript of each.
+    fn open(run_paths: &[PathBuf]) -> Result<RunMerger, ExternalError> {
+        let mut readers = run_paths
+            .iter()
+            .map(|x| RunReader::open(x))
+            .collect::<Result<Vec<RunReader>, ExternalError>>()?;
+
+        let mut heap = BinaryHeap::new();
+        for (reader_index, reader) in readers.iter_mut().enumerate() {
+            if let Some(record) = reader.next_record()? {
+                heap.push(Reverse((record, reader_index)));
+            }
+        }
+
+        Ok(RunMerger { readers, heap })
+    }
+
+    /// Obtain the next transcript across every run, returning `None` once
+    /// every run is merged.
+    fn next_record(&mut self) -> Result<Option<Record>, ExternalError> {
+        let Some(Reverse((record, reader_index))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        if let Some(next) = self.readers[reader_index].next_record()? {
+            self.heap.push(Reverse((next, reader_index)));
+        }
+        Ok(Some(record))
+    }
+}
+
+/// Buffer how each sample transcript was handled, appending to the spilled
+/// assignments of each sample once over the memory limit.
+struct AssignmentWriter {
+    /// Approximate number of bytes buffered before flushing.
+    max_memory: usize,
+
+    /// Buffered lines of each sample.
+    buffers: Vec<String>,
+
+    /// Approximate bytes used by `buffers`.
+    buffered_bytes: usize,
+}
+
+impl AssignmentWriter {
+    /// Create `AssignmentWriter` for `n_samples` samples.
+    fn new(max_memory: usize, n_samples: usize) -> AssignmentWriter {
+        AssignmentWriter {
+            max_memory,
+            buffers: vec![String::new(); n_samples],
+            buffered_bytes: 0,
+        }
+    }
+
+    /// Buffer the assignment of a sample transcript.
+    fn push(&mut self, sample_index: usize, transcript_id: TranscriptId, assignment: Assignment) {
+        let line = match assignment {
+            Assignment::Unified(unified_id) => {
+                format!("{}\tunified\t{}\n", transcript_id, unified_id)
+            }
+            Assignment::Filtered(filter_reason) => {
+                format!("{}\tfiltered\t{}\n", transcript_id, filter_reason.as_str())
+            }
+        };
+        self.buffered_bytes += line.len();
+        self.buffers[sample_index].push_str(&line);
+    }
+
+    /// Returns true if the buffered assignments exceed the memory limit,
+    /// otherwise false.
+    fn is_full(&self) -> bool {
+        self.buffered_bytes > self.max_memory
+    }
+
+    /// Append the buffered assignments of each sample to its spilled
+    /// assignments.
+    fn flush(&mut self, external_unifier: &ExternalUnifier) -> Result<(), ExternalError> {
+        for (sample_index, buffer) in self.buffers.iter_mut().enumerate() {
+            if buffer.is_empty() {
+                continue;
+            }
+            let assignments_path = external_unifier.assignments_path(sample_index);
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&assignments_path)
+                .map_err(|_| ExternalError::FileCreateError(assignments_path.clone()))?;
+            file.write_all(buffer.as_bytes())
+                .map_err(|_| ExternalError::FileWriteError(assignments_path.clone()))?;
+            buffer.clear();
+        }
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gtf_gff::{self, ReadOptions};
+    use crate::unify::{UnifiedId, UnifyOptions};
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    // A limit of 1 byte spills every transcript to its own run, which are
+    // merged in batches as there are more than `MAX_MERGE_WIDTH` runs.
+    #[rstest]
+    #[case(1)]
+    #[case(1_000_000)]
+    fn test_external_unifier(#[case] max_memory: usize) {
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+            PathBuf::from("tests/data/unit/sample_redundant.gtf"),
+            PathBuf::from("tests/data/unit/sample_locus_conflict.gtf"),
+        ];
+        let options = UnifyOptions::builder().min_samples(2).build();
+
+        let mut expected_unifier = TranscriptUnifier::new().with_options(&options);
+        let mut transcript_unifier = TranscriptUnifier::new().with_options(&options);
+        let temp_dir = tempdir().unwrap();
+        let mut external_unifier = ExternalUnifier::new(temp_dir.path(), max_memory).unwrap();
+
+        for gtf_gff_path in &gtf_gff_paths {
+            let gtf_gff_file_name = gtf_gff::extract_file_name(gtf_gff_path).unwrap();
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap();
+            let mut external_transcripts =
+                gtf_gff::read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap();
+
+            expected_unifier
+                .group_transcripts(Arc::clone(&gtf_gff_file_name), &mut gtf_gff_transcripts);
+            external_unifier
+                .add_sample(
+                    &mut transcript_unifier,
+                    gtf_gff_file_name,
+                    &mut external_transcripts,
+                )
+                .unwrap();
+        }
+
+        expected_unifier.unify_transcripts();
+        external_unifier.unify(&mut transcript_unifier).unwrap();
+
+        assert_eq!(
+            transcript_unifier.unified_signatures(),
+            expected_unifier.unified_signatures()
+        );
+        assert_eq!(
+            transcript_unifier.redundant_groups(),
+            expected_unifier.redundant_groups()
+        );
+        assert_eq!(
+            transcript_unifier.unify_counts(),
+            expected_unifier.unify_counts()
+        );
+
+        // Assignments are only held in memory once loaded.
+        assert!(transcript_unifier.unified_transcripts().next().is_none());
+        for gtf_gff_path in &gtf_gff_paths {
+            external_unifier
+                .load_assignments(
+                    &mut transcript_unifier,
+                    &gtf_gff::extract_file_name(gtf_gff_path).unwrap(),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            transcript_unifier
+                .unified_transcripts()
+                .collect::<HashMap<SampleTranscriptId, &UnifiedId>>(),
+            expected_unifier
+                .unified_transcripts()
+                .collect::<HashMap<SampleTranscriptId, &UnifiedId>>()
+        );
+        assert_eq!(
+            transcript_unifier
+                .filtered_transcripts()
+                .collect::<HashMap<SampleTranscriptId, FilterReason>>(),
+            expected_unifier
+                .filtered_transcripts()
+                .collect::<HashMap<SampleTranscriptId, FilterReason>>()
+        );
+    }
+}
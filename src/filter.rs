@@ -0,0 +1,280 @@
+//! Filter transcripts before they are unified.
+
+use crate::gtf_gff::Coordinate;
+use clap::ValueEnum;
+
+/// Reason a transcript was filtered, reported in the outputted GTF/GFFs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterReason {
+    /// Transcript was not found in enough samples.
+    Recurrence,
+
+    /// Transcript shares a `TranscriptSignature` with another transcript in
+    /// the same sample.
+    Redundant,
+
+    /// Transcript is outside of the included contigs and regions.
+    Region,
+
+    /// Transcript structure (e.g. exon count) fails the structure filter.
+    Structure,
+
+    /// Transcript attributes fail the attribute filter.
+    Attribute,
+}
+
+impl FilterReason {
+    /// Value of the "tuni_filtered" attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterReason::Recurrence => "recurrence",
+            FilterReason::Redundant => "redundant",
+            FilterReason::Region => "region",
+            FilterReason::Structure => "structure",
+            FilterReason::Attribute => "attribute",
+        }
+    }
+
+    /// Parse the value written by [`FilterReason::as_str`].
+    ///
+    /// Returns `None` if the value is not a filter reason.
+    pub fn parse(value: &str) -> Option<FilterReason> {
+        match value {
+            "recurrence" => Some(FilterReason::Recurrence),
+            "redundant" => Some(FilterReason::Redundant),
+            "region" => Some(FilterReason::Region),
+            "structure" => Some(FilterReason::Structure),
+            "attribute" => Some(FilterReason::Attribute),
+            _ => None,
+        }
+    }
+}
+
+/// How lines of filtered transcripts are handled in the outputted GTF/GFFs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FilterAction {
+    /// Keep lines, adding a "tuni_filtered" attribute with the filter reason.
+    #[default]
+    Tag,
+
+    /// Remove lines.
+    Drop,
+}
+
+/// Minimum number or fraction of samples a transcript must be found in to be
+/// unified.
+///
+/// When samples are assigned to groups (e.g. tissues), a transcript can
+/// instead pass by being found in a minimum fraction of the samples of any one
+/// group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecurrenceFilter {
+    /// Minimum number of samples.
+    pub min_samples: usize,
+
+    /// Minimum fraction of all samples.
+    pub min_fraction: f64,
+
+    /// Minimum fraction of the samples within any one group.
+    pub min_group_fraction: Option<f64>,
+}
+
+impl Default for RecurrenceFilter {
+    /// By default, every transcript passes.
+    fn default() -> RecurrenceFilter {
+        RecurrenceFilter {
+            min_samples: 1,
+            min_fraction: 0.0,
+            min_group_fraction: None,
+        }
+    }
+}
+
+impl RecurrenceFilter {
+    /// Returns true if a transcript found in `n_samples` out of `total_samples`
+    /// passes the global minimums, otherwise false.
+    pub fn passes(&self, n_samples: usize, total_samples: usize) -> bool {
+        n_samples >= self.min_samples
+            && (n_samples as f64) >= self.min_fraction * (total_samples as f64)
+    }
+
+    /// Returns true if a transcript found in `n_group_samples` out of the
+    /// `group_size` samples of a group passes the group minimum, otherwise
+    /// false.
+    ///
+    /// Always false if no group minimum is set.
+    pub fn passes_group(&self, n_group_samples: usize, group_size: usize) -> bool {
+        self.min_group_fraction
+            .is_some_and(|x| (n_group_samples as f64) >= x * (group_size as f64))
+    }
+
+    /// Returns true if either global minimum would filter any transcripts,
+    /// otherwise false.
+    pub fn has_global_minimum(&self) -> bool {
+        self.min_samples > 1 || self.min_fraction > 0.0
+    }
+}
+
+/// Which transcripts are kept depending on whether they have CDS regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodingFilter {
+    /// Keep every transcript.
+    #[default]
+    All,
+
+    /// Only keep transcripts with CDS regions.
+    Coding,
+
+    /// Only keep transcripts without CDS regions.
+    Noncoding,
+}
+
+/// Requirements on the structure of a transcript for it to be unified,
+/// evaluated on its exon/CDS records before grouping.
+///
+/// Exons are the transcript's exon records, or its CDS records if it has no
+/// exon records. Overlapping or adjacent exons are merged before counting.
+/// By default, every transcript passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StructureFilter {
+    /// Minimum total length of the exons.
+    pub min_spliced_length: Option<Coordinate>,
+
+    /// Minimum number of exons.
+    pub min_exons: Option<usize>,
+
+    /// Maximum number of exons.
+    pub max_exons: Option<usize>,
+
+    /// Whether to only keep coding or noncoding transcripts.
+    pub coding: CodingFilter,
+
+    /// Minimum length of every intron, single-exon transcripts always pass.
+    pub min_intron_length: Option<Coordinate>,
+}
+
+impl StructureFilter {
+    /// Returns true if any requirement would filter transcripts, otherwise
+    /// false.
+    pub fn is_active(&self) -> bool {
+        *self != StructureFilter::default()
+    }
+
+    /// Returns true if a transcript with the start and end coordinates of
+    /// every exon and CDS record, in any order, passes, otherwise false.
+    pub fn passes(&self, exon_boundaries: &[Coordinate], cds_boundaries: &[Coordinate]) -> bool {
+        let has_cds = !cds_boundaries.is_empty();
+        let passes_coding = match self.coding {
+            CodingFilter::All => true,
+            CodingFilter::Coding => has_cds,
+            CodingFilter::Noncoding => !has_cds,
+        };
+        if !passes_coding {
+            return false;
+        }
+
+        let exons = match exon_boundaries.is_empty() {
+            true => merge_intervals(cds_boundaries),
+            false => merge_intervals(exon_boundaries),
+        };
+        let spliced_length: Coordinate = exons.iter().map(|(start, end)| end - start + 1).sum();
+        let min_intron_length = exons.windows(2).map(|x| x[1].0 - x[0].1 - 1).min();
+
+        self.min_spliced_length.is_none_or(|x| spliced_length >= x)
+            && self.min_exons.is_none_or(|x| exons.len() >= x)
+            && self.max_exons.is_none_or(|x| exons.len() <= x)
+            && self
+                .min_intron_length
+                .is_none_or(|x| min_intron_length.is_none_or(|y| y >= x))
+    }
+}
+
+/// Sort (start, end) pairs of flat `boundaries`, merging overlapping or
+/// adjacent intervals.
+fn merge_intervals(boundaries: &[Coordinate]) -> Vec<(Coordinate, Coordinate)> {
+    let mut intervals = boundaries
+        .chunks_exact(2)
+        .map(|x| (x[0].min(x[1]), x[0].max(x[1])))
+        .collect::<Vec<(Coordinate, Coordinate)>>();
+    intervals.sort_unstable();
+
+    let mut merged: Vec<(Coordinate, Coordinate)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(RecurrenceFilter::default(), 1, 10, true)]
+    #[case(RecurrenceFilter { min_samples: 2, ..Default::default() }, 1, 10, false)]
+    #[case(RecurrenceFilter { min_samples: 2, ..Default::default() }, 2, 10, true)]
+    #[case(RecurrenceFilter { min_fraction: 0.5, ..Default::default() }, 4, 10, false)]
+    #[case(RecurrenceFilter { min_fraction: 0.5, ..Default::default() }, 5, 10, true)]
+    fn test_recurrence_filter(
+        #[case] recurrence_filter: RecurrenceFilter,
+        #[case] n_samples: usize,
+        #[case] total_samples: usize,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(recurrence_filter.passes(n_samples, total_samples), expected);
+    }
+
+    #[rstest]
+    #[case(RecurrenceFilter::default(), 3, 3, false)]
+    #[case(RecurrenceFilter { min_group_fraction: Some(0.5), ..Default::default() }, 1, 3, false)]
+    #[case(RecurrenceFilter { min_group_fraction: Some(0.5), ..Default::default() }, 2, 3, true)]
+    fn test_recurrence_filter_group(
+        #[case] recurrence_filter: RecurrenceFilter,
+        #[case] n_group_samples: usize,
+        #[case] group_size: usize,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            recurrence_filter.passes_group(n_group_samples, group_size),
+            expected
+        );
+    }
+
+    // Exons 1-10, 21-30 and 26-40 (overlapping) merge into 2 exons with a
+    // spliced length of 30 and an intron of 10.
+    #[rstest]
+    #[case(StructureFilter::default(), true)]
+    #[case(StructureFilter { min_spliced_length: Some(30), ..Default::default() }, true)]
+    #[case(StructureFilter { min_spliced_length: Some(31), ..Default::default() }, false)]
+    #[case(StructureFilter { min_exons: Some(2), ..Default::default() }, true)]
+    #[case(StructureFilter { min_exons: Some(3), ..Default::default() }, false)]
+    #[case(StructureFilter { max_exons: Some(1), ..Default::default() }, false)]
+    #[case(StructureFilter { coding: CodingFilter::Coding, ..Default::default() }, true)]
+    #[case(StructureFilter { coding: CodingFilter::Noncoding, ..Default::default() }, false)]
+    #[case(StructureFilter { min_intron_length: Some(10), ..Default::default() }, true)]
+    #[case(StructureFilter { min_intron_length: Some(11), ..Default::default() }, false)]
+    fn test_structure_filter(#[case] structure_filter: StructureFilter, #[case] expected: bool) {
+        assert_eq!(
+            structure_filter.passes(&[26, 40, 1, 10, 21, 30], &[5, 10]),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_structure_filter_cds_only() {
+        let structure_filter = StructureFilter {
+            min_exons: Some(2),
+            min_intron_length: Some(5),
+            ..Default::default()
+        };
+        assert!(structure_filter.passes(&[], &[1, 10, 16, 20]));
+        assert!(!structure_filter.passes(&[], &[1, 10, 11, 20]));
+        assert!(!structure_filter.passes(&[1, 20], &[1, 10, 16, 20]));
+        assert!(structure_filter.is_active());
+        assert!(!StructureFilter::default().is_active());
+    }
+}
@@ -1,17 +1,33 @@
+use crate::attribute_filter::AttributeFilter;
+use crate::chr_alias::ChrAliases;
 use crate::error::GtfGffError;
+use crate::filter::{FilterAction, FilterReason, StructureFilter};
+use crate::line_cache::{read_line_cache, LineCacheWriter};
+use crate::region::RegionFilter;
 use crate::unify::TranscriptUnifier;
-use log::{info, warn};
+use crate::warning::{Warning, WarningCategory, Warnings};
+use clap::ValueEnum;
+use log::info;
+use rayon::prelude::*;
 
 use std::{
-    collections::{BTreeSet, HashMap},
-    fs::File,
-    io::{BufRead, BufReader, BufWriter, Write},
-    path::Path,
-    rc::Rc,
+    cmp,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 /// Transcript ID in the format "transcript_id \"A.1\"".
-pub type TranscriptId = Rc<str>;
+pub type TranscriptId = Arc<str>;
+
+/// Genomic coordinate of an exon/CDS boundary.
+pub type Coordinate = u64;
 
 /// Contains all details needed to identify a unique transcript.
 ///
@@ -20,62 +36,434 @@ pub type TranscriptId = Rc<str>;
 /// included to differentiate between transcripts that have:
 /// 1. The same coding regions and different UTRs.
 /// 2. The same UTRs and different coding regions.
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// To keep memory low across millions of transcripts, chromosome and strand
+/// names are shared between transcripts (see [`read_gtf_gff`]) and boundaries
+/// are stored in one flat, sorted vector of integer coordinates.
+///
+/// `TranscriptSignature`s are ordered by chromosome, strand, exon boundaries
+/// and then CDS boundaries, comparing boundaries numerically.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct TranscriptSignature {
     /// Chromosome.
-    chr: Rc<str>,
+    chr: Arc<str>,
 
     /// Strand.
-    strand: Rc<str>,
+    strand: Arc<str>,
 
-    /// The start and end coordinates of every exon in the transcript.
-    ///
-    /// Must be `BTreesSet`s as:
-    /// 1. `TranscriptSignature` will be used a `HashMap`` key. `HashSet`s are not
-    /// hashable as they do not have an order.
-    /// 2. A `Vec<Rc<str>>` cannot be used as regions are not assumed to be
-    /// sorted in the input GTF/GFF.
-    exon_boundaries: BTreeSet<Rc<str>>,
-
-    /// The start and end coordinates of every CDS region in the transcript.
+    /// Number of exon boundaries at the start of `boundaries`.
+    n_exon_boundaries: u32,
+
+    /// The unique start and end coordinates of every exon in the transcript,
+    /// sorted, followed by those of every CDS region.
     ///
-    /// Must be a `BTreeSet` for the same reasons as above.
-    cds_boundaries: BTreeSet<Rc<str>>,
+    /// Boundaries are sorted and deduplicated on creation as regions are not
+    /// assumed to be sorted in the input GTF/GFF.
+    boundaries: Box<[Coordinate]>,
 }
 
 impl TranscriptSignature {
     /// Create `TranscriptSignature`.
     pub fn from(
-        chr: Rc<str>,
-        strand: Rc<str>,
-        exon_boundaries: BTreeSet<Rc<str>>,
-        cds_boundaries: BTreeSet<Rc<str>>,
+        chr: Arc<str>,
+        strand: Arc<str>,
+        exon_boundaries: impl IntoIterator<Item = Coordinate>,
+        cds_boundaries: impl IntoIterator<Item = Coordinate>,
     ) -> TranscriptSignature {
+        let mut boundaries = exon_boundaries.into_iter().collect::<Vec<Coordinate>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        let n_exon_boundaries = boundaries.len();
+
+        let mut cds = cds_boundaries.into_iter().collect::<Vec<Coordinate>>();
+        cds.sort_unstable();
+        cds.dedup();
+        boundaries.extend(cds);
+
         TranscriptSignature {
             chr,
             strand,
-            exon_boundaries,
-            cds_boundaries,
+            n_exon_boundaries: u32::try_from(n_exon_boundaries)
+                .expect("number of exon boundaries should fit in u32"),
+            boundaries: boundaries.into_boxed_slice(),
+        }
+    }
+
+    /// The sorted start and end coordinates of every exon.
+    pub fn exon_boundaries(&self) -> &[Coordinate] {
+        &self.boundaries[..self.n_exon_boundaries as usize]
+    }
+
+    /// The sorted start and end coordinates of every CDS region.
+    pub fn cds_boundaries(&self) -> &[Coordinate] {
+        &self.boundaries[self.n_exon_boundaries as usize..]
+    }
+
+    /// Returns true if the transcript has any CDS regions, otherwise false.
+    pub fn has_cds(&self) -> bool {
+        !self.cds_boundaries().is_empty()
+    }
+
+    /// Create `TranscriptSignature` from the fields written by its `Display`
+    /// implementation, i.e. chromosome, strand, exon boundaries and CDS
+    /// boundaries.
+    ///
+    /// Returns `None` if the number of fields is not 4 or any boundary is not
+    /// an integer.
+    pub fn from_fields(fields: &[&str]) -> Option<TranscriptSignature> {
+        let [chr, strand, exon_boundaries, cds_boundaries] = fields else {
+            return None;
+        };
+
+        Some(TranscriptSignature::from(
+            Arc::from(*chr),
+            Arc::from(*strand),
+            TranscriptSignature::parse_boundaries(exon_boundaries)?,
+            TranscriptSignature::parse_boundaries(cds_boundaries)?,
+        ))
+    }
+
+    /// Parse comma-separated boundaries, an empty string represents no
+    /// boundaries.
+    ///
+    /// Returns `None` if any boundary is not an integer.
+    fn parse_boundaries(boundaries: &str) -> Option<Vec<Coordinate>> {
+        boundaries
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse::<Coordinate>().ok())
+            .collect()
+    }
+}
+
+impl Ord for TranscriptSignature {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (
+            &self.chr,
+            &self.strand,
+            self.exon_boundaries(),
+            self.cds_boundaries(),
+        )
+            .cmp(&(
+                &other.chr,
+                &other.strand,
+                other.exon_boundaries(),
+                other.cds_boundaries(),
+            ))
+    }
+}
+
+impl PartialOrd for TranscriptSignature {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for TranscriptSignature {
+    /// Format as tab-separated chromosome, strand, exon boundaries and CDS
+    /// boundaries, where boundaries are comma-separated.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |boundaries: &[Coordinate]| {
+            boundaries
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        write!(
+            f,
+            "{}\t{}\t{}\t{}",
+            self.chr,
+            self.strand,
+            join(self.exon_boundaries()),
+            join(self.cds_boundaries())
+        )
+    }
+}
+
+/// Boundaries of a transcript collected while reading a GTF/GFF, before they
+/// are sorted into a `TranscriptSignature`.
+#[derive(Debug)]
+struct TranscriptBuilder {
+    /// Chromosome.
+    chr: Arc<str>,
+
+    /// Strand.
+    strand: Arc<str>,
+
+    /// The start and end coordinates of every exon, in the order read.
+    exon_boundaries: Vec<Coordinate>,
+
+    /// The start and end coordinates of every CDS region, in the order read.
+    cds_boundaries: Vec<Coordinate>,
+}
+
+impl TranscriptBuilder {
+    /// Create `TranscriptBuilder` without any boundaries.
+    fn from(chr: Arc<str>, strand: Arc<str>) -> TranscriptBuilder {
+        TranscriptBuilder {
+            chr,
+            strand,
+            exon_boundaries: Vec::new(),
+            cds_boundaries: Vec::new(),
         }
     }
 
-    /// Insert exon/CDS boundary into `TranscriptSignature`.
+    /// Insert the start and end of an exon/CDS record.
     ///
     /// # Errors
     ///
     /// Returns [`UnknownFeatureError`](GtfGffError::UnknownFeatureError) if the
     /// feature is not "exon" or "CDS". This error likely indicates a bug in
     /// tuni when filtering GTF/GFF lines.
-    fn insert_boundary(&mut self, feature: &str, value: Rc<str>) -> Result<(), GtfGffError> {
-        match feature {
-            "exon" => {
-                self.exon_boundaries.insert(value);
-            }
-            "CDS" => {
-                self.cds_boundaries.insert(value);
-            }
+    fn insert_boundaries(&mut self, record: &GtfGffRecord) -> Result<(), GtfGffError> {
+        let boundaries = match record.feature {
+            "exon" => &mut self.exon_boundaries,
+            "CDS" => &mut self.cds_boundaries,
             other => return Err(GtfGffError::UnknownFeatureError(other.to_string())),
         };
+        boundaries.extend([record.start, record.end]);
+        Ok(())
+    }
+
+    /// Returns true if the transcript is on the chromosome and strand
+    /// provided, otherwise false.
+    fn is_on(&self, chr: &str, strand: &str) -> bool {
+        self.chr.as_ref() == chr && self.strand.as_ref() == strand
+    }
+
+    /// Obtain the first and last coordinate of the transcript.
+    fn span(&self) -> (Coordinate, Coordinate) {
+        let boundaries = self.exon_boundaries.iter().chain(&self.cds_boundaries);
+        (
+            boundaries.clone().copied().min().unwrap_or_default(),
+            boundaries.copied().max().unwrap_or_default(),
+        )
+    }
+
+    /// Obtain the reason the transcript is filtered by `read_options`, if any.
+    ///
+    /// `attribute_values` are the values of each attribute of the attribute
+    /// filter, see [`AttributeFilter::keys`].
+    fn filter_reason(
+        &self,
+        read_options: &ReadOptions,
+        attribute_values: &[Option<&str>],
+    ) -> Option<FilterReason> {
+        let (start, end) = self.span();
+        if !read_options.region_filter.includes(&self.chr, start, end) {
+            return Some(FilterReason::Region);
+        }
+        if !read_options
+            .structure_filter
+            .passes(&self.exon_boundaries, &self.cds_boundaries)
+        {
+            return Some(FilterReason::Structure);
+        }
+        if let Some(attribute_filter) = &read_options.attribute_filter {
+            if !attribute_filter.passes(attribute_values) {
+                return Some(FilterReason::Attribute);
+            }
+        }
+        None
+    }
+
+    /// Sort the boundaries into a `TranscriptSignature`.
+    fn build(self) -> TranscriptSignature {
+        TranscriptSignature::from(
+            self.chr,
+            self.strand,
+            self.exon_boundaries,
+            self.cds_boundaries,
+        )
+    }
+}
+
+/// Share one allocation between repeated names, e.g. the chromosome or
+/// transcript ID of every line in a GTF/GFF.
+#[derive(Debug, Default)]
+struct Interner {
+    /// Link each name read to its shared (and possibly normalised) value.
+    values: HashMap<Box<str>, Arc<str>>,
+}
+
+impl Interner {
+    /// Obtain the shared value of `name`, creating it with `create` if `name`
+    /// has not been seen before.
+    fn intern(&mut self, name: &str, create: impl FnOnce() -> Arc<str>) -> Arc<str> {
+        match self.values.get(name) {
+            Some(value) => Arc::clone(value),
+            None => {
+                let value = create();
+                self.values.insert(Box::from(name), Arc::clone(&value));
+                value
+            }
+        }
+    }
+}
+
+/// Transcripts read from a GTF/GFF file.
+#[derive(Debug, Default, PartialEq)]
+pub struct GtfGffTranscripts {
+    /// `TranscriptSignature` of each transcript.
+    pub signatures: HashMap<TranscriptId, TranscriptSignature>,
+
+    /// Transcripts with exon/CDS lines on more than one chromosome or strand,
+    /// kept using the chromosome and strand of their first exon/CDS line.
+    pub locus_conflicts: HashSet<TranscriptId>,
+
+    /// Transcripts filtered while reading, which are not in `signatures`.
+    pub filtered: HashMap<TranscriptId, FilterReason>,
+
+    /// Warnings found while reading.
+    pub warnings: Vec<Warning>,
+}
+
+/// How transcripts with exon/CDS lines on more than one chromosome or strand
+/// are handled.
+///
+/// These can be caused by reusing the same transcript ID for different
+/// transcripts or genuine trans-spliced transcripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LocusConflict {
+    /// Return an error.
+    Error,
+
+    /// Split into a separate transcript for each chromosome and strand.
+    Split,
+
+    /// Keep the chromosome and strand of the first exon/CDS line, adding a
+    /// "tuni_flag" attribute to the outputted lines.
+    #[default]
+    Flag,
+}
+
+/// Options controlling how `TranscriptSignature`s are built when reading
+/// GTF/GFFs.
+#[derive(Debug, Default)]
+pub struct ReadOptions {
+    /// Normalise chromosome names.
+    pub chr_aliases: ChrAliases,
+
+    /// Handle transcripts on more than one chromosome or strand.
+    pub locus_conflict: LocusConflict,
+
+    /// Only read and write lines on these (normalised) contigs, processing
+    /// one shard of the genome. By default, every contig is included.
+    pub contigs: Option<HashSet<Arc<str>>>,
+
+    /// Directory in which the lines of each GTF/GFF are cached when read, so
+    /// that the original GTF/GFF is not read and parsed again when written.
+    /// By default, GTF/GFFs are read again when written.
+    pub line_cache: Option<PathBuf>,
+
+    /// Transcripts outside of the included contigs and regions are filtered.
+    pub region_filter: RegionFilter,
+
+    /// Transcripts failing the structure requirements are filtered.
+    pub structure_filter: StructureFilter,
+
+    /// Transcripts whose attributes fail the expression are filtered.
+    pub attribute_filter: Option<AttributeFilter>,
+}
+
+impl ReadOptions {
+    /// Returns true if lines on the (normalised) chromosome `chr` are read and
+    /// written, otherwise false.
+    pub fn includes_contig(&self, chr: &Arc<str>) -> bool {
+        self.contigs.as_ref().is_none_or(|x| x.contains(chr))
+    }
+
+    /// Returns true if a (non-header) GTF/GFF line split by tabs is written,
+    /// otherwise false. Lines on other contigs belong to a different shard.
+    fn includes_line(&self, line_split: &[&str]) -> bool {
+        self.contigs.is_none()
+            || self.includes_contig(&self.chr_aliases.normalise(Arc::from(line_split[0])))
+    }
+
+    /// Returns true if any transcripts could be filtered while reading,
+    /// otherwise false.
+    fn is_filtering(&self) -> bool {
+        self.region_filter.is_active()
+            || self.structure_filter.is_active()
+            || self.attribute_filter.is_some()
+    }
+
+    /// Obtain the path of the cached lines of a GTF/GFF, if lines are cached.
+    ///
+    /// # Errors
+    ///
+    /// See [`extract_file_name`].
+    fn line_cache_path(&self, gtf_gff_path: &Path) -> Result<Option<PathBuf>, GtfGffError> {
+        self.line_cache
+            .as_ref()
+            .map(|x| Ok(x.join(format!("{}.lines", extract_file_name(gtf_gff_path)?))))
+            .transpose()
+    }
+}
+
+/// Input line number of each line of a unified GTF/GFF written for one shard
+/// (see [`ReadOptions::contigs`]), so that shards can be merged in the order
+/// of the input GTF/GFF.
+///
+/// Written as the footer of the unified GTF/GFF, e.g.
+/// "#tuni_shard_lines: 1-3,3-8,12", listing runs of consecutive line numbers.
+/// Lines added by `tuni` (e.g. the region filter header) take the number of
+/// the input line they are written before.
+#[derive(Debug, Default, PartialEq)]
+pub struct ShardLines {
+    /// First and last line number of each run.
+    runs: Vec<(usize, usize)>,
+}
+
+impl ShardLines {
+    /// Prefix of the footer.
+    pub const PREFIX: &'static str = "#tuni_shard_lines: ";
+
+    /// Add the line number of the next line written.
+    fn push(&mut self, line_number: usize) {
+        match self.runs.last_mut() {
+            Some((_, end)) if *end + 1 == line_number => *end = line_number,
+            _ => self.runs.push((line_number, line_number)),
+        }
+    }
+
+    /// Parse the footer of a shard's unified GTF/GFF.
+    ///
+    /// Returns `None` if `line` is not a footer.
+    pub fn from_footer(line: &str) -> Option<ShardLines> {
+        let runs = line.strip_prefix(ShardLines::PREFIX)?;
+        let runs = runs
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|run| {
+                let (start, end) = run.split_once('-').unwrap_or((run, run));
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                (start <= end).then_some((start, end))
+            })
+            .collect::<Option<Vec<(usize, usize)>>>()?;
+        Some(ShardLines { runs })
+    }
+
+    /// Iterate over the line number of each line, in the order written.
+    pub fn line_numbers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.runs.iter().flat_map(|(start, end)| *start..=*end)
+    }
+}
+
+impl fmt::Display for ShardLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ShardLines::PREFIX)?;
+        for (i, (start, end)) in self.runs.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match start == end {
+                true => write!(f, "{}", start)?,
+                false => write!(f, "{}-{}", start, end)?,
+            }
+        }
         Ok(())
     }
 }
@@ -85,45 +473,55 @@ impl TranscriptSignature {
 /// `GtfGffRecord` requires a `transcript_id`. In `tuni`, this is satisfied as
 /// `GtfGffRecord` are only created from "exon"/"CDS" lines, which should always
 /// contain a `transcript_id`.
+///
+/// Fields borrow from the line, avoiding an allocation per field.
 #[derive(Debug, PartialEq)]
-struct GtfGffRecord {
+struct GtfGffRecord<'a> {
     /// Feature e.g. "exon", "transcript", "CDS".
-    feature: Rc<str>,
+    feature: &'a str,
 
     /// Strand.
-    strand: Rc<str>,
+    strand: &'a str,
 
     /// Chromosome.
-    chr: Rc<str>,
+    chr: &'a str,
 
     /// Start coordinate.
-    start: Rc<str>,
+    start: Coordinate,
 
     /// End coordinate.
-    end: Rc<str>,
+    end: Coordinate,
 
     /// Transcript ID.
-    transcript_id: Rc<str>,
+    transcript_id: &'a str,
 }
 
-impl GtfGffRecord {
+impl<'a> GtfGffRecord<'a> {
     /// Create a `GtfGffRecord` from a line.
     ///
     /// # Errors
     ///
     /// Returns [`MissingTranscriptIdError`](GtfGffError::MissingTranscriptIdError)
     /// if the line does not contain a "transcript_id" attribute.
-    fn from(line_split: &[&str]) -> Result<GtfGffRecord, GtfGffError> {
+    ///
+    /// Returns [`InvalidCoordinateError`](GtfGffError::InvalidCoordinateError)
+    /// if the start or end is not a non-negative integer.
+    fn from(line_split: &[&'a str]) -> Result<GtfGffRecord<'a>, GtfGffError> {
         let transcript_id = GtfGffRecord::get_transcript_id(line_split)
             .ok_or(GtfGffError::MissingTranscriptIdError(line_split.join("\t")))?;
+        let parse_coordinate = |coordinate: &str| {
+            coordinate
+                .parse::<Coordinate>()
+                .map_err(|_| GtfGffError::InvalidCoordinateError(line_split.join("\t")))
+        };
 
         Ok(GtfGffRecord {
-            chr: Rc::from(line_split[0]),
-            feature: Rc::from(line_split[2]),
-            strand: Rc::from(line_split[6]),
-            start: Rc::from(line_split[3]),
-            end: Rc::from(line_split[4]),
-            transcript_id: Rc::from(transcript_id),
+            chr: line_split[0],
+            feature: line_split[2],
+            strand: line_split[6],
+            start: parse_coordinate(line_split[3])?,
+            end: parse_coordinate(line_split[4])?,
+            transcript_id,
         })
     }
 
@@ -136,11 +534,22 @@ impl GtfGffRecord {
     ///
     /// This relies on transcript ID attributes being named exactly
     /// "transcript_id".
-    fn get_transcript_id<'a>(line_split: &[&'a str]) -> Option<&'a str> {
+    fn get_transcript_id(line_split: &[&'a str]) -> Option<&'a str> {
         line_split[8]
             .split(';')
             .find(|x| x.trim().starts_with("transcript_id"))
     }
+
+    /// Obtain the value of an attribute, with any surrounding quotes removed.
+    ///
+    /// Supports both GTF (`key "value";`) and GFF (`key=value;`) attributes.
+    fn get_attribute(line_split: &[&'a str], key: &str) -> Option<&'a str> {
+        line_split[8].split(';').find_map(|x| {
+            let value = x.trim().strip_prefix(key)?;
+            let value = value.strip_prefix(' ').or(value.strip_prefix('='))?;
+            Some(value.trim().trim_matches('"'))
+        })
+    }
 }
 
 /// Format outputted unified ID.
@@ -166,9 +575,19 @@ impl TuniIdFormatter {
 
     /// Format unified ID depending on input file type.
     fn format(&self, unified_id: &str) -> String {
+        self.format_attribute("tuni_id", unified_id)
+    }
+
+    /// Format the reason a transcript was filtered depending on input file type.
+    fn format_filtered(&self, filter_reason: FilterReason) -> String {
+        self.format_attribute("tuni_filtered", filter_reason.as_str())
+    }
+
+    /// Format an attribute depending on input file type.
+    fn format_attribute(&self, key: &str, value: &str) -> String {
         match self {
-            TuniIdFormatter::Gtf => format!(r#" tuni_id "{}";"#, unified_id),
-            TuniIdFormatter::Gff => format!(" tuni_id={};", unified_id),
+            TuniIdFormatter::Gtf => format!(r#" {} "{}";"#, key, value),
+            TuniIdFormatter::Gff => format!(" {}={};", key, value),
         }
     }
 }
@@ -176,50 +595,341 @@ impl TuniIdFormatter {
 /// Read unique transcripts from a GTF/GFF file.
 ///
 /// Using the "transcript_id" as a differentiating key, build a
-/// `TranscriptSignature` for every unique transcript.
+/// `TranscriptSignature` for every unique transcript. Chromosome names are
+/// normalised within the `TranscriptSignature` only, leaving the GTF/GFF lines
+/// unchanged.
+///
+/// Transcripts on more than one chromosome or strand are handled according to
+/// `read_options.locus_conflict`. When split, each part is keyed by a
+/// transcript ID including its chromosome and strand, e.g.
+/// "transcript_id \"A\"|chr1:+".
+///
+/// Transcripts failing the region, structure or attribute filters of
+/// `read_options` are recorded in [`GtfGffTranscripts::filtered`] instead.
 ///
 /// # Errors
 ///
+/// Returns [`FileOpenError`](GtfGffError::FileOpenError) if the GTF/GFF cannot
+/// be opened.
+///
+/// Returns [`InvalidPathError`](GtfGffError::InvalidPathError) if the GTF/GFF
+/// path does not end in a valid file name.
+///
 /// Returns [`LineReadError`](GtfGffError::LineReadError) if any line in the
 /// GTF/GFF cannot be read.
+///
+/// Returns [`LocusConflictError`](GtfGffError::LocusConflictError) if a
+/// transcript is on more than one chromosome or strand and
+/// `read_options.locus_conflict` is [`LocusConflict::Error`].
 pub fn read_gtf_gff(
     gtf_gff_path: &Path,
-) -> Result<HashMap<TranscriptId, TranscriptSignature>, GtfGffError> {
+    read_options: &ReadOptions,
+) -> Result<GtfGffTranscripts, GtfGffError> {
     info!("{}", gtf_gff_path.display());
 
-    let reader = open_gtf_gff_reader(gtf_gff_path);
-    let mut gtf_gff_transcripts: HashMap<TranscriptId, TranscriptSignature> = HashMap::new();
-
-    for line in reader.lines() {
+    let reader = open_gtf_gff_reader(gtf_gff_path)?;
+    let mut gtf_gff_transcripts = GtfGffTranscripts::default();
+    let mut transcripts: HashMap<TranscriptId, TranscriptBuilder> = HashMap::new();
+    let mut split_transcripts: HashSet<TranscriptId> = HashSet::new();
+    let mut attribute_values: HashMap<TranscriptId, Vec<Option<Box<str>>>> = HashMap::new();
+    let mut chrs = Interner::default();
+    let mut strands = Interner::default();
+    let mut transcript_ids = Interner::default();
+    let mut line_cache = read_options
+        .line_cache_path(gtf_gff_path)?
+        .map(LineCacheWriter::create)
+        .transpose()?;
+
+    for (i, line) in reader.lines().enumerate() {
         let line = line.map_err(|_| GtfGffError::LineReadError(gtf_gff_path.to_path_buf()))?;
 
-        if !line.starts_with('#') {
+        if line.starts_with('#') {
+            if let Some(line_cache) = &mut line_cache {
+                line_cache.write(i + 1, &[&line], None)?;
+            }
+        } else {
             let line_split = line.split('\t').collect::<Vec<&str>>();
 
+            if let Some(line_cache) = &mut line_cache {
+                if read_options.includes_line(&line_split) {
+                    let transcript_id = GtfGffRecord::get_transcript_id(&line_split);
+                    line_cache.write(i + 1, &line_split, transcript_id)?;
+                }
+            }
+
+            if let Some(attribute_filter) = &read_options.attribute_filter {
+                if let Some(transcript_id) = GtfGffRecord::get_transcript_id(&line_split) {
+                    let keys = attribute_filter.keys();
+                    let transcript_id =
+                        transcript_ids.intern(transcript_id, || Arc::from(transcript_id));
+                    let values = attribute_values
+                        .entry(transcript_id)
+                        .or_insert_with(|| vec![None; keys.len()]);
+                    // Keep the first value of each attribute.
+                    for (key, value) in keys.iter().zip(values.iter_mut()) {
+                        if value.is_none() {
+                            *value = GtfGffRecord::get_attribute(&line_split, key).map(Box::from);
+                        }
+                    }
+                }
+            }
+
             if GtfGffRecord::is_exon_or_cds(&line_split) {
                 let record = GtfGffRecord::from(&line_split)?;
+                let chr = chrs.intern(record.chr, || {
+                    read_options.chr_aliases.normalise(Arc::from(record.chr))
+                });
+                if !read_options.includes_contig(&chr) {
+                    continue;
+                }
+                let strand = strands.intern(record.strand, || Arc::from(record.strand));
+
+                let mut transcript_id = match split_transcripts.contains(record.transcript_id) {
+                    true => split_transcript_id(record.transcript_id, &chr, &strand),
+                    false => transcript_ids
+                        .intern(record.transcript_id, || Arc::from(record.transcript_id)),
+                };
+
+                let is_conflict = transcripts
+                    .get(&transcript_id)
+                    .is_some_and(|x| !x.is_on(&chr, &strand));
+
+                if is_conflict {
+                    match read_options.locus_conflict {
+                        LocusConflict::Error => {
+                            return Err(GtfGffError::LocusConflictError(transcript_id.to_string()))
+                        }
+                        LocusConflict::Flag => {
+                            if gtf_gff_transcripts
+                                .locus_conflicts
+                                .insert(Arc::clone(&transcript_id))
+                            {
+                                gtf_gff_transcripts.warnings.push(Warning {
+                                    category: WarningCategory::LocusConflict,
+                                    message: extract_transcript_name(&transcript_id).to_string(),
+                                });
+                            }
+                        }
+                        LocusConflict::Split => {
+                            // Split keys include the chromosome and strand, so
+                            // only unsplit transcripts can conflict. Re-key the
+                            // existing part by its own chromosome and strand.
+                            split_transcripts.insert(Arc::clone(&transcript_id));
+                            gtf_gff_transcripts.warnings.push(Warning {
+                                category: WarningCategory::LocusConflict,
+                                message: extract_transcript_name(&transcript_id).to_string(),
+                            });
+                            let existing = transcripts.remove(&transcript_id).unwrap();
+                            transcripts.insert(
+                                split_transcript_id(
+                                    &transcript_id,
+                                    &existing.chr,
+                                    &existing.strand,
+                                ),
+                                existing,
+                            );
+                            transcript_id = split_transcript_id(&transcript_id, &chr, &strand);
+                        }
+                    }
+                }
 
                 // Only insert chromosome and strand once, upon initialisation.
-                let transcript_signature = gtf_gff_transcripts
-                    .entry(record.transcript_id)
-                    .or_insert(TranscriptSignature::from(
-                        record.chr,
-                        record.strand,
-                        BTreeSet::new(),
-                        BTreeSet::new(),
-                    ));
-
-                transcript_signature.insert_boundary(&record.feature, record.start)?;
-                transcript_signature.insert_boundary(&record.feature, record.end)?;
+                transcripts
+                    .entry(transcript_id)
+                    .or_insert_with(|| TranscriptBuilder::from(chr, strand))
+                    .insert_boundaries(&record)?;
             }
         }
     }
 
+    if let Some(line_cache) = line_cache {
+        line_cache.finish()?;
+    }
+
+    if read_options.is_filtering() {
+        let mut filtered = Vec::new();
+        // Warn once per transcript, sorted so that examples do not depend on
+        // the order of `transcripts`.
+        let mut non_numeric = BTreeSet::new();
+        for (transcript_id, transcript) in &transcripts {
+            // Parts of split transcripts share the attributes of the transcript.
+            // Without an attribute filter, no attribute values are collected.
+            let values = match &read_options.attribute_filter {
+                Some(attribute_filter) => {
+                    let attribute_id = transcript_id
+                        .rsplit_once('|')
+                        .map(|(x, _)| x)
+                        .filter(|x| split_transcripts.contains(*x))
+                        .unwrap_or(transcript_id);
+                    let values = attribute_values
+                        .get(attribute_id)
+                        .map(|x| {
+                            x.iter()
+                                .map(|x| x.as_deref())
+                                .collect::<Vec<Option<&str>>>()
+                        })
+                        .unwrap_or_default();
+                    for (key, value) in attribute_filter.non_numeric_values(&values) {
+                        non_numeric.insert(format!(
+                            "{} ({} {:?})",
+                            extract_transcript_name(attribute_id),
+                            key,
+                            value
+                        ));
+                    }
+                    values
+                }
+                None => Vec::new(),
+            };
+
+            if let Some(filter_reason) = transcript.filter_reason(read_options, &values) {
+                filtered.push((Arc::clone(transcript_id), filter_reason));
+            }
+        }
+
+        for (transcript_id, filter_reason) in filtered {
+            transcripts.remove(&transcript_id);
+            gtf_gff_transcripts
+                .filtered
+                .insert(transcript_id, filter_reason);
+        }
+        gtf_gff_transcripts
+            .warnings
+            .extend(non_numeric.into_iter().map(|message| Warning {
+                category: WarningCategory::NonNumericAttribute,
+                message,
+            }));
+    }
+
+    gtf_gff_transcripts.signatures = transcripts
+        .into_iter()
+        .map(|(transcript_id, transcript)| (transcript_id, transcript.build()))
+        .collect();
+
     Ok(gtf_gff_transcripts)
 }
 
+/// Read transcripts from each GTF/GFF in `gtf_gff_paths` in parallel, using
+/// the current rayon thread pool.
+///
+/// Transcripts are returned in the same order as `gtf_gff_paths`, regardless
+/// of the number of threads.
+///
+/// # Errors
+///
+/// See [`read_gtf_gff`].
+pub fn read_gtf_gffs(
+    gtf_gff_paths: &[PathBuf],
+    read_options: &ReadOptions,
+) -> Result<Vec<GtfGffTranscripts>, GtfGffError> {
+    gtf_gff_paths
+        .par_iter()
+        .map(|gtf_gff_path| read_gtf_gff(gtf_gff_path, read_options))
+        .collect()
+}
+
+/// Create the transcript ID of the part of a split transcript on a given
+/// chromosome and strand.
+///
+/// "transcript_id \"A\"" -> "transcript_id \"A\"|chr1:+"
+pub(crate) fn split_transcript_id(transcript_id: &str, chr: &str, strand: &str) -> TranscriptId {
+    Arc::from(format!("{}|{}:{}", transcript_id, chr, strand))
+}
+
+/// Read the value of an attribute for each transcript in a GTF/GFF file.
+///
+/// Only lines of `feature` are read if provided, otherwise all lines are read.
+/// The first value found for each transcript is kept, and transcripts without
+/// the attribute are skipped. Lines are read from the line cache when
+/// `read_options.line_cache` is set, see [`read_gtf_gff`].
+///
+/// # Errors
+///
+/// Returns [`FileOpenError`](GtfGffError::FileOpenError) if the GTF/GFF cannot
+/// be opened.
+///
+/// Returns [`LineReadError`](GtfGffError::LineReadError) if any line in the
+/// GTF/GFF or line cache cannot be read.
+pub fn read_attribute(
+    gtf_gff_path: &Path,
+    attribute: &str,
+    feature: Option<&str>,
+    read_options: &ReadOptions,
+) -> Result<HashMap<TranscriptId, Arc<str>>, GtfGffError> {
+    let mut transcript_values: HashMap<TranscriptId, Arc<str>> = HashMap::new();
+
+    for_each_line(
+        gtf_gff_path,
+        read_options,
+        |_, line_split, transcript_id| {
+            let Some(transcript_id) = transcript_id else {
+                return Ok(());
+            };
+            if transcript_values.contains_key(transcript_id) {
+                return Ok(());
+            }
+
+            if feature.is_some_and(|x| x != line_split[2]) {
+                return Ok(());
+            }
+
+            if let Some(value) = GtfGffRecord::get_attribute(line_split, attribute) {
+                transcript_values.insert(Arc::from(transcript_id), Arc::from(value));
+            }
+            Ok(())
+        },
+    )?;
+
+    Ok(transcript_values)
+}
+
+/// Read a numeric attribute (e.g. "TPM") from the "transcript" lines of a
+/// GTF/GFF file, see [`read_attribute`].
+///
+/// Transcripts without the attribute are skipped.
+///
+/// # Errors
+///
+/// Returns [`FileOpenError`](GtfGffError::FileOpenError) if the GTF/GFF cannot
+/// be opened.
+///
+/// Returns [`LineReadError`](GtfGffError::LineReadError) if any line in the
+/// GTF/GFF or line cache cannot be read.
+///
+/// Returns [`NonNumericAttributeError`](GtfGffError::NonNumericAttributeError)
+/// if the attribute value is not a number.
+pub fn read_expression(
+    gtf_gff_path: &Path,
+    attribute: &str,
+    read_options: &ReadOptions,
+) -> Result<HashMap<TranscriptId, f64>, GtfGffError> {
+    read_attribute(gtf_gff_path, attribute, Some("transcript"), read_options)?
+        .into_iter()
+        .map(|(transcript_id, value)| {
+            let value = value
+                .parse::<f64>()
+                .map_err(|_| GtfGffError::NonNumericAttributeError(value.to_string()))?;
+            Ok((transcript_id, value))
+        })
+        .collect()
+}
+
 /// Write GTF/GFF file with unified transcript IDs.
 ///
+/// Lines of filtered transcripts are tagged with the filter reason or dropped,
+/// depending on `filter_action`. Transcripts are identified using the same
+/// `read_options` used by [`read_gtf_gff`]. When dropping, lines without a
+/// known transcript (e.g. genes) are also dropped if they lie outside of the
+/// `read_options` region filter. When only some contigs are written (see
+/// [`ReadOptions::contigs`]), a [`ShardLines`] footer is added.
+///
+/// Lines are written to a temporary file, which is renamed to the output file
+/// only once complete. Writing stops if `cancelled` is set, e.g. by another
+/// failed output, removing the temporary file.
+///
+/// Returns warnings for lines with an unrecognised transcript ID, i.e.
+/// transcripts without exon/CDS lines.
+///
 /// # Errors
 ///
 /// Returns [`UnknownExtensionError`](GtfGffError::UnknownExtensionError) if any
@@ -228,18 +938,30 @@ pub fn read_gtf_gff(
 /// Returns [`FileCreateError`](GtfGffError::FileCreateError) if the output file
 /// cannot be be created.
 ///
+/// Returns [`FileOpenError`](GtfGffError::FileOpenError) if the GTF/GFF cannot
+/// be opened.
+///
+/// Returns [`InvalidPathError`](GtfGffError::InvalidPathError) if the GTF/GFF
+/// path does not end in a valid file name.
+///
 /// Returns [`LineReadError`](GtfGffError::LineReadError) if any line in the
 /// GTF/GFF cannot be read.
 ///
 /// Returns [`FileWriteError`](GtfGffError::FileWriteError) if any line in
 /// the output GTF/GFF cannot be written.
+///
+/// Returns [`CancelledError`](GtfGffError::CancelledError) if `cancelled` is
+/// set before the output file is complete.
 pub fn write_unified_gtf_gff(
     gtf_gff_extension: &str,
     gtf_gff_path: &Path,
     output_dir: &Path,
     transcript_unifier: &TranscriptUnifier,
-) -> Result<(), GtfGffError> {
-    let gtf_gff_file_name = extract_file_name(gtf_gff_path);
+    read_options: &ReadOptions,
+    filter_action: FilterAction,
+    cancelled: &AtomicBool,
+) -> Result<Vec<Warning>, GtfGffError> {
+    let gtf_gff_file_name = extract_file_name(gtf_gff_path)?;
 
     let mut output_path = output_dir.to_path_buf();
     output_path.push(gtf_gff_file_name.to_string());
@@ -247,52 +969,329 @@ pub fn write_unified_gtf_gff(
 
     info!("{}", output_path.display());
 
-    let reader = open_gtf_gff_reader(gtf_gff_path);
-    let mut writer = open_gtf_gff_writer(&output_path)?;
+    let mut temp_path = output_path.clone().into_os_string();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = write_unified_lines(
+        gtf_gff_extension,
+        gtf_gff_path,
+        &temp_path,
+        transcript_unifier,
+        read_options,
+        filter_action,
+        cancelled,
+    )
+    .and_then(|warnings| {
+        fs::rename(&temp_path, &output_path)
+            .map_err(|_| GtfGffError::FileCreateError(output_path.clone()))?;
+        Ok(warnings)
+    });
+
+    if result.is_err() {
+        // The temporary file may not have been created.
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Write GTF/GFF files with unified transcript IDs in parallel, using the
+/// current rayon thread pool. See [`write_unified_gtf_gff`].
+///
+/// If writing any file fails, the remaining files are cancelled. Files that
+/// are not complete are removed, so no partially written files are left.
+/// Warnings are added to `warnings` in the order of `gtf_gff_paths`.
+///
+/// # Errors
+///
+/// Returns the error of the first file in `gtf_gff_paths` that failed, see
+/// [`write_unified_gtf_gff`].
+pub fn write_unified_gtf_gffs(
+    gtf_gff_extension: &str,
+    gtf_gff_paths: &[PathBuf],
+    output_dir: &Path,
+    transcript_unifier: &TranscriptUnifier,
+    read_options: &ReadOptions,
+    filter_action: FilterAction,
+    warnings: &mut Warnings,
+) -> Result<(), GtfGffError> {
+    let cancelled = AtomicBool::new(false);
+
+    let results = gtf_gff_paths
+        .par_iter()
+        .map(|gtf_gff_path| {
+            write_unified_gtf_gff(
+                gtf_gff_extension,
+                gtf_gff_path,
+                output_dir,
+                transcript_unifier,
+                read_options,
+                filter_action,
+                &cancelled,
+            )
+            .inspect_err(|_| cancelled.store(true, Ordering::Relaxed))
+        })
+        .collect::<Vec<Result<Vec<Warning>, GtfGffError>>>();
+
+    // Report the failure that caused the cancellation, rather than the
+    // cancellations themselves.
+    let mut cancelled_error = None;
+    let mut sample_warnings = Vec::new();
+    for (gtf_gff_path, result) in gtf_gff_paths.iter().zip(results) {
+        match result {
+            Ok(warnings) => sample_warnings.push((extract_file_name(gtf_gff_path)?, warnings)),
+            Err(e @ GtfGffError::CancelledError(_)) => {
+                cancelled_error.get_or_insert(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if let Some(e) = cancelled_error {
+        return Err(e);
+    }
+
+    for (gtf_gff_file_name, file_warnings) in sample_warnings {
+        warnings.extend(&gtf_gff_file_name, file_warnings);
+    }
+
+    Ok(())
+}
+
+/// Write each line of the GTF/GFF to `output_path`, adding unified transcript
+/// IDs. See [`write_unified_gtf_gff`].
+fn write_unified_lines(
+    gtf_gff_extension: &str,
+    gtf_gff_path: &Path,
+    output_path: &Path,
+    transcript_unifier: &TranscriptUnifier,
+    read_options: &ReadOptions,
+    filter_action: FilterAction,
+    cancelled: &AtomicBool,
+) -> Result<Vec<Warning>, GtfGffError> {
+    let gtf_gff_file_name = extract_file_name(gtf_gff_path)?;
+
+    let mut writer = open_gtf_gff_writer(output_path)?;
+    let write_error = |_| GtfGffError::FileWriteError(output_path.to_path_buf());
 
     let tuni_id_formatter = TuniIdFormatter::from(gtf_gff_extension)?;
+    let mut warnings = Vec::new();
+
+    // Record how lines outside of the included contigs and regions are handled,
+    // after any leading directives (e.g. "##gff-version 3" must be first).
+    let region_filter = &read_options.region_filter;
+    let mut region_header = region_filter.is_active().then(|| {
+        let action = match filter_action {
+            FilterAction::Tag => "tagged",
+            FilterAction::Drop => "dropped",
+        };
+        format!(
+            "#tuni_region_filter: {}; out-of-region lines {}",
+            region_filter, action
+        )
+    });
+
+    // Lines of a shard record their input line numbers, see `ShardLines`.
+    let mut shard_lines = read_options.contigs.is_some().then(ShardLines::default);
+    let mut n_directives = 0;
+
+    for_each_line(
+        gtf_gff_path,
+        read_options,
+        |line_number, line_split, transcript_id| {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(GtfGffError::CancelledError(output_path.to_path_buf()));
+            }
 
-    for line in reader.lines() {
-        let mut line = line.map_err(|_| GtfGffError::LineReadError(gtf_gff_path.to_path_buf()))?;
+            if region_header.is_some() {
+                if line_number == n_directives + 1 && line_split[0].starts_with("##") {
+                    n_directives += 1;
+                } else {
+                    writeln!(writer, "{}", region_header.take().unwrap()).map_err(write_error)?;
+                    if let Some(shard_lines) = &mut shard_lines {
+                        shard_lines.push(n_directives + 1);
+                    }
+                }
+            }
 
-        if !line.starts_with('#') {
-            let line_split = line.split('\t').collect::<Vec<&str>>();
-            let transcript_id = GtfGffRecord::get_transcript_id(&line_split);
+            let mut attributes = String::new();
+
+            // Lines without a known transcript (e.g. genes) are dropped by position.
+            let is_dropped_by_position = || {
+                if !region_filter.is_active() || filter_action == FilterAction::Tag {
+                    return false;
+                }
+                let chr = read_options.chr_aliases.normalise(Arc::from(line_split[0]));
+                let coordinate = |i: usize| line_split.get(i)?.parse::<Coordinate>().ok();
+                let span = coordinate(3).zip(coordinate(4));
+                span.is_none_or(|(start, end)| !region_filter.includes(&chr, start, end))
+            };
+
+            if transcript_id.is_none()
+                && !line_split[0].starts_with('#')
+                && is_dropped_by_position()
+            {
+                return Ok(());
+            }
 
             if let Some(transcript_id) = transcript_id {
-                let unified_id = transcript_unifier
-                    .get_unified_id(&[Rc::clone(&gtf_gff_file_name), Rc::from(transcript_id)]);
+                let split_id;
+                let mut keyed_transcript_id = transcript_id;
+
+                // Parts of split transcripts are keyed by chromosome and strand.
+                if read_options.locus_conflict == LocusConflict::Split
+                    && !transcript_unifier.is_known(&gtf_gff_file_name, keyed_transcript_id)
+                {
+                    let chr = read_options.chr_aliases.normalise(Arc::from(line_split[0]));
+                    split_id = split_transcript_id(transcript_id, &chr, line_split[6]);
+                    keyed_transcript_id = &split_id;
+                }
+
+                let unified_id =
+                    transcript_unifier.get_unified_id(&gtf_gff_file_name, keyed_transcript_id);
+                let has_locus_conflict =
+                    transcript_unifier.has_locus_conflict(&gtf_gff_file_name, keyed_transcript_id);
 
                 match unified_id {
-                    Some(unified_id) => line.push_str(&tuni_id_formatter.format(unified_id)),
-                    None => warn!("Unrecognised transcript ID found {}", transcript_id),
+                    Some(unified_id) => attributes.push_str(&tuni_id_formatter.format(unified_id)),
+                    None => match transcript_unifier
+                        .get_filter_reason(&gtf_gff_file_name, keyed_transcript_id)
+                    {
+                        Some(_) if filter_action == FilterAction::Drop => return Ok(()),
+                        Some(filter_reason) => {
+                            attributes.push_str(&tuni_id_formatter.format_filtered(filter_reason))
+                        }
+                        None if is_dropped_by_position() => return Ok(()),
+                        None => warnings.push(Warning {
+                            category: WarningCategory::UnrecognisedTranscript,
+                            message: extract_transcript_name(transcript_id).to_string(),
+                        }),
+                    },
+                }
+
+                if has_locus_conflict {
+                    attributes.push_str(
+                        &tuni_id_formatter.format_attribute("tuni_flag", "locus_conflict"),
+                    );
                 }
             }
+
+            if let Some(shard_lines) = &mut shard_lines {
+                shard_lines.push(line_number);
+            }
+            write_fields(&mut writer, line_split)
+                .and_then(|_| writeln!(writer, "{}", attributes))
+                .map_err(write_error)
+        },
+    )?;
+
+    if let Some(region_header) = region_header {
+        writeln!(writer, "{}", region_header).map_err(write_error)?;
+        if let Some(shard_lines) = &mut shard_lines {
+            shard_lines.push(n_directives + 1);
         }
+    }
+    if let Some(shard_lines) = shard_lines {
+        writeln!(writer, "{}", shard_lines).map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
 
-        writeln!(writer, "{}", line)
-            .map_err(|_| GtfGffError::FileWriteError(output_path.clone()))?;
+    Ok(warnings)
+}
+
+/// Call `f` with the number (from 1) and fields of each line of the GTF/GFF to
+/// be written and its transcript ID, if any.
+///
+/// Lines are read from the line cache when `read_options.line_cache` is set,
+/// otherwise from the GTF/GFF. Lines on contigs excluded by
+/// `read_options.contigs` are skipped. Header lines are a single field.
+///
+/// # Errors
+///
+/// Returns [`FileOpenError`](GtfGffError::FileOpenError) if the GTF/GFF cannot
+/// be opened.
+///
+/// Returns [`LineReadError`](GtfGffError::LineReadError) if any line in the
+/// GTF/GFF or line cache cannot be read.
+///
+/// Returns any error returned by `f`.
+fn for_each_line(
+    gtf_gff_path: &Path,
+    read_options: &ReadOptions,
+    mut f: impl FnMut(usize, &[&str], Option<&str>) -> Result<(), GtfGffError>,
+) -> Result<(), GtfGffError> {
+    if let Some(line_cache_path) = read_options.line_cache_path(gtf_gff_path)? {
+        return read_line_cache(&line_cache_path, f);
+    }
+
+    let reader = open_gtf_gff_reader(gtf_gff_path)?;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|_| GtfGffError::LineReadError(gtf_gff_path.to_path_buf()))?;
+
+        if line.starts_with('#') {
+            f(i + 1, &[&line], None)?;
+            continue;
+        }
+
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+        if read_options.includes_line(&line_split) {
+            f(
+                i + 1,
+                &line_split,
+                GtfGffRecord::get_transcript_id(&line_split),
+            )?;
+        }
     }
 
     Ok(())
 }
 
+/// Write the fields of a line separated by tabs, without a newline.
+fn write_fields(writer: &mut impl Write, fields: &[&str]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b"\t")?;
+        }
+        writer.write_all(field.as_bytes())?;
+    }
+    Ok(())
+}
+
 /// Isolate only the GTF/GFF file name from full path.
 ///
 /// "/path/to/a.gtf" -> "a.gtf"
-pub fn extract_file_name(gtf_gff_path: &Path) -> Rc<str> {
-    // We have already checked GTF/GFF paths are valid files
-    // with a ".gtf"/".gff" extension during cli argument parsing.
-    Rc::from(gtf_gff_path.file_name().unwrap().to_str().unwrap())
+///
+/// # Errors
+///
+/// Returns [`InvalidPathError`](GtfGffError::InvalidPathError) if the path
+/// does not end in a UTF-8 file name.
+pub fn extract_file_name(gtf_gff_path: &Path) -> Result<Arc<str>, GtfGffError> {
+    gtf_gff_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .map(Arc::from)
+        .ok_or_else(|| GtfGffError::InvalidPathError(gtf_gff_path.to_path_buf()))
+}
+
+/// Isolate only the transcript name from the transcript ID.
+///
+/// "transcript_id \"A.1\"" -> "A.1", "transcript_id=A.1" -> "A.1"
+pub fn extract_transcript_name(transcript_id: &str) -> &str {
+    transcript_id
+        .trim()
+        .trim_start_matches("transcript_id")
+        .trim_start_matches([' ', '='])
+        .trim_matches('"')
 }
 
 /// Open reader that reads GTF/GFF line by line.
-fn open_gtf_gff_reader(gtf_gff_path: &Path) -> BufReader<File> {
-    // GTFs are checked to exist/be readable during cli argument parsing.
-    let gtf_gff = File::open(gtf_gff_path).unwrap();
+fn open_gtf_gff_reader(gtf_gff_path: &Path) -> Result<BufReader<File>, GtfGffError> {
+    let gtf_gff = File::open(gtf_gff_path)
+        .map_err(|_| GtfGffError::FileOpenError(gtf_gff_path.to_path_buf()))?;
 
     // Avoid reading the entire file into memory at once.
-    BufReader::new(gtf_gff)
+    Ok(BufReader::new(gtf_gff))
 }
 
 /// Open writer that writes GTF/GFF line by line.
@@ -307,6 +1306,9 @@ mod tests {
     use std::path::PathBuf;
 
     use super::*;
+    use crate::chr_alias::ChrStyle;
+    use crate::filter::{CodingFilter, RecurrenceFilter};
+    use crate::region::Region;
     use rstest::rstest;
     use std::fs::read_to_string;
     use tempfile::tempdir;
@@ -319,12 +1321,12 @@ mod tests {
         assert_eq!(
             GtfGffRecord::from(&line_split).unwrap(),
             GtfGffRecord {
-                feature: Rc::from("exon"),
-                strand: Rc::from("+"),
-                chr: Rc::from("chr1"),
-                start: Rc::from("1"),
-                end: Rc::from("2"),
-                transcript_id: Rc::from("transcript_id \"A\""),
+                feature: "exon",
+                strand: "+",
+                chr: "chr1",
+                start: 1,
+                end: 2,
+                transcript_id: "transcript_id \"A\"",
             }
         );
 
@@ -369,33 +1371,195 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case(
+        r#"chr1	test	transcript	1	2	.	+	.	transcript_id "A"; TPM "1.5";"#,
+        Some("1.5")
+    )]
+    #[case(
+        r#"chr1	test	transcript	1	2	.	+	.	transcript_id=A; TPM=1.5;"#,
+        Some("1.5")
+    )]
+    #[case(
+        r#"chr1	test	transcript	1	2	.	+	.	transcript_id "A"; TPM_2 "1.5";"#,
+        None
+    )]
+    fn test_get_attribute(#[case] line: &str, #[case] expected: Option<&str>) {
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+
+        assert_eq!(GtfGffRecord::get_attribute(&line_split, "TPM"), expected);
+    }
+
+    #[rstest]
+    #[case("transcript_id \"A.1\"", "A.1")]
+    #[case(" transcript_id \"A.1\"", "A.1")]
+    #[case("transcript_id=A.1", "A.1")]
+    fn test_extract_transcript_name(#[case] transcript_id: &str, #[case] expected: &str) {
+        assert_eq!(extract_transcript_name(transcript_id), expected);
+    }
+
+    #[test]
+    fn test_read_missing_path() {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/missing.gtf");
+
+        assert!(matches!(
+            read_gtf_gff(&gtf_gff_path, &ReadOptions::default()),
+            Err(GtfGffError::FileOpenError(_))
+        ));
+        assert!(matches!(
+            read_attribute(&gtf_gff_path, "gene_id", None, &ReadOptions::default()),
+            Err(GtfGffError::FileOpenError(_))
+        ));
+        assert!(matches!(
+            read_expression(&gtf_gff_path, "TPM", &ReadOptions::default()),
+            Err(GtfGffError::FileOpenError(_))
+        ));
+        assert!(matches!(
+            crate::unify_gtf_gffs(
+                &[gtf_gff_path],
+                &Default::default(),
+                &mut Warnings::default()
+            ),
+            Err(GtfGffError::FileOpenError(_))
+        ));
+    }
+
+    #[rstest]
+    #[case("tests/data/unit/sample_1.gtf", Some("sample_1.gtf"))]
+    #[case("/", None)]
+    #[case("tests/data/unit/..", None)]
+    fn test_extract_file_name(#[case] gtf_gff_path: &str, #[case] expected: Option<&str>) {
+        let file_name = extract_file_name(Path::new(gtf_gff_path));
+        match expected {
+            Some(expected) => assert_eq!(file_name.unwrap().as_ref(), expected),
+            None => assert!(matches!(file_name, Err(GtfGffError::InvalidPathError(_)))),
+        }
+    }
+
     #[test]
-    fn test_transcript_signature() {
-        let mut transcript_signature = TranscriptSignature::from(
-            Rc::from("chr1"),
-            Rc::from("+"),
-            BTreeSet::new(),
-            BTreeSet::new(),
+    fn test_read_attribute() {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
+
+        assert_eq!(
+            read_attribute(&gtf_gff_path, "gene_id", None, &ReadOptions::default()).unwrap(),
+            HashMap::from([
+                (Arc::from("transcript_id \"A\""), Arc::from("A")),
+                (Arc::from("transcript_id \"B\""), Arc::from("B")),
+            ])
         );
+        assert!(read_attribute(
+            &gtf_gff_path,
+            "gene_id",
+            Some("transcript"),
+            &ReadOptions::default()
+        )
+        .unwrap()
+        .is_empty());
+    }
 
-        transcript_signature
-            .insert_boundary("exon", Rc::from("1"))
-            .unwrap();
-        transcript_signature
-            .insert_boundary("CDS", Rc::from("2"))
-            .unwrap();
+    #[test]
+    fn test_read_expression() {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_expression.gtf");
 
         assert_eq!(
-            transcript_signature.exon_boundaries,
-            BTreeSet::from([Rc::from("1")])
+            read_expression(&gtf_gff_path, "TPM", &ReadOptions::default()).unwrap(),
+            HashMap::from([
+                (Arc::from("transcript_id \"A\""), 2.5),
+                (Arc::from("transcript_id \"A_dup\""), 3.0),
+                (Arc::from("transcript_id \"B\""), 1.0),
+            ])
+        );
+        assert!(
+            read_expression(&gtf_gff_path, "transcript_id", &ReadOptions::default())
+                .is_err_and(|e| e.to_string().contains("must be numeric"))
+        );
+    }
+
+    // Test that attributes are read from the line cache once the GTF is
+    // removed, i.e. the GTF is only read once.
+    #[test]
+    fn test_read_expression_line_cache() {
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = tempdir().unwrap();
+        let gtf_gff_path = temp_dir.path().join("sample_expression.gtf");
+        fs::copy("tests/data/unit/sample_expression.gtf", &gtf_gff_path).unwrap();
+
+        let read_options = ReadOptions {
+            line_cache: Some(cache_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        read_gtf_gff(&gtf_gff_path, &read_options).unwrap();
+        fs::remove_file(&gtf_gff_path).unwrap();
+
+        assert_eq!(
+            read_expression(&gtf_gff_path, "TPM", &read_options).unwrap(),
+            HashMap::from([
+                (Arc::from("transcript_id \"A\""), 2.5),
+                (Arc::from("transcript_id \"A_dup\""), 3.0),
+                (Arc::from("transcript_id \"B\""), 1.0),
+            ])
         );
         assert_eq!(
-            transcript_signature.cds_boundaries,
-            BTreeSet::from([Rc::from("2")])
+            read_attribute(&gtf_gff_path, "TPM", Some("exon"), &read_options).unwrap(),
+            HashMap::new()
         );
-        assert!(transcript_signature
-            .insert_boundary("not_a_feature", Rc::from("1"))
-            .is_err_and(|e| e.to_string().contains("Feature must be 'exon' or 'CDS'")))
+    }
+
+    #[test]
+    fn test_transcript_signature() {
+        let record = |feature, start, end| GtfGffRecord {
+            feature,
+            strand: "+",
+            chr: "chr1",
+            start,
+            end,
+            transcript_id: "transcript_id \"A\"",
+        };
+        let mut transcript = TranscriptBuilder::from(Arc::from("chr1"), Arc::from("+"));
+
+        // Boundaries are sorted numerically and deduplicated.
+        transcript
+            .insert_boundaries(&record("exon", 20, 30))
+            .unwrap();
+        transcript
+            .insert_boundaries(&record("exon", 3, 20))
+            .unwrap();
+        transcript
+            .insert_boundaries(&record("CDS", 25, 29))
+            .unwrap();
+        assert!(transcript
+            .insert_boundaries(&record("not_a_feature", 1, 2))
+            .is_err_and(|e| e.to_string().contains("Feature must be 'exon' or 'CDS'")));
+
+        let transcript_signature = transcript.build();
+
+        assert_eq!(transcript_signature.exon_boundaries(), [3, 20, 30]);
+        assert_eq!(transcript_signature.cds_boundaries(), [25, 29]);
+        assert!(transcript_signature.has_cds());
+
+        let formatted = transcript_signature.to_string();
+        assert_eq!(formatted, "chr1\t+\t3,20,30\t25,29");
+        assert_eq!(
+            TranscriptSignature::from_fields(&formatted.split('\t').collect::<Vec<&str>>()),
+            Some(transcript_signature)
+        );
+    }
+
+    // Boundaries are compared before the number of exon boundaries.
+    #[rstest]
+    #[case("chr1\t+\t100,200\t", "chr1\t+\t1,2,10,20\t")]
+    #[case("chr1\t+\t1,2,10,20\t", "chr1\t+\t1,2\t")]
+    #[case("chr1\t+\t1,20\t5,10", "chr1\t+\t1,20\t")]
+    #[case("chr1\t+\t1,20\t5,10", "chr1\t+\t1,20\t4,10")]
+    #[case("chr1\t-\t1,20\t", "chr1\t+\t1,20\t")]
+    #[case("chr2\t+\t1,20\t", "chr10\t+\t1,2\t")]
+    fn test_transcript_signature_ord(#[case] greater: &str, #[case] less: &str) {
+        let signature = |x: &str| {
+            TranscriptSignature::from_fields(&x.split('\t').collect::<Vec<&str>>()).unwrap()
+        };
+
+        assert!(signature(greater) > signature(less));
+        assert_eq!(signature(less).cmp(&signature(less)), cmp::Ordering::Equal);
     }
 
     #[test]
@@ -403,43 +1567,240 @@ mod tests {
         let mut expected_transcripts: HashMap<TranscriptId, TranscriptSignature> = HashMap::new();
 
         expected_transcripts.insert(
-            Rc::from("transcript_id \"A\""),
-            TranscriptSignature::from(
-                Rc::from("chr1"),
-                Rc::from("-"),
-                BTreeSet::from([Rc::from("1"), Rc::from("12"), Rc::from("11"), Rc::from("2")]),
-                BTreeSet::new(),
-            ),
+            Arc::from("transcript_id \"A\""),
+            TranscriptSignature::from(Arc::from("chr1"), Arc::from("-"), [1, 12, 11, 2], []),
         );
 
         expected_transcripts.insert(
-            Rc::from("transcript_id \"B\""),
-            TranscriptSignature::from(
-                Rc::from("chr2"),
-                Rc::from("+"),
-                BTreeSet::from([Rc::from("20"), Rc::from("30")]),
-                BTreeSet::from([Rc::from("25"), Rc::from("29")]),
-            ),
+            Arc::from("transcript_id \"B\""),
+            TranscriptSignature::from(Arc::from("chr2"), Arc::from("+"), [20, 30], [25, 29]),
         );
 
         assert_eq!(
-            read_gtf_gff(&PathBuf::from("tests/data/unit/sample_1.gtf")).unwrap(),
+            read_gtf_gff(
+                &PathBuf::from("tests/data/unit/sample_1.gtf"),
+                &ReadOptions::default()
+            )
+            .unwrap()
+            .signatures,
             expected_transcripts
         )
     }
 
+    #[test]
+    fn test_read_gtf_gff_chr_aliases() {
+        let read_options = ReadOptions {
+            chr_aliases: ChrAliases::from(Some(ChrStyle::Ensembl), HashMap::new()),
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_1.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            gtf_gff_transcripts.signatures[&Arc::from("transcript_id \"A\"")].chr,
+            Arc::from("1")
+        );
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(4)]
+    fn test_read_gtf_gffs(#[case] threads: usize) {
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+            PathBuf::from("tests/data/unit/sample_redundant.gtf"),
+        ];
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let all_gtf_gff_transcripts = thread_pool
+            .install(|| read_gtf_gffs(&gtf_gff_paths, &ReadOptions::default()))
+            .unwrap();
+
+        // Transcripts are returned in the order of the paths.
+        for (gtf_gff_path, gtf_gff_transcripts) in gtf_gff_paths.iter().zip(all_gtf_gff_transcripts)
+        {
+            assert_eq!(
+                gtf_gff_transcripts,
+                read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap()
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(LocusConflict::Flag, 1, 1)]
+    #[case(LocusConflict::Split, 2, 0)]
+    fn test_read_gtf_gff_locus_conflict(
+        #[case] locus_conflict: LocusConflict,
+        #[case] expected_signatures: usize,
+        #[case] expected_conflicts: usize,
+    ) {
+        let read_options = ReadOptions {
+            locus_conflict,
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_locus_conflict.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        assert_eq!(gtf_gff_transcripts.signatures.len(), expected_signatures);
+        assert_eq!(
+            gtf_gff_transcripts.locus_conflicts.len(),
+            expected_conflicts
+        );
+    }
+
+    #[test]
+    fn test_read_gtf_gff_locus_conflict_error() {
+        let read_options = ReadOptions {
+            locus_conflict: LocusConflict::Error,
+            ..Default::default()
+        };
+
+        assert!(read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_locus_conflict.gtf"),
+            &read_options,
+        )
+        .is_err());
+    }
+
+    #[rstest]
+    #[case(LocusConflict::Flag, 2)]
+    #[case(LocusConflict::Split, 0)]
+    fn test_write_unified_gtf_locus_conflict(
+        #[case] locus_conflict: LocusConflict,
+        #[case] expected_flagged: usize,
+    ) {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_locus_conflict.gtf");
+        let read_options = ReadOptions {
+            locus_conflict,
+            ..Default::default()
+        };
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &read_options).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.group_transcripts(
+            Arc::from("sample_locus_conflict.gtf"),
+            &mut gtf_gff_transcripts,
+        );
+        transcript_unifier.unify_transcripts();
+
+        let temp_dir = tempdir().unwrap();
+        write_unified_gtf_gff(
+            "gtf",
+            &gtf_gff_path,
+            temp_dir.path(),
+            &transcript_unifier,
+            &read_options,
+            FilterAction::Tag,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let output =
+            read_to_string(temp_dir.path().join("sample_locus_conflict.tuni.gtf")).unwrap();
+
+        assert_eq!(
+            output
+                .lines()
+                .filter(|line| line.contains("tuni_id"))
+                .count(),
+            2
+        );
+        assert_eq!(
+            output.matches("tuni_flag \"locus_conflict\"").count(),
+            expected_flagged
+        );
+    }
+
+    // Test that writing from the line cache matches writing from the GTF, even
+    // once the GTF is removed, i.e. the GTF is only read once.
+    #[rstest]
+    #[case(LocusConflict::Flag)]
+    #[case(LocusConflict::Split)]
+    fn test_write_unified_gtf_line_cache(#[case] locus_conflict: LocusConflict) {
+        let temp_dir = tempdir().unwrap();
+        let gtf_gff_path = temp_dir.path().join("sample_locus_conflict.gtf");
+        fs::copy("tests/data/unit/sample_locus_conflict.gtf", &gtf_gff_path).unwrap();
+
+        let write_unified_gtf = |read_options: &ReadOptions, output_dir: &Path| {
+            let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, read_options).unwrap();
+            let mut transcript_unifier = TranscriptUnifier::new();
+            transcript_unifier.group_transcripts(
+                extract_file_name(&gtf_gff_path).unwrap(),
+                &mut gtf_gff_transcripts,
+            );
+            transcript_unifier.unify_transcripts();
+
+            // The GTF is no longer needed once cached.
+            if read_options.line_cache.is_some() {
+                fs::remove_file(&gtf_gff_path).unwrap();
+            }
+
+            write_unified_gtf_gff(
+                "gtf",
+                &gtf_gff_path,
+                output_dir,
+                &transcript_unifier,
+                read_options,
+                FilterAction::Tag,
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+            read_to_string(output_dir.join("sample_locus_conflict.tuni.gtf")).unwrap()
+        };
+
+        let expected_dir = tempdir().unwrap();
+        let expected = write_unified_gtf(
+            &ReadOptions {
+                locus_conflict,
+                ..Default::default()
+            },
+            expected_dir.path(),
+        );
+
+        let cache_dir = tempdir().unwrap();
+        let output_dir = tempdir().unwrap();
+        let output = write_unified_gtf(
+            &ReadOptions {
+                locus_conflict,
+                line_cache: Some(cache_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            output_dir.path(),
+        );
+
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_write_unified_gtf() {
         let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
-        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path).unwrap();
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
 
         let mut transcript_unifier = TranscriptUnifier::new();
-        transcript_unifier.group_transcripts(Rc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
         transcript_unifier.unify_transcripts();
 
         let temp_dir = tempdir().unwrap();
         let output_path = temp_dir.path().join("sample_1.tuni.gtf");
-        write_unified_gtf_gff("gtf", &gtf_gff_path, temp_dir.path(), &transcript_unifier).unwrap();
+        write_unified_gtf_gff(
+            "gtf",
+            &gtf_gff_path,
+            temp_dir.path(),
+            &transcript_unifier,
+            &ReadOptions::default(),
+            FilterAction::Tag,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
 
         // .collect() as <Vec<&str>> for easier debugging.
         assert_eq!(
@@ -453,4 +1814,327 @@ mod tests {
                 .collect::<Vec<&str>>()
         );
     }
+
+    #[rstest]
+    #[case(FilterAction::Tag, 4)]
+    #[case(FilterAction::Drop, 0)]
+    fn test_write_unified_gtf_filtered(
+        #[case] filter_action: FilterAction,
+        #[case] expected_n_lines: usize,
+    ) {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+
+        // With a single sample, every transcript fails the recurrence filter.
+        let mut transcript_unifier =
+            TranscriptUnifier::new().with_recurrence_filter(RecurrenceFilter {
+                min_samples: 2,
+                ..Default::default()
+            });
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        let temp_dir = tempdir().unwrap();
+        let output_path = temp_dir.path().join("sample_1.tuni.gtf");
+        write_unified_gtf_gff(
+            "gtf",
+            &gtf_gff_path,
+            temp_dir.path(),
+            &transcript_unifier,
+            &ReadOptions::default(),
+            filter_action,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let output = read_to_string(output_path).unwrap();
+        assert_eq!(output.lines().count(), expected_n_lines);
+        assert!(output
+            .lines()
+            .all(|x| x.ends_with(r#" tuni_filtered "recurrence";"#)));
+    }
+
+    #[rstest]
+    #[case(RegionFilter::new().with_regions([Region::parse("1:5-10").unwrap()]), "A", "B")]
+    #[case(RegionFilter::new().with_exclude_contigs([Arc::from("1")]), "B", "A")]
+    #[case(RegionFilter::new().with_include_contigs([Arc::from("2")]), "B", "A")]
+    #[case(RegionFilter::new().with_regions([Region::parse("2:31-40").unwrap()]), "", "AB")]
+    fn test_read_gtf_region_filter(
+        #[case] region_filter: RegionFilter,
+        #[case] expected_included: &str,
+        #[case] expected_filtered: &str,
+    ) {
+        let read_options = ReadOptions {
+            chr_aliases: ChrAliases::from(Some(ChrStyle::Ensembl), HashMap::new()),
+            region_filter,
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_1.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        // Chromosomes are compared after conversion, e.g. "chr1" to "1".
+        let transcript_ids = |x: &str| {
+            x.chars()
+                .map(|x| Arc::from(format!("transcript_id \"{}\"", x)))
+                .collect::<HashSet<TranscriptId>>()
+        };
+        assert_eq!(
+            gtf_gff_transcripts
+                .signatures
+                .into_keys()
+                .collect::<HashSet<TranscriptId>>(),
+            transcript_ids(expected_included)
+        );
+        assert_eq!(
+            gtf_gff_transcripts.filtered,
+            transcript_ids(expected_filtered)
+                .into_iter()
+                .map(|x| (x, FilterReason::Region))
+                .collect()
+        );
+    }
+
+    // Transcript A has 2 non-coding exons, B has 1 coding exon.
+    #[rstest]
+    #[case(StructureFilter { min_exons: Some(2), ..Default::default() }, "A", "B")]
+    #[case(StructureFilter { max_exons: Some(1), ..Default::default() }, "B", "A")]
+    #[case(StructureFilter { min_spliced_length: Some(5), ..Default::default() }, "B", "A")]
+    #[case(StructureFilter { coding: CodingFilter::Noncoding, ..Default::default() }, "A", "B")]
+    #[case(StructureFilter { min_intron_length: Some(9), ..Default::default() }, "", "A")]
+    fn test_read_gtf_structure_filter(
+        #[case] structure_filter: StructureFilter,
+        #[case] expected_included: &str,
+        #[case] expected_filtered: &str,
+    ) {
+        let read_options = ReadOptions {
+            structure_filter,
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_1.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        let transcript_id = |x: &str| Arc::from(format!("transcript_id \"{}\"", x));
+        assert_eq!(
+            gtf_gff_transcripts.filtered,
+            expected_filtered
+                .chars()
+                .map(|x| (transcript_id(&x.to_string()), FilterReason::Structure))
+                .collect()
+        );
+        assert_eq!(
+            gtf_gff_transcripts.signatures.len(),
+            2 - expected_filtered.len()
+        );
+        assert!(expected_included.chars().all(|x| gtf_gff_transcripts
+            .signatures
+            .contains_key(&transcript_id(&x.to_string()))));
+    }
+
+    // The TPM of A is on its transcript line, not its exon lines.
+    #[rstest]
+    #[case("TPM > 2", &["B"])]
+    #[case("TPM >= 3", &["A", "B"])]
+    #[case("transcript_biotype != lncRNA", &[])]
+    #[case("transcript_biotype == lncRNA", &["A", "A_dup", "B"])]
+    fn test_read_gtf_attribute_filter(#[case] source: &str, #[case] expected_filtered: &[&str]) {
+        let read_options = ReadOptions {
+            attribute_filter: Some(AttributeFilter::parse(source).unwrap()),
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_expression.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            gtf_gff_transcripts.filtered,
+            expected_filtered
+                .iter()
+                .map(|x| (
+                    Arc::from(format!("transcript_id \"{}\"", x)),
+                    FilterReason::Attribute
+                ))
+                .collect()
+        );
+        assert_eq!(
+            gtf_gff_transcripts.signatures.len(),
+            3 - expected_filtered.len()
+        );
+    }
+
+    #[test]
+    fn test_read_gtf_attribute_filter_non_numeric() {
+        let read_options = ReadOptions {
+            attribute_filter: Some(AttributeFilter::parse("transcript_id > 1").unwrap()),
+            ..Default::default()
+        };
+        let gtf_gff_transcripts = read_gtf_gff(
+            &PathBuf::from("tests/data/unit/sample_expression.gtf"),
+            &read_options,
+        )
+        .unwrap();
+
+        // Non-numeric values fail the comparison, as if missing, and warnings
+        // are sorted by transcript.
+        assert_eq!(gtf_gff_transcripts.filtered.len(), 3);
+        assert!(gtf_gff_transcripts.signatures.is_empty());
+        assert_eq!(
+            gtf_gff_transcripts.warnings,
+            ["A", "A_dup", "B"]
+                .iter()
+                .map(|x| Warning {
+                    category: WarningCategory::NonNumericAttribute,
+                    message: format!("{} (transcript_id {:?})", x, x),
+                })
+                .collect::<Vec<Warning>>()
+        );
+    }
+
+    #[rstest]
+    #[case(FilterAction::Tag, 4)]
+    #[case(FilterAction::Drop, 2)]
+    fn test_write_unified_gtf_region_filter(
+        #[case] filter_action: FilterAction,
+        #[case] expected_n_lines: usize,
+    ) {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
+        let read_options = ReadOptions {
+            region_filter: RegionFilter::new().with_exclude_contigs([Arc::from("chr1")]),
+            ..Default::default()
+        };
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &read_options).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        let temp_dir = tempdir().unwrap();
+        let output_path = temp_dir.path().join("sample_1.tuni.gtf");
+        write_unified_gtf_gff(
+            "gtf",
+            &gtf_gff_path,
+            temp_dir.path(),
+            &transcript_unifier,
+            &read_options,
+            filter_action,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let output = read_to_string(output_path).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), expected_n_lines + 1);
+        assert!(lines[0].starts_with("#tuni_region_filter: exclude_contigs=chr1;"));
+        assert!(lines[1..].iter().all(|x| match x.starts_with("chr1") {
+            true => x.ends_with(r#" tuni_filtered "region";"#),
+            false => x.ends_with(r#" tuni_id "tuni_0";"#),
+        }));
+    }
+
+    #[test]
+    fn test_write_unified_gff_region_filter_directives() {
+        let temp_dir = tempdir().unwrap();
+        let gtf_gff_path = temp_dir.path().join("sample_1.gff");
+        std::fs::write(
+            &gtf_gff_path,
+            "##gff-version 3\n##sequence-region chr1 1 100\n\
+             chr1\ttest\texon\t1\t100\t.\t+\t.\ttranscript_id=A;\n",
+        )
+        .unwrap();
+        let read_options = ReadOptions {
+            region_filter: RegionFilter::new().with_exclude_contigs([Arc::from("chr2")]),
+            ..Default::default()
+        };
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &read_options).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gff"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        let output_dir = temp_dir.path().join("output");
+        std::fs::create_dir(&output_dir).unwrap();
+        write_unified_gtf_gff(
+            "gff",
+            &gtf_gff_path,
+            &output_dir,
+            &transcript_unifier,
+            &read_options,
+            FilterAction::Tag,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let output = read_to_string(output_dir.join("sample_1.tuni.gff")).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "##gff-version 3");
+        assert_eq!(lines[1], "##sequence-region chr1 1 100");
+        assert!(lines[2].starts_with("#tuni_region_filter: exclude_contigs=chr2;"));
+        assert!(lines[3].ends_with("tuni_id=tuni_0;"));
+    }
+
+    #[rstest]
+    #[case(&[], "#tuni_shard_lines: ")]
+    #[case(&[3, 4, 5, 5, 6, 9], "#tuni_shard_lines: 3-5,5-6,9")]
+    fn test_shard_lines(#[case] line_numbers: &[usize], #[case] expected: &str) {
+        let mut shard_lines = ShardLines::default();
+        for line_number in line_numbers {
+            shard_lines.push(*line_number);
+        }
+        assert_eq!(shard_lines.to_string(), expected);
+
+        let shard_lines = ShardLines::from_footer(expected).unwrap();
+        assert_eq!(
+            shard_lines.line_numbers().collect::<Vec<usize>>(),
+            line_numbers
+        );
+    }
+
+    #[rstest]
+    #[case("#tuni_region_filter: exclude_contigs=chr1")]
+    #[case("#tuni_shard_lines: 5-3")]
+    #[case("#tuni_shard_lines: 1,a")]
+    fn test_shard_lines_from_footer_invalid(#[case] line: &str) {
+        assert_eq!(ShardLines::from_footer(line), None);
+    }
+
+    #[test]
+    fn test_write_unified_gtfs_failure() {
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_1.gtf");
+        let mut gtf_gff_transcripts = read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.group_transcripts(Arc::from("sample_1.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        // The last line of sample_invalid_utf8.gtf cannot be read.
+        let temp_dir = tempdir().unwrap();
+        let result = write_unified_gtf_gffs(
+            "gtf",
+            &[
+                gtf_gff_path,
+                PathBuf::from("tests/data/unit/sample_invalid_utf8.gtf"),
+            ],
+            temp_dir.path(),
+            &transcript_unifier,
+            &ReadOptions::default(),
+            FilterAction::Tag,
+            &mut Warnings::default(),
+        );
+
+        assert!(matches!(result, Err(GtfGffError::LineReadError(_))));
+
+        // Only complete files are left in the output directory.
+        let output_file_names = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|x| x.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        assert!(output_file_names.iter().all(|x| x == "sample_1.tuni.gtf"));
+    }
 }
@@ -0,0 +1,115 @@
+//! Unify transcripts across different samples.
+//!
+//! `tuni` groups transcripts from different GTF/GFFs that share the same
+//! structure (chromosome, strand, exon and CDS coordinates), then gives each
+//! group a unified ID that is consistent across samples.
+//!
+//! The main entry points are:
+//!
+//! - [`read_gtf_gff`] reads the [`TranscriptSignature`] of each transcript in a
+//!   GTF/GFF.
+//! - [`TranscriptUnifier`] groups transcripts across samples and creates
+//!   unified IDs, configured with [`UnifyOptions`].
+//! - [`write_unified_gtf_gffs`] and [`write_catalog`] write the unified
+//!   transcripts.
+//!
+//! [`unify_gtf_gffs`] combines reading and unifying a set of GTF/GFFs. For
+//! cohorts that do not fit in memory, [`ExternalUnifier`] unifies transcripts
+//! using temporary files.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use tuni::{UnifyOptions, Warnings};
+//!
+//! let gtf_gff_paths = [
+//!     PathBuf::from("tests/data/unit/sample_1.gtf"),
+//!     PathBuf::from("tests/data/unit/sample_2.gtf"),
+//! ];
+//! let options = UnifyOptions::builder().min_samples(2).build();
+//! let mut warnings = Warnings::default();
+//!
+//! let transcript_unifier = tuni::unify_gtf_gffs(&gtf_gff_paths, &options, &mut warnings)?;
+//!
+//! // Transcript "A" of sample 1 is found in both samples.
+//! let unified_id = transcript_unifier.get_unified_id("sample_1.gtf", "transcript_id \"A\"");
+//! assert_eq!(unified_id.map(|x| &**x), Some("tuni_0"));
+//! # Ok::<(), tuni::error::GtfGffError>(())
+//! ```
+
+mod attribute_filter;
+mod catalog;
+mod chr_alias;
+pub mod error;
+mod expression;
+mod external;
+mod filter;
+mod gtf_gff;
+mod line_cache;
+mod quant;
+mod redundancy;
+mod region;
+mod tx2gene;
+mod unify;
+mod warning;
+
+use std::path::PathBuf;
+
+pub use attribute_filter::AttributeFilter;
+pub use catalog::{
+    merge_catalogs, read_catalog, stage_catalog, write_catalog, write_translation_table,
+    StagedCatalog, CATALOG_FILE_NAME, TRANSLATION_FILE_NAME,
+};
+pub use chr_alias::{ChrAliases, ChrStyle};
+pub use error::GtfGffError;
+pub use expression::{write_expression_matrix, Aggregate, ExpressionMatrix, EXPRESSION_FILE_NAME};
+pub use external::ExternalUnifier;
+pub use filter::{CodingFilter, FilterAction, FilterReason, RecurrenceFilter, StructureFilter};
+pub use gtf_gff::{
+    extract_file_name, extract_transcript_name, read_attribute, read_expression, read_gtf_gff,
+    read_gtf_gffs, write_unified_gtf_gff, write_unified_gtf_gffs, Coordinate, GtfGffTranscripts,
+    LocusConflict, ReadOptions, ShardLines, TranscriptId, TranscriptSignature,
+};
+pub use quant::{read_quant, Quantification, COUNTS_FILE_NAME, TPM_FILE_NAME};
+pub use redundancy::{write_redundancy_report, REDUNDANCY_FILE_NAME};
+pub use region::{read_regions_bed, Region, RegionFilter};
+pub use tx2gene::{
+    unified_gene_id, write_tx2gene, Tx2Gene, TX2GENE_FILE_NAME, TX2GENE_SAMPLES_FILE_NAME,
+};
+pub use unify::{
+    RedundantGroup, SampleTranscriptId, TranscriptUnifier, UnifiedId, UnifyCounts, UnifyOptions,
+    UnifyOptionsBuilder,
+};
+pub use warning::{write_warnings, Warning, WarningCategory, Warnings};
+
+/// Read then unify the transcripts of each GTF/GFF in `gtf_gff_paths`.
+///
+/// Each GTF/GFF is a sample, named by its file name. GTF/GFFs are read in
+/// parallel using the current rayon thread pool, then unified in the order
+/// given, so unified IDs do not depend on the number of threads. Warnings found
+/// while reading are added to `warnings`.
+///
+/// # Errors
+///
+/// See [`read_gtf_gff`].
+pub fn unify_gtf_gffs(
+    gtf_gff_paths: &[PathBuf],
+    options: &UnifyOptions,
+    warnings: &mut Warnings,
+) -> Result<TranscriptUnifier, GtfGffError> {
+    let mut transcript_unifier = TranscriptUnifier::new().with_options(options);
+
+    let all_gtf_gff_transcripts = read_gtf_gffs(gtf_gff_paths, &options.read_options)?;
+
+    for (gtf_gff_path, mut gtf_gff_transcripts) in gtf_gff_paths.iter().zip(all_gtf_gff_transcripts)
+    {
+        let gtf_gff_file_name = extract_file_name(gtf_gff_path)?;
+        warnings.extend(&gtf_gff_file_name, gtf_gff_transcripts.warnings.drain(..));
+        transcript_unifier.group_transcripts(gtf_gff_file_name, &mut gtf_gff_transcripts);
+    }
+
+    transcript_unifier.unify_transcripts();
+
+    Ok(transcript_unifier)
+}
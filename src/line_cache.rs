@@ -0,0 +1,473 @@
+//! Cache the lines of GTF/GFFs while reading, so that outputs are written
+//! without reading and parsing the GTF/GFFs again.
+//!
+//! Lines are cached split into fields in a compact binary format, so the cache
+//! is smaller than the GTF/GFF. Repeated fields (e.g. chromosome, source,
+//! feature, strand) and transcript IDs are stored once in a table of names and
+//! referred to by index, and coordinates are stored as integers. Attributes are
+//! split on ";", and each attribute also found in the previous line (e.g. the
+//! gene and transcript IDs of consecutive exons) refers to it by index.
+//!
+//! The cache is a sequence of records, each starting with a tag byte:
+//!
+//! - [`NAME`]: the length and bytes of the next name in the table.
+//! - [`LINE`]: the difference from the previous line number, the transcript
+//!   ID (0 if none, otherwise its name index + 1), the number of fields, then
+//!   each field.
+//!
+//! Each field starts with a header whose lowest two bits give its kind, with
+//! the rest of the header holding the name index, integer, number of inline
+//! bytes or number of attributes. Each attribute starts with a header whose
+//! lowest bit is 0 for the index of an attribute of the previous line, or 1
+//! for the number of inline bytes. All integers are LEB128 varints.
+
+use crate::error::GtfGffError;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Tag of a record adding a name to the table.
+const NAME: u8 = 0;
+
+/// Tag of a record holding a line.
+const LINE: u8 = 1;
+
+/// Kind of a field that is a name index.
+const FIELD_NAME: u64 = 0;
+
+/// Kind of a field that is an integer.
+const FIELD_INTEGER: u64 = 1;
+
+/// Kind of a field that is stored inline.
+const FIELD_INLINE: u64 = 2;
+
+/// Kind of a field that is a list of attributes.
+const FIELD_ATTRIBUTES: u64 = 3;
+
+/// Attributes of the previous line, to which attributes of the next line refer.
+#[derive(Default)]
+struct PreviousAttributes {
+    /// Attributes field of the previous line.
+    attributes: String,
+
+    /// Range of each attribute in `attributes`.
+    ranges: Vec<Range<usize>>,
+}
+
+impl PreviousAttributes {
+    /// Replace the attributes of the previous line.
+    fn set(&mut self, attributes: &str) {
+        self.attributes.clear();
+        self.attributes.push_str(attributes);
+        self.ranges.clear();
+
+        let mut start = 0;
+        for attribute in attributes.split(';') {
+            self.ranges.push(start..start + attribute.len());
+            start += attribute.len() + 1;
+        }
+    }
+
+    /// Obtain the index of `attribute`, if found in the previous line.
+    fn position(&self, attribute: &str) -> Option<usize> {
+        self.ranges
+            .iter()
+            .position(|x| &self.attributes[x.clone()] == attribute)
+    }
+
+    /// Obtain the attribute at `index`, if any.
+    fn get(&self, index: usize) -> Option<&str> {
+        self.ranges.get(index).map(|x| &self.attributes[x.clone()])
+    }
+}
+
+/// Write the lines of a GTF/GFF to a cache.
+pub(crate) struct LineCacheWriter {
+    /// Path of the cache.
+    path: PathBuf,
+
+    /// Writer of the cache.
+    writer: BufWriter<File>,
+
+    /// Link each name to its index in the table.
+    name_indexes: HashMap<Box<str>, u64>,
+
+    /// Attributes of the previous line with attributes.
+    previous_attributes: PreviousAttributes,
+
+    /// Number of the previous line in the GTF/GFF.
+    previous_line_number: usize,
+
+    /// Line being encoded, written once any new names have been written.
+    buffer: Vec<u8>,
+}
+
+impl LineCacheWriter {
+    /// Create a cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileCreateError`](GtfGffError::FileCreateError) if the cache
+    /// cannot be created.
+    pub(crate) fn create(path: PathBuf) -> Result<LineCacheWriter, GtfGffError> {
+        let file = File::create(&path).map_err(|_| GtfGffError::FileCreateError(path.clone()))?;
+        Ok(LineCacheWriter {
+            path,
+            writer: BufWriter::new(file),
+            name_indexes: HashMap::new(),
+            previous_attributes: PreviousAttributes::default(),
+            previous_line_number: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Cache one line, split into fields, with its (increasing) number in the
+    /// GTF/GFF and its transcript ID, if any.
+    ///
+    /// Lines of a single field (e.g. headers) are stored inline. Otherwise, the
+    /// last field is stored as a list of attributes, and every other field as
+    /// an integer or a name index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileWriteError`](GtfGffError::FileWriteError) if the cache
+    /// cannot be written.
+    pub(crate) fn write(
+        &mut self,
+        line_number: usize,
+        fields: &[&str],
+        transcript_id: Option<&str>,
+    ) -> Result<(), GtfGffError> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.push(LINE);
+        write_varint(
+            &mut buffer,
+            (line_number - self.previous_line_number) as u64,
+        );
+        self.previous_line_number = line_number;
+
+        let transcript_index = match transcript_id {
+            Some(transcript_id) => self.name_index(transcript_id)? + 1,
+            None => 0,
+        };
+        write_varint(&mut buffer, transcript_index);
+        write_varint(&mut buffer, fields.len() as u64);
+
+        for (i, field) in fields.iter().enumerate() {
+            if fields.len() == 1 {
+                write_varint(&mut buffer, (field.len() as u64) << 2 | FIELD_INLINE);
+                buffer.extend_from_slice(field.as_bytes());
+            } else if i + 1 == fields.len() {
+                self.write_attributes(&mut buffer, field);
+            } else if let Some(integer) = parse_integer(field) {
+                write_varint(&mut buffer, integer << 2 | FIELD_INTEGER);
+            } else {
+                write_varint(&mut buffer, self.name_index(field)? << 2 | FIELD_NAME);
+            }
+        }
+
+        let result = self.writer.write_all(&buffer);
+        self.buffer = buffer;
+        result.map_err(|_| GtfGffError::FileWriteError(self.path.clone()))
+    }
+
+    /// Encode the attributes field of a line, referring to attributes of the
+    /// previous line where possible.
+    fn write_attributes(&mut self, buffer: &mut Vec<u8>, attributes: &str) {
+        let n_attributes = attributes.split(';').count() as u64;
+        write_varint(buffer, n_attributes << 2 | FIELD_ATTRIBUTES);
+
+        for attribute in attributes.split(';') {
+            match self.previous_attributes.position(attribute) {
+                Some(index) => write_varint(buffer, (index as u64) << 1),
+                None => {
+                    write_varint(buffer, (attribute.len() as u64) << 1 | 1);
+                    buffer.extend_from_slice(attribute.as_bytes());
+                }
+            }
+        }
+
+        self.previous_attributes.set(attributes);
+    }
+
+    /// Obtain the index of `name` in the table, adding it if new.
+    fn name_index(&mut self, name: &str) -> Result<u64, GtfGffError> {
+        if let Some(index) = self.name_indexes.get(name) {
+            return Ok(*index);
+        }
+
+        let index = self.name_indexes.len() as u64;
+        self.name_indexes.insert(Box::from(name), index);
+
+        let mut record = vec![NAME];
+        write_varint(&mut record, name.len() as u64);
+        record.extend_from_slice(name.as_bytes());
+        self.writer
+            .write_all(&record)
+            .map_err(|_| GtfGffError::FileWriteError(self.path.clone()))?;
+
+        Ok(index)
+    }
+
+    /// Flush the cache to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileWriteError`](GtfGffError::FileWriteError) if the cache
+    /// cannot be written.
+    pub(crate) fn finish(mut self) -> Result<(), GtfGffError> {
+        self.writer
+            .flush()
+            .map_err(|_| GtfGffError::FileWriteError(self.path.clone()))
+    }
+}
+
+/// Field of a cached line being read.
+enum Field {
+    /// Index of a name in the table.
+    Name(usize),
+
+    /// Range of the line buffer.
+    Buffer(Range<usize>),
+}
+
+/// Call `f` with the number, fields and transcript ID, if any, of each line in
+/// the cache at `path`, in the order cached.
+///
+/// # Errors
+///
+/// Returns [`LineReadError`](GtfGffError::LineReadError) if the cache cannot
+/// be read.
+///
+/// Returns any error returned by `f`.
+pub(crate) fn read_line_cache(
+    path: &Path,
+    mut f: impl FnMut(usize, &[&str], Option<&str>) -> Result<(), GtfGffError>,
+) -> Result<(), GtfGffError> {
+    let read_error = || GtfGffError::LineReadError(path.to_path_buf());
+    let mut reader = BufReader::new(File::open(path).map_err(|_| read_error())?);
+    let mut names: Vec<Box<str>> = Vec::new();
+    let mut previous_attributes = PreviousAttributes::default();
+    let mut line_number = 0;
+    let mut buffer = Vec::new();
+    let mut fields = Vec::new();
+
+    loop {
+        let tag = match reader.fill_buf().map_err(|_| read_error())?.first() {
+            Some(tag) => *tag,
+            None => return Ok(()),
+        };
+        reader.consume(1);
+
+        if tag == NAME {
+            let len = read_varint(&mut reader).map_err(|_| read_error())?;
+            let mut name = Vec::new();
+            read_bytes(&mut reader, len, &mut name).map_err(|_| read_error())?;
+            names.push(String::from_utf8(name).map_err(|_| read_error())?.into());
+            continue;
+        } else if tag != LINE {
+            return Err(read_error());
+        }
+
+        line_number += read_varint(&mut reader).map_err(|_| read_error())? as usize;
+        let transcript_index = read_varint(&mut reader).map_err(|_| read_error())?;
+        let n_fields = read_varint(&mut reader).map_err(|_| read_error())?;
+        let mut attributes_range = None;
+        buffer.clear();
+        fields.clear();
+
+        for _ in 0..n_fields {
+            let header = read_varint(&mut reader).map_err(|_| read_error())?;
+            let value = header >> 2;
+            let start = buffer.len();
+            match header & 3 {
+                FIELD_NAME => {
+                    fields.push(Field::Name(value as usize));
+                    continue;
+                }
+                FIELD_INTEGER => write!(buffer, "{}", value).map_err(|_| read_error())?,
+                FIELD_INLINE => {
+                    read_bytes(&mut reader, value, &mut buffer).map_err(|_| read_error())?
+                }
+                _ => {
+                    for i in 0..value {
+                        if i > 0 {
+                            buffer.push(b';');
+                        }
+                        let header = read_varint(&mut reader).map_err(|_| read_error())?;
+                        match header & 1 {
+                            0 => {
+                                let attribute = previous_attributes
+                                    .get((header >> 1) as usize)
+                                    .ok_or_else(read_error)?;
+                                buffer.extend_from_slice(attribute.as_bytes());
+                            }
+                            _ => read_bytes(&mut reader, header >> 1, &mut buffer)
+                                .map_err(|_| read_error())?,
+                        }
+                    }
+                    attributes_range = Some(start..buffer.len());
+                }
+            }
+            fields.push(Field::Buffer(start..buffer.len()));
+        }
+
+        let line = std::str::from_utf8(&buffer).map_err(|_| read_error())?;
+        if let Some(range) = attributes_range {
+            previous_attributes.set(&line[range]);
+        }
+
+        let name = |index: usize| names.get(index).map(|x| &**x).ok_or_else(read_error);
+        let line_fields = fields
+            .iter()
+            .map(|x| match x {
+                Field::Name(index) => name(*index),
+                Field::Buffer(range) => line.get(range.clone()).ok_or_else(read_error),
+            })
+            .collect::<Result<Vec<&str>, GtfGffError>>()?;
+        let transcript_id = match transcript_index {
+            0 => None,
+            index => Some(name(index as usize - 1)?),
+        };
+
+        f(line_number, &line_fields, transcript_id)?;
+    }
+}
+
+/// Parse a field written as a non-negative integer, without leading zeros, so
+/// that it is written back unchanged. Integers must fit in a field header.
+fn parse_integer(field: &str) -> Option<u64> {
+    let is_canonical = field.bytes().all(|x| x.is_ascii_digit())
+        && (field == "0" || !field.starts_with('0'))
+        && (1..20).contains(&field.len());
+    is_canonical
+        .then(|| field.parse::<u64>().ok())
+        .flatten()
+        .filter(|x| *x < 1 << 62)
+}
+
+/// Append `value` to `buffer` as a LEB128 varint.
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Read a LEB128 varint.
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err(io::ErrorKind::InvalidData.into())
+}
+
+/// Append exactly `len` bytes to `buffer`.
+fn read_bytes(reader: &mut impl Read, len: u64, buffer: &mut Vec<u8>) -> io::Result<()> {
+    match reader.take(len).read_to_end(buffer)? as u64 == len {
+        true => Ok(()),
+        false => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_line_cache() {
+        let lines: [(&[&str], Option<&str>); 5] = [
+            (&["#!comment\twith tab"], None),
+            (
+                &[
+                    "chr1",
+                    "test",
+                    "exon",
+                    "1",
+                    "0100",
+                    ".",
+                    "+",
+                    "0",
+                    r#"transcript_id "A";"#,
+                ],
+                Some(r#"transcript_id "A""#),
+            ),
+            (
+                &[
+                    "chr1",
+                    "test",
+                    "exon",
+                    "1",
+                    "100",
+                    ".",
+                    "+",
+                    ".",
+                    r#"gene_id "G"; transcript_id "A";"#,
+                ],
+                Some(r#"transcript_id "A""#),
+            ),
+            (
+                &["chr1", "test", "gene", "1", "100", ".", "+", ".", ""],
+                None,
+            ),
+            (
+                &[
+                    "chr1",
+                    "test",
+                    "CDS",
+                    "9999999999999999999",
+                    "2",
+                    "transcript_id=A",
+                ],
+                Some(r#"transcript_id "A""#),
+            ),
+        ];
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("sample_1.gtf.lines");
+        let mut line_cache = LineCacheWriter::create(path.clone()).unwrap();
+        for (i, (fields, transcript_id)) in lines.into_iter().enumerate() {
+            line_cache.write(2 * i + 1, fields, transcript_id).unwrap();
+        }
+        line_cache.finish().unwrap();
+
+        let mut cached_lines = Vec::new();
+        read_line_cache(&path, |line_number, fields, transcript_id| {
+            assert_eq!(line_number, 2 * cached_lines.len() + 1);
+            cached_lines.push((fields.join("\t"), transcript_id.map(String::from)));
+            Ok(())
+        })
+        .unwrap();
+
+        let expected_lines = lines
+            .iter()
+            .map(|(fields, transcript_id)| (fields.join("\t"), transcript_id.map(String::from)))
+            .collect::<Vec<(String, Option<String>)>>();
+        assert_eq!(cached_lines, expected_lines);
+    }
+
+    #[test]
+    fn test_read_line_cache_truncated() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("sample_1.gtf.lines");
+        std::fs::write(&path, [LINE, 1, 0, 1, 0x0a, b'#']).unwrap();
+
+        assert!(matches!(
+            read_line_cache(&path, |_, _, _| Ok(())),
+            Err(GtfGffError::LineReadError(_))
+        ));
+    }
+}
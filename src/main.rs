@@ -1,30 +1,84 @@
 mod cli;
-mod error;
-mod gtf_gff;
-mod unify;
+mod cli_error;
+mod config;
+mod shard;
+mod summary;
+mod validate;
 
-use clap::Parser;
-use log::{info, LevelFilter};
+use clap::{CommandFactory, FromArgMatches};
+use log::{info, warn, LevelFilter};
+use rayon::prelude::*;
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Arc;
+use tempfile::TempDir;
 
-use cli::Cli;
-use unify::TranscriptUnifier;
+use cli::{
+    AnnotateArgs, Cli, Command, GlobalArgs, MergeCatalogsArgs, MergeShardsArgs, OutputArgs,
+    QuantArgs, UnifyArgs, ValidateArgs, WarningArgs,
+};
+use summary::RunSummary;
+use tuni::{
+    ExpressionMatrix, ExternalUnifier, FilterAction, GtfGffError, ReadOptions, StagedCatalog,
+    TranscriptUnifier, Tx2Gene, WarningCategory, Warnings,
+};
 
 /// Responsible for parsing cli arguments, setting the log level and
 /// printing errors.
 fn main() {
-    let cli = Cli::parse();
+    let args = match merge_config_args(env::args_os().collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let matches = Cli::command().get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let resolved_config = config::resolve_config(&matches, &mut Cli::command());
 
     // By default, warn users.
     // Warning indicates potentially incorrectly formatted input.
-    let log_level = match cli.verbose {
+    let log_level = match cli.global_args.verbose {
         true => LevelFilter::Info,
         false => LevelFilter::Warn,
     };
     env_logger::Builder::new().filter_level(log_level).init();
 
-    match run(cli) {
+    if let Err(e) = rayon::ThreadPoolBuilder::new()
+        .num_threads(cli.global_args.threads)
+        .build_global()
+    {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    let global_args = cli.global_args.clone();
+    let result = match cli.into_command() {
+        Command::Unify(args) => run(args, &global_args),
+        Command::Annotate(args) => annotate(args, &global_args),
+        Command::MergeCatalogs(args) => merge_catalogs(args),
+        Command::MergeShards(args) => merge_shards(args),
+        Command::Quant(args) => quant(args),
+        Command::Validate(args) => validate(args),
+    };
+
+    // Record the resolved config alongside the outputs.
+    let result = result.and_then(|_| match resolved_config.get("output_dir") {
+        Some(output_dir) => {
+            let config_path = PathBuf::from(output_dir.as_str().unwrap_or_default())
+                .join(config::CONFIG_FILE_NAME);
+            info!("Writing resolved config");
+            Ok(config::write_config(&config_path, &resolved_config)?)
+        }
+        None => Ok(()),
+    });
+
+    match result {
         Ok(_) => (),
         Err(e) => {
             eprintln!("{}", e);
@@ -33,37 +87,552 @@ fn main() {
     }
 }
 
+/// Insert the values of the config given by `--config`, if any, into the
+/// command line arguments.
+fn merge_config_args(args: Vec<OsString>) -> Result<Vec<OsString>, Box<dyn Error>> {
+    let Some(config_path) = config::find_config_path(&args) else {
+        return Ok(args);
+    };
+
+    let config = config::read_config(config_path)?;
+    Ok(config::merge_config(&args, &config, &mut Cli::command())?)
+}
+
 /// Executes tuni, prints top-level logs and returns unrecoverable errors.
-fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
-    let mut transcript_unifier = TranscriptUnifier::new();
-    // Due to <https://github.com/clap-rs/clap/issues/4808>, value_parser cannot
-    // directly use this function.
-    let (gtf_gff_extension, gtf_gff_paths) = Cli::parse_gtf_gff_paths(cli.gtf_gff_path)?;
+fn run(args: UnifyArgs, global_args: &GlobalArgs) -> Result<(), Box<dyn Error>> {
+    let mut run_summary = RunSummary::new();
+    let mut options = args
+        .filter_args
+        .unify_options(args.read_args.read_options()?)?;
+    let _line_cache = create_line_cache(&global_args.cache_dir, &mut options.read_options)?;
+    let mut transcript_unifier = TranscriptUnifier::new().with_options(&options);
+
+    let mut warnings = Warnings::new(args.warning_args.warnings_file.is_some());
+
+    let staged_catalog = unify_gtf_gffs(
+        &mut transcript_unifier,
+        &mut run_summary,
+        &mut warnings,
+        args.gtf_gff_path,
+        &args.output_dir,
+        &args.output_dir.join(tuni::CATALOG_FILE_NAME),
+        &options.read_options,
+        args.filter_args.filter_action,
+        &args.output_args,
+        global_args.max_memory,
+    )?;
+
+    report_warnings(&warnings, &args.warning_args)?;
+    run_summary.add_warnings(&warnings);
+
+    staged_catalog.persist()?;
+
+    report_summary(&mut run_summary, &args.output_dir, global_args.verbose)?;
+
+    info!("Done");
+
+    Ok(())
+}
+
+/// Annotates new GTF/GFFs with the unified IDs from an existing catalog, then
+/// updates the catalog with any new transcripts.
+fn annotate(args: AnnotateArgs, global_args: &GlobalArgs) -> Result<(), Box<dyn Error>> {
+    let mut run_summary = RunSummary::new();
+
+    info!("Reading catalog");
+
+    let unified_signatures = tuni::read_catalog(&args.catalog)?;
+    let mut options = args
+        .filter_args
+        .unify_options(args.read_args.read_options()?)?;
+    let _line_cache = create_line_cache(&global_args.cache_dir, &mut options.read_options)?;
+    let mut transcript_unifier =
+        TranscriptUnifier::from_catalog(unified_signatures).with_options(&options);
+
+    let mut warnings = Warnings::new(args.warning_args.warnings_file.is_some());
+
+    let staged_catalog = unify_gtf_gffs(
+        &mut transcript_unifier,
+        &mut run_summary,
+        &mut warnings,
+        args.gtf_gff_path,
+        &args.output_dir,
+        &args.catalog,
+        &options.read_options,
+        args.filter_args.filter_action,
+        &args.output_args,
+        global_args.max_memory,
+    )?;
+
+    report_warnings(&warnings, &args.warning_args)?;
+    run_summary.add_warnings(&warnings);
+
+    info!("Updating catalog");
+
+    staged_catalog.persist()?;
+
+    report_summary(&mut run_summary, &args.output_dir, global_args.verbose)?;
+
+    info!("Done");
+
+    Ok(())
+}
+
+/// Merges catalogs from separate runs, writing the merged catalog and a table
+/// translating the unified IDs of each catalog into merged unified IDs.
+fn merge_catalogs(args: MergeCatalogsArgs) -> Result<(), Box<dyn Error>> {
+    info!("Reading catalogs");
+
+    let mut catalogs = Vec::new();
+    for catalog_path in &args.catalogs {
+        let catalog_name = Arc::from(catalog_path.to_string_lossy());
+        catalogs.push((catalog_name, tuni::read_catalog(catalog_path)?));
+    }
+
+    info!("Merging catalogs");
+
+    let transcript_unifier = tuni::merge_catalogs(&catalogs);
+
+    info!("Writing merged catalog");
+
+    tuni::write_catalog(
+        &args.output_dir.join(tuni::CATALOG_FILE_NAME),
+        &transcript_unifier,
+    )?;
+    tuni::write_translation_table(
+        &args.output_dir.join(tuni::TRANSLATION_FILE_NAME),
+        &catalogs,
+        &transcript_unifier,
+    )?;
+
+    info!("Done");
+
+    Ok(())
+}
+
+/// Merges the outputs of runs over disjoint contigs, translating the unified
+/// IDs of each shard into merged unified IDs. The run summaries of each shard
+/// are added together.
+fn merge_shards(args: MergeShardsArgs) -> Result<(), Box<dyn Error>> {
+    info!("Reading catalogs");
+
+    let mut catalogs = Vec::new();
+    for shard_dir in &args.shards {
+        let catalog_path = shard_dir.join(tuni::CATALOG_FILE_NAME);
+        catalogs.push((
+            shard::shard_name(shard_dir),
+            tuni::read_catalog(&catalog_path)?,
+        ));
+    }
+
+    info!("Reading run summaries");
+
+    let mut run_summary = RunSummary::new();
+    for shard_dir in &args.shards {
+        run_summary.add_shard(&summary::read_summary(
+            &shard_dir.join(summary::SUMMARY_FILE_NAME),
+        )?);
+    }
+
+    info!("Merging catalogs");
+
+    let transcript_unifier = tuni::merge_catalogs(&catalogs);
+
+    info!("Writing merged catalog");
+
+    let staged_catalog = tuni::stage_catalog(
+        &args.output_dir.join(tuni::CATALOG_FILE_NAME),
+        &transcript_unifier,
+    )?;
+
+    info!("Writing merged GTF/GFFs");
+
+    shard::find_unified_gtf_gffs(&args.shards)?
+        .par_iter()
+        .try_for_each(|file_name| {
+            shard::write_merged_gtf_gff(
+                file_name,
+                &args.shards,
+                &args.output_dir,
+                &transcript_unifier,
+            )
+        })?;
+
+    info!("Writing merged redundancy report");
+
+    shard::write_merged_redundancy_report(&args.shards, &args.output_dir, &transcript_unifier)?;
+
+    info!("Writing merged expression matrix and tx2gene");
+
+    for file_name in [tuni::EXPRESSION_FILE_NAME, tuni::TX2GENE_FILE_NAME] {
+        shard::write_merged_unified_table(
+            file_name,
+            &args.shards,
+            &args.output_dir,
+            &transcript_unifier,
+        )?;
+    }
+    shard::write_merged_tx2gene_samples(
+        &args.shards,
+        &args.output_dir,
+        &transcript_unifier,
+        &run_summary
+            .samples
+            .iter()
+            .map(|x| x.sample.as_str())
+            .collect::<Vec<&str>>(),
+    )?;
+
+    info!("Writing merged run summary");
+
+    summary::write_summary(
+        &args.output_dir.join(summary::SUMMARY_FILE_NAME),
+        &run_summary,
+    )?;
+
+    info!("Writing translation table");
+
+    tuni::write_translation_table(
+        &args.output_dir.join(tuni::TRANSLATION_FILE_NAME),
+        &catalogs,
+        &transcript_unifier,
+    )?;
+    staged_catalog.persist()?;
+
+    info!("Done");
+
+    Ok(())
+}
+
+/// Translates per-sample quantifications to unified IDs, writing count and TPM
+/// matrices keyed by unified ID.
+///
+/// Unified IDs are taken from the catalog if provided, otherwise the GTF/GFFs
+/// are unified as in `run`.
+fn quant(args: QuantArgs) -> Result<(), Box<dyn Error>> {
+    let (_, gtf_gff_paths) = Cli::parse_gtf_gff_paths(args.gtf_gff_path)?;
+    let quant_paths = Cli::parse_quant_paths(args.quant_path, gtf_gff_paths.len())?;
+    let read_options = args.read_args.read_options()?;
+
+    let mut transcript_unifier = match &args.catalog {
+        Some(catalog_path) => TranscriptUnifier::from_catalog(tuni::read_catalog(catalog_path)?),
+        None => TranscriptUnifier::new(),
+    };
 
     info!("Reading GTF/GFFs");
 
-    for gtf_gff_path in &gtf_gff_paths {
-        let gtf_gff_file_name = gtf_gff::extract_file_name(gtf_gff_path);
-        let mut gtf_gff_transcripts = gtf_gff::read_gtf_gff(gtf_gff_path)?;
+    let mut sample_transcript_names = Vec::new();
+    let mut warnings = Warnings::default();
+    let all_gtf_gff_transcripts = tuni::read_gtf_gffs(&gtf_gff_paths, &read_options)?;
+
+    for (gtf_gff_path, mut gtf_gff_transcripts) in gtf_gff_paths.iter().zip(all_gtf_gff_transcripts)
+    {
+        let gtf_gff_file_name = tuni::extract_file_name(gtf_gff_path)?;
+        sample_transcript_names.push(tuni::Quantification::transcript_names(
+            gtf_gff_transcripts.signatures.keys(),
+        ));
+        warnings.extend(&gtf_gff_file_name, gtf_gff_transcripts.warnings.drain(..));
         transcript_unifier.group_transcripts(gtf_gff_file_name, &mut gtf_gff_transcripts);
     }
+    warnings.log();
 
     info!("Unifying transcripts");
 
     transcript_unifier.unify_transcripts();
 
-    info!("Writing unified transcripts");
+    info!("Reading quantifications");
 
-    for gtf_gff_path in &gtf_gff_paths {
-        gtf_gff::write_unified_gtf_gff(
-            &gtf_gff_extension,
-            gtf_gff_path,
-            &cli.output_dir,
-            &transcript_unifier,
-        )?
+    let mut counts = ExpressionMatrix::new(args.aggregate);
+    let mut tpms = ExpressionMatrix::new(args.aggregate);
+
+    for ((gtf_gff_path, quant_path), transcript_names) in gtf_gff_paths
+        .iter()
+        .zip(&quant_paths)
+        .zip(&sample_transcript_names)
+    {
+        let sample = tuni::extract_file_name(gtf_gff_path)?;
+        let (sample_counts, sample_tpms, n_missing) =
+            tuni::read_quant(quant_path)?.into_transcript_ids(transcript_names);
+
+        if n_missing > 0 {
+            warn!(
+                "{} transcripts in {} not found in {}",
+                n_missing,
+                quant_path.display(),
+                sample
+            );
+        }
+
+        counts.add_sample(Arc::clone(&sample), sample_counts, &transcript_unifier);
+        tpms.add_sample(sample, sample_tpms, &transcript_unifier);
     }
 
+    info!("Writing count and TPM matrices");
+
+    tuni::write_expression_matrix(
+        &args.output_dir.join(tuni::COUNTS_FILE_NAME),
+        &counts,
+        &transcript_unifier,
+    )?;
+    tuni::write_expression_matrix(
+        &args.output_dir.join(tuni::TPM_FILE_NAME),
+        &tpms,
+        &transcript_unifier,
+    )?;
+
     info!("Done");
 
     Ok(())
 }
+
+/// Checks GTF/GFFs for problems, printing them to stdout.
+///
+/// Exits with a code summarising the most severe problem found, see
+/// [`validate::Severity::exit_code`].
+fn validate(args: ValidateArgs) -> Result<(), Box<dyn Error>> {
+    let (_, gtf_gff_paths) = Cli::parse_gtf_gff_paths(args.gtf_gff_path)?;
+
+    info!("Validating GTF/GFFs");
+
+    let validations = gtf_gff_paths
+        .par_iter()
+        .map(|gtf_gff_path| {
+            info!("{}", gtf_gff_path.display());
+            validate::validate_gtf_gff(gtf_gff_path)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    validate::write_validations(&mut io::stdout().lock(), &validations, args.format)?;
+
+    if let Some(severity) = validations.iter().filter_map(|x| x.max_severity()).max() {
+        process::exit(severity.exit_code());
+    }
+
+    Ok(())
+}
+
+/// Creates a temporary directory within `cache_dir`, if given, in which the
+/// lines of each GTF/GFF are cached while reading, see
+/// [`ReadOptions::line_cache`].
+///
+/// The cache is removed when the returned directory is dropped.
+fn create_line_cache(
+    cache_dir: &Option<PathBuf>,
+    read_options: &mut ReadOptions,
+) -> Result<Option<TempDir>, Box<dyn Error>> {
+    let Some(cache_dir) = cache_dir else {
+        return Ok(None);
+    };
+
+    let line_cache = tempfile::Builder::new()
+        .prefix(".tuni_cache_")
+        .tempdir_in(cache_dir)
+        .map_err(|_| GtfGffError::FileCreateError(cache_dir.clone()))?;
+    read_options.line_cache = Some(line_cache.path().to_path_buf());
+
+    Ok(Some(line_cache))
+}
+
+/// Logs warnings, writing every warning if requested, then returns an error if
+/// warnings of any `--strict` category were found.
+fn report_warnings(warnings: &Warnings, warning_args: &WarningArgs) -> Result<(), Box<dyn Error>> {
+    warnings.log();
+
+    if let Some(warnings_file) = &warning_args.warnings_file {
+        info!("Writing warnings");
+        tuni::write_warnings(warnings_file, warnings)?;
+    }
+
+    if let Some(strict) = &warning_args.strict {
+        warnings.check_strict(strict)?;
+    }
+
+    Ok(())
+}
+
+/// Finishes the run summary, writing it to `output_dir` and, if `verbose`,
+/// printing it to stdout.
+fn report_summary(
+    run_summary: &mut RunSummary,
+    output_dir: &Path,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    info!("Writing run summary");
+
+    run_summary.finish();
+    summary::write_summary(&output_dir.join(summary::SUMMARY_FILE_NAME), run_summary)?;
+    if verbose {
+        summary::print_summary(&mut io::stdout().lock(), run_summary)?;
+    }
+
+    Ok(())
+}
+
+/// Reads, unifies then writes the GTF/GFFs listed in `gtf_gff_path`, along
+/// with any additional outputs.
+///
+/// The catalog is staged to `catalog_path` once transcripts are unified, before
+/// any output is written, and returned to be persisted once the run succeeds.
+///
+/// With `max_memory`, transcripts are unified using temporary files in
+/// `output_dir`, see [`ExternalUnifier`]. GTF/GFFs are then read and written a
+/// few at a time, so that only their unified IDs are held in memory at once.
+#[allow(clippy::too_many_arguments)]
+fn unify_gtf_gffs(
+    transcript_unifier: &mut TranscriptUnifier,
+    run_summary: &mut RunSummary,
+    warnings: &mut Warnings,
+    gtf_gff_path: PathBuf,
+    output_dir: &Path,
+    catalog_path: &Path,
+    read_options: &ReadOptions,
+    filter_action: FilterAction,
+    output_args: &OutputArgs,
+    max_memory: Option<usize>,
+) -> Result<StagedCatalog, Box<dyn Error>> {
+    // Due to <https://github.com/clap-rs/clap/issues/4808>, value_parser cannot
+    // directly use this function.
+    let (gtf_gff_extension, gtf_gff_paths) = Cli::parse_gtf_gff_paths(gtf_gff_path)?;
+
+    let mut external_unifier = max_memory
+        .map(|x| ExternalUnifier::new(output_dir, x))
+        .transpose()?;
+    let chunk_size = match external_unifier {
+        Some(_) => rayon::current_num_threads(),
+        None => gtf_gff_paths.len().max(1),
+    };
+
+    info!("Reading GTF/GFFs");
+
+    for chunk_paths in gtf_gff_paths.chunks(chunk_size) {
+        let all_gtf_gff_transcripts = tuni::read_gtf_gffs(chunk_paths, read_options)?;
+
+        for (gtf_gff_path, mut gtf_gff_transcripts) in
+            chunk_paths.iter().zip(all_gtf_gff_transcripts)
+        {
+            let gtf_gff_file_name = tuni::extract_file_name(gtf_gff_path)?;
+            run_summary.add_sample(&gtf_gff_file_name, gtf_gff_transcripts.signatures.len());
+            warnings.extend(&gtf_gff_file_name, gtf_gff_transcripts.warnings.drain(..));
+            match &mut external_unifier {
+                Some(external_unifier) => external_unifier.add_sample(
+                    transcript_unifier,
+                    gtf_gff_file_name,
+                    &mut gtf_gff_transcripts,
+                )?,
+                None => transcript_unifier
+                    .group_transcripts(gtf_gff_file_name, &mut gtf_gff_transcripts),
+            }
+        }
+    }
+
+    info!("Unifying transcripts");
+
+    match &mut external_unifier {
+        Some(external_unifier) => external_unifier.unify(transcript_unifier)?,
+        None => transcript_unifier.unify_transcripts(),
+    }
+    run_summary.add_unifier(transcript_unifier);
+
+    for redundant_group in transcript_unifier.redundant_groups() {
+        warnings.add(
+            WarningCategory::RedundantTranscripts,
+            &redundant_group.sample,
+            redundant_group
+                .transcript_ids
+                .iter()
+                .map(|x| tuni::extract_transcript_name(x))
+                .collect::<Vec<&str>>()
+                .join(","),
+        );
+    }
+
+    info!("Writing catalog");
+
+    let staged_catalog = tuni::stage_catalog(catalog_path, transcript_unifier)?;
+
+    info!("Writing unified transcripts");
+
+    let mut expression_matrix = ExpressionMatrix::new(output_args.expression_aggregate);
+    let mut tx2gene = Tx2Gene::new();
+
+    for chunk_paths in gtf_gff_paths.chunks(chunk_size) {
+        if let Some(external_unifier) = &external_unifier {
+            for gtf_gff_path in chunk_paths {
+                external_unifier.load_assignments(
+                    transcript_unifier,
+                    &tuni::extract_file_name(gtf_gff_path)?,
+                )?;
+            }
+        }
+
+        tuni::write_unified_gtf_gffs(
+            &gtf_gff_extension,
+            chunk_paths,
+            output_dir,
+            transcript_unifier,
+            read_options,
+            filter_action,
+            warnings,
+        )?;
+
+        for gtf_gff_path in chunk_paths {
+            if let Some(expression_attribute) = &output_args.expression_attribute {
+                let transcript_values =
+                    tuni::read_expression(gtf_gff_path, expression_attribute, read_options)?;
+                expression_matrix.add_sample(
+                    tuni::extract_file_name(gtf_gff_path)?,
+                    transcript_values,
+                    transcript_unifier,
+                );
+            }
+
+            if output_args.tx2gene {
+                let transcript_genes = tuni::read_attribute(
+                    gtf_gff_path,
+                    &output_args.gene_attribute,
+                    None,
+                    read_options,
+                )?;
+                tx2gene.add_sample(
+                    tuni::extract_file_name(gtf_gff_path)?,
+                    transcript_genes,
+                    transcript_unifier,
+                );
+            }
+        }
+
+        if external_unifier.is_some() {
+            transcript_unifier.clear_assignments();
+        }
+    }
+
+    info!("Writing redundancy report");
+
+    tuni::write_redundancy_report(
+        &output_dir.join(tuni::REDUNDANCY_FILE_NAME),
+        transcript_unifier,
+    )?;
+
+    if output_args.expression_attribute.is_some() {
+        info!("Writing expression matrix");
+
+        tuni::write_expression_matrix(
+            &output_dir.join(tuni::EXPRESSION_FILE_NAME),
+            &expression_matrix,
+            transcript_unifier,
+        )?;
+    }
+
+    if output_args.tx2gene {
+        info!("Writing tx2gene");
+
+        tuni::write_tx2gene(
+            output_dir,
+            &tx2gene,
+            transcript_unifier,
+            output_args.tx2gene_sample_ids,
+        )?;
+    }
+
+    Ok(staged_catalog)
+}
@@ -0,0 +1,213 @@
+//! Read transcript quantifications (salmon, kallisto, RSEM) so they can be
+//! translated to unified IDs.
+
+use crate::error::QuantError;
+use crate::gtf_gff::{self, TranscriptId};
+use log::info;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+/// File name of the count matrix stored in the output directory.
+pub const COUNTS_FILE_NAME: &str = "tuni_counts.tsv";
+
+/// File name of the TPM matrix stored in the output directory.
+pub const TPM_FILE_NAME: &str = "tuni_tpm.tsv";
+
+/// Supported quantification outputs, detected from their header.
+#[derive(Debug, PartialEq)]
+enum QuantFormat {
+    /// salmon "quant.sf".
+    Salmon,
+
+    /// kallisto "abundance.tsv".
+    Kallisto,
+
+    /// RSEM "*.isoforms.results".
+    Rsem,
+}
+
+impl QuantFormat {
+    /// Detect the format from the header columns.
+    ///
+    /// Returns the format if recognised, otherwise `None`.
+    fn from_header(header: &[&str]) -> Option<QuantFormat> {
+        [
+            QuantFormat::Salmon,
+            QuantFormat::Kallisto,
+            QuantFormat::Rsem,
+        ]
+        .into_iter()
+        .find(|x| {
+            let (name, count, tpm) = x.columns();
+            [name, count, tpm].iter().all(|x| header.contains(x))
+        })
+    }
+
+    /// Names of the transcript name, count and TPM columns.
+    fn columns(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            QuantFormat::Salmon => ("Name", "NumReads", "TPM"),
+            QuantFormat::Kallisto => ("target_id", "est_counts", "tpm"),
+            QuantFormat::Rsem => ("transcript_id", "expected_count", "TPM"),
+        }
+    }
+}
+
+/// Counts and TPMs of a sample, keyed by transcript name (e.g. "A.1").
+#[derive(Debug, Default, PartialEq)]
+pub struct Quantification {
+    /// Estimated read count of each transcript.
+    pub counts: HashMap<Arc<str>, f64>,
+
+    /// TPM of each transcript.
+    pub tpms: HashMap<Arc<str>, f64>,
+}
+
+impl Quantification {
+    /// Link the name of each transcript (e.g. "A.1") to its transcript ID, as
+    /// passed to [`Quantification::into_transcript_ids`].
+    pub fn transcript_names<'a>(
+        transcript_ids: impl Iterator<Item = &'a TranscriptId>,
+    ) -> HashMap<Arc<str>, TranscriptId> {
+        transcript_ids
+            .map(|x| {
+                (
+                    Arc::from(gtf_gff::extract_transcript_name(x)),
+                    Arc::clone(x),
+                )
+            })
+            .collect()
+    }
+
+    /// Re-key the counts and TPMs by the transcript IDs of a GTF/GFF.
+    ///
+    /// Returns the re-keyed counts and TPMs, along with the number of
+    /// transcripts not found in the GTF/GFF.
+    pub fn into_transcript_ids(
+        self,
+        transcript_ids: &HashMap<Arc<str>, TranscriptId>,
+    ) -> (
+        HashMap<TranscriptId, f64>,
+        HashMap<TranscriptId, f64>,
+        usize,
+    ) {
+        let n_missing = self
+            .counts
+            .keys()
+            .filter(|x| !transcript_ids.contains_key(*x))
+            .count();
+        let rekey = |values: HashMap<Arc<str>, f64>| {
+            values
+                .into_iter()
+                .filter_map(|(name, value)| Some((Arc::clone(transcript_ids.get(&name)?), value)))
+                .collect::<HashMap<TranscriptId, f64>>()
+        };
+
+        (rekey(self.counts), rekey(self.tpms), n_missing)
+    }
+}
+
+/// Read a salmon, kallisto or RSEM quantification.
+///
+/// # Errors
+///
+/// Returns [`LineReadError`](QuantError::LineReadError) if the file or any
+/// line in the file cannot be read.
+///
+/// Returns [`UnknownFormatError`](QuantError::UnknownFormatError) if the
+/// header does not match salmon, kallisto or RSEM.
+///
+/// Returns [`MalformedLineError`](QuantError::MalformedLineError) if any line
+/// is missing a column or has a non-numeric count/TPM.
+pub fn read_quant(quant_path: &Path) -> Result<Quantification, QuantError> {
+    info!("{}", quant_path.display());
+
+    let quant = File::open(quant_path).map_err(|_| QuantError::LineReadError(quant_path.into()))?;
+    let mut lines = BufReader::new(quant).lines();
+
+    let header = lines
+        .next()
+        .ok_or(QuantError::UnknownFormatError(quant_path.to_path_buf()))?
+        .map_err(|_| QuantError::LineReadError(quant_path.to_path_buf()))?;
+    let header = header.split('\t').collect::<Vec<&str>>();
+
+    let quant_format = QuantFormat::from_header(&header)
+        .ok_or(QuantError::UnknownFormatError(quant_path.to_path_buf()))?;
+    let (name, count, tpm) = quant_format.columns();
+    // Columns are checked to be present by QuantFormat::from_header().
+    let position = |column: &str| header.iter().position(|x| *x == column).unwrap();
+    let (name_index, count_index, tpm_index) = (position(name), position(count), position(tpm));
+
+    let mut quantification = Quantification::default();
+
+    for line in lines {
+        let line = line.map_err(|_| QuantError::LineReadError(quant_path.to_path_buf()))?;
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+
+        let parse = |index: usize| {
+            line_split
+                .get(index)
+                .and_then(|x| x.parse::<f64>().ok())
+                .ok_or(QuantError::MalformedLineError(line.clone()))
+        };
+        let (count, tpm) = (parse(count_index)?, parse(tpm_index)?);
+        let name: Arc<str> = Arc::from(line_split[name_index]);
+
+        quantification.counts.insert(Arc::clone(&name), count);
+        quantification.tpms.insert(name, tpm);
+    }
+
+    Ok(quantification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    #[rstest]
+    #[case("tests/data/unit/quant/quant.sf")]
+    #[case("tests/data/unit/quant/abundance.tsv")]
+    #[case("tests/data/unit/quant/isoforms.results")]
+    fn test_read_quant(#[case] quant_path: &str) {
+        assert_eq!(
+            read_quant(&PathBuf::from(quant_path)).unwrap(),
+            Quantification {
+                counts: HashMap::from([(Arc::from("A"), 20.0), (Arc::from("B"), 7.0)]),
+                tpms: HashMap::from([(Arc::from("A"), 10.5), (Arc::from("B"), 5.0)]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_quant_unknown_format() {
+        let result = read_quant(&PathBuf::from("tests/data/unit/quant/unknown.tsv"));
+        assert!(result.is_err_and(|e| e.to_string().contains("salmon, kallisto or RSEM")));
+    }
+
+    #[test]
+    fn test_into_transcript_ids() {
+        let quant_path = PathBuf::from("tests/data/unit/quant/quant.sf");
+        let transcript_ids = [Arc::from("transcript_id \"A\"")];
+
+        let (counts, tpms, n_missing) = read_quant(&quant_path)
+            .unwrap()
+            .into_transcript_ids(&Quantification::transcript_names(transcript_ids.iter()));
+
+        assert_eq!(
+            counts,
+            HashMap::from([(Arc::from("transcript_id \"A\""), 20.0)])
+        );
+        assert_eq!(
+            tpms,
+            HashMap::from([(Arc::from("transcript_id \"A\""), 10.5)])
+        );
+        assert_eq!(n_missing, 1);
+    }
+}
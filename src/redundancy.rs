@@ -0,0 +1,98 @@
+//! Report transcripts within one sample that share a transcript signature.
+
+use crate::error::RedundancyError;
+use crate::gtf_gff;
+use crate::unify::TranscriptUnifier;
+use log::info;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// File name of the redundancy report stored alongside the unified GTF/GFFs.
+pub const REDUNDANCY_FILE_NAME: &str = "tuni_redundancy.tsv";
+
+/// Write the redundancy report.
+///
+/// Each row lists a set of transcripts from one sample sharing a transcript
+/// signature, with the representative transcript first. Filtered transcripts
+/// are written with a unified ID of ".".
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](RedundancyError::FileCreateError) if the report
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](RedundancyError::FileWriteError) if any line in
+/// the report cannot be written.
+pub fn write_redundancy_report(
+    redundancy_path: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), RedundancyError> {
+    info!("{}", redundancy_path.display());
+
+    let report = File::create(redundancy_path)
+        .map_err(|_| RedundancyError::FileCreateError(redundancy_path.to_path_buf()))?;
+    let mut writer = BufWriter::new(report);
+    let write_error = |_| RedundancyError::FileWriteError(redundancy_path.to_path_buf());
+
+    writeln!(writer, "sample\ttuni_id\tn_transcripts\ttranscript_ids").map_err(write_error)?;
+
+    for redundant_group in transcript_unifier.redundant_groups() {
+        let transcript_names = redundant_group
+            .transcript_ids
+            .iter()
+            .map(|x| gtf_gff::extract_transcript_name(x))
+            .collect::<Vec<&str>>();
+
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            redundant_group.sample,
+            redundant_group.unified_id.as_deref().unwrap_or("."),
+            transcript_names.len(),
+            transcript_names.join(",")
+        )
+        .map_err(write_error)?;
+    }
+
+    writer.flush().map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gtf_gff::ReadOptions;
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_redundancy_report() {
+        let mut transcript_unifier = TranscriptUnifier::new();
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_redundant.gtf"),
+        ];
+
+        for gtf_gff_path in &gtf_gff_paths {
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(gtf_gff_path).unwrap();
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+        transcript_unifier.unify_transcripts();
+
+        let temp_dir = tempdir().unwrap();
+        let redundancy_path = temp_dir.path().join(REDUNDANCY_FILE_NAME);
+        write_redundancy_report(&redundancy_path, &transcript_unifier).unwrap();
+
+        assert_eq!(
+            read_to_string(redundancy_path).unwrap(),
+            "sample\ttuni_id\tn_transcripts\ttranscript_ids\n\
+             sample_redundant.gtf\ttuni_0\t2\tA,A_dup\n\
+             sample_redundant.gtf\ttuni_1\t3\tB,B_dup,B_dup_2\n"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Restrict unification to regions of the genome.
+//!
+//! Transcripts outside of the regions, or on excluded contigs, are filtered
+//! while reading with [`FilterReason::Region`](crate::filter::FilterReason),
+//! before their `TranscriptSignature`s are grouped.
+
+use crate::error::RegionError;
+use crate::gtf_gff::Coordinate;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+/// Region of a chromosome, with 1-based inclusive coordinates as in GTF/GFFs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Chromosome.
+    pub chr: Arc<str>,
+
+    /// Start coordinate.
+    pub start: Coordinate,
+
+    /// End coordinate.
+    pub end: Coordinate,
+}
+
+impl Region {
+    /// Parse a region in the format "chr:start-end", with 1-based inclusive
+    /// coordinates. Commas within coordinates are ignored (e.g. "1,000").
+    ///
+    /// Returns `None` if the region is malformed or the start is after the
+    /// end.
+    pub fn parse(region: &str) -> Option<Region> {
+        let (chr, coordinates) = region.rsplit_once(':')?;
+        let (start, end) = coordinates.split_once('-')?;
+        let parse_coordinate = |x: &str| x.replace(',', "").parse::<Coordinate>().ok();
+
+        let region = Region {
+            chr: Arc::from(chr),
+            start: parse_coordinate(start)?,
+            end: parse_coordinate(end)?,
+        };
+        (!chr.is_empty() && region.start <= region.end).then_some(region)
+    }
+}
+
+/// Sorted (start, end) coordinates of the regions of each chromosome.
+type ChrIntervals = HashMap<Arc<str>, Vec<(Coordinate, Coordinate)>>;
+
+/// Read the regions of a BED file.
+///
+/// BED coordinates (0-based, half-open) are converted to 1-based inclusive
+/// coordinates. Header, track and browser lines are skipped.
+///
+/// # Errors
+///
+/// Returns [`LineReadError`](RegionError::LineReadError) if the BED file or
+/// any line of the BED file cannot be read.
+///
+/// Returns [`BedParseError`](RegionError::BedParseError) if any line does not
+/// start with a chromosome, start and end.
+pub fn read_regions_bed(bed_path: &Path) -> Result<Vec<Region>, RegionError> {
+    let bed = File::open(bed_path).map_err(|_| RegionError::LineReadError(bed_path.into()))?;
+    let mut regions = Vec::new();
+
+    for line in BufReader::new(bed).lines() {
+        let line = line.map_err(|_| RegionError::LineReadError(bed_path.to_path_buf()))?;
+
+        if line.is_empty()
+            || ["#", "track", "browser"]
+                .iter()
+                .any(|x| line.starts_with(x))
+        {
+            continue;
+        }
+
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+        let region = match line_split[..] {
+            [chr, start, end, ..] => start
+                .parse::<Coordinate>()
+                .ok()
+                .zip(end.parse::<Coordinate>().ok())
+                .filter(|(start, end)| start < end)
+                .map(|(start, end)| Region {
+                    chr: Arc::from(chr),
+                    start: start + 1,
+                    end,
+                }),
+            _ => None,
+        };
+
+        regions.push(region.ok_or(RegionError::BedParseError(line.clone()))?);
+    }
+
+    Ok(regions)
+}
+
+/// Restrict unification to contigs and regions of the genome.
+///
+/// Chromosome names are compared after normalisation, see
+/// [`ChrAliases`](crate::chr_alias::ChrAliases). By default, every contig is
+/// included.
+#[derive(Debug, Default)]
+pub struct RegionFilter {
+    /// Only include these contigs.
+    include_contigs: Option<HashSet<Arc<str>>>,
+
+    /// Exclude these contigs.
+    exclude_contigs: HashSet<Arc<str>>,
+
+    /// Only include transcripts overlapping these regions.
+    regions: Option<ChrIntervals>,
+}
+
+impl RegionFilter {
+    /// Create `RegionFilter` including every contig.
+    pub fn new() -> RegionFilter {
+        RegionFilter::default()
+    }
+
+    /// Only include `contigs`.
+    pub fn with_include_contigs(
+        mut self,
+        contigs: impl IntoIterator<Item = Arc<str>>,
+    ) -> RegionFilter {
+        self.include_contigs = Some(contigs.into_iter().collect());
+        self
+    }
+
+    /// Exclude `contigs`, even if they are included or contain regions.
+    pub fn with_exclude_contigs(
+        mut self,
+        contigs: impl IntoIterator<Item = Arc<str>>,
+    ) -> RegionFilter {
+        self.exclude_contigs.extend(contigs);
+        self
+    }
+
+    /// Only include transcripts overlapping any of `regions`.
+    pub fn with_regions(mut self, regions: impl IntoIterator<Item = Region>) -> RegionFilter {
+        let chr_regions = self.regions.get_or_insert_with(HashMap::new);
+        for region in regions {
+            chr_regions
+                .entry(region.chr)
+                .or_default()
+                .push((region.start, region.end));
+        }
+        for intervals in chr_regions.values_mut() {
+            intervals.sort_unstable();
+        }
+        self
+    }
+
+    /// Returns true if any contig or region is excluded, otherwise false.
+    pub fn is_active(&self) -> bool {
+        self.include_contigs.is_some() || !self.exclude_contigs.is_empty() || self.regions.is_some()
+    }
+
+    /// Returns true if the (normalised) chromosome `chr` may contain included
+    /// transcripts, otherwise false.
+    pub fn includes_contig(&self, chr: &str) -> bool {
+        !self.exclude_contigs.contains(chr)
+            && self
+                .include_contigs
+                .as_ref()
+                .is_none_or(|x| x.contains(chr))
+            && self.regions.as_ref().is_none_or(|x| x.contains_key(chr))
+    }
+
+    /// Returns true if `start`-`end` of the (normalised) chromosome `chr` is
+    /// included, i.e. it is on an included contig and overlaps any region,
+    /// otherwise false.
+    pub fn includes(&self, chr: &str, start: Coordinate, end: Coordinate) -> bool {
+        if !self.includes_contig(chr) {
+            return false;
+        }
+
+        let Some(regions) = &self.regions else {
+            return true;
+        };
+        // Regions starting after `end` cannot overlap.
+        let intervals = &regions[chr];
+        let n_candidates = intervals.partition_point(|(x, _)| *x <= end);
+        intervals[..n_candidates].iter().any(|(_, x)| *x >= start)
+    }
+}
+
+impl fmt::Display for RegionFilter {
+    /// Describe the included contigs and the number of regions, e.g.
+    /// "include_contigs=chr1,chr2; regions=3".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sorted = |contigs: &HashSet<Arc<str>>| {
+            contigs
+                .iter()
+                .map(|x| x.as_ref())
+                .collect::<BTreeSet<&str>>()
+                .into_iter()
+                .collect::<Vec<&str>>()
+                .join(",")
+        };
+
+        let mut descriptions = Vec::new();
+        if let Some(include_contigs) = &self.include_contigs {
+            descriptions.push(format!("include_contigs={}", sorted(include_contigs)));
+        }
+        if !self.exclude_contigs.is_empty() {
+            descriptions.push(format!("exclude_contigs={}", sorted(&self.exclude_contigs)));
+        }
+        if let Some(regions) = &self.regions {
+            let n_regions = regions.values().map(|x| x.len()).sum::<usize>();
+            descriptions.push(format!("regions={}", n_regions));
+        }
+
+        write!(f, "{}", descriptions.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[rstest]
+    #[case("chr1:100-200", Some(("chr1", 100, 200)))]
+    #[case("chr1:1,000-2,000", Some(("chr1", 1000, 2000)))]
+    #[case("HLA-A*01:01:1-10", Some(("HLA-A*01:01", 1, 10)))]
+    #[case("chr1:200-100", None)]
+    #[case("chr1", None)]
+    #[case(":1-10", None)]
+    #[case("chr1:a-10", None)]
+    fn test_parse_region(#[case] region: &str, #[case] expected: Option<(&str, u64, u64)>) {
+        assert_eq!(
+            Region::parse(region),
+            expected.map(|(chr, start, end)| Region {
+                chr: Arc::from(chr),
+                start,
+                end
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_regions_bed() {
+        let mut bed = NamedTempFile::new().unwrap();
+        writeln!(bed, "track name=test\nchr1\t99\t200\tname\nchr2\t0\t10").unwrap();
+
+        assert_eq!(
+            read_regions_bed(bed.path()).unwrap(),
+            [
+                Region::parse("chr1:100-200").unwrap(),
+                Region::parse("chr2:1-10").unwrap()
+            ]
+        );
+
+        writeln!(bed, "chr3\t10").unwrap();
+        assert!(read_regions_bed(bed.path())
+            .is_err_and(|e| e.to_string().contains("chromosome, start and end")));
+    }
+
+    #[rstest]
+    #[case("chr1", 150, 160, true)]
+    #[case("chr1", 50, 100, true)]
+    #[case("chr1", 50, 99, false)]
+    #[case("chr1", 201, 299, false)]
+    #[case("chr1", 250, 400, true)]
+    #[case("chr2", 1, 1000, false)]
+    #[case("chrM", 1, 1000, false)]
+    fn test_region_filter(
+        #[case] chr: &str,
+        #[case] start: Coordinate,
+        #[case] end: Coordinate,
+        #[case] expected: bool,
+    ) {
+        let region_filter = RegionFilter::new()
+            .with_regions([
+                Region::parse("chr1:300-400").unwrap(),
+                Region::parse("chr1:100-200").unwrap(),
+                Region::parse("chrM:1-100").unwrap(),
+            ])
+            .with_exclude_contigs([Arc::from("chrM")]);
+
+        assert!(region_filter.is_active());
+        assert_eq!(region_filter.includes(chr, start, end), expected);
+    }
+
+    // Regions are only counted, as a BED file may contain many.
+    #[rstest]
+    #[case(RegionFilter::new(), "")]
+    #[case(
+        RegionFilter::new().with_include_contigs([Arc::from("chr2"), Arc::from("chr1")]),
+        "include_contigs=chr1,chr2"
+    )]
+    #[case(
+        RegionFilter::new()
+            .with_exclude_contigs([Arc::from("chrM")])
+            .with_regions([Region::parse("chr1:1-10").unwrap(), Region::parse("chr2:1-10").unwrap()])
+            .with_regions([Region::parse("chr1:20-30").unwrap()]),
+        "exclude_contigs=chrM; regions=3"
+    )]
+    fn test_region_filter_display(#[case] region_filter: RegionFilter, #[case] expected: &str) {
+        assert_eq!(region_filter.to_string(), expected);
+    }
+
+    #[test]
+    fn test_region_filter_contigs() {
+        let region_filter = RegionFilter::new()
+            .with_include_contigs([Arc::from("chr1"), Arc::from("chr2")])
+            .with_exclude_contigs([Arc::from("chr2")]);
+
+        assert!(region_filter.includes("chr1", 1, 10));
+        assert!(!region_filter.includes("chr2", 1, 10));
+        assert!(!region_filter.includes("chr3", 1, 10));
+        assert!(!RegionFilter::new().is_active());
+        assert!(RegionFilter::new().includes("chr3", 1, 10));
+    }
+}
@@ -0,0 +1,703 @@
+//! Merge the outputs of runs that each unified one shard of the genome.
+//!
+//! Transcripts on different contigs never share a `TranscriptSignature`, so
+//! each shard (a set of contigs, see [`ReadOptions::contigs`]) can be unified
+//! independently. Shards are merged by merging their catalogs, then
+//! translating the unified IDs of each shard's GTF/GFFs, redundancy report,
+//! expression matrix and tx2gene tables into the merged unified IDs. As unified
+//! IDs follow the order of `TranscriptSignature`s, the merged unified IDs
+//! match those of a single run across every contig.
+//!
+//! Each shard is a separate run of `tuni`; shards are not looped over within
+//! one run.
+//!
+//! [`ReadOptions::contigs`]: tuni::ReadOptions::contigs
+
+use crate::cli_error::ShardError;
+use log::info;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Lines, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tuni::{ShardLines, TranscriptUnifier, REDUNDANCY_FILE_NAME, TX2GENE_SAMPLES_FILE_NAME};
+
+/// Extensions of the unified GTF/GFFs written by each shard.
+const UNIFIED_EXTENSIONS: [&str; 2] = [".tuni.gtf", ".tuni.gff"];
+
+/// Prefixes of the unified ID attribute in GTF and GFF lines.
+const UNIFIED_ID_PREFIXES: [&str; 2] = ["tuni_id \"", "tuni_id="];
+
+/// Header and rows of a tab-separated table read from every shard.
+type ShardTable = (String, Vec<Vec<String>>);
+
+/// Find the file names of the unified GTF/GFFs across every shard directory.
+///
+/// Returns file names in alphabetical order.
+///
+/// # Errors
+///
+/// Returns [`DirReadError`](ShardError::DirReadError) if any shard directory
+/// cannot be read.
+pub fn find_unified_gtf_gffs(shard_dirs: &[PathBuf]) -> Result<Vec<String>, ShardError> {
+    let mut file_names = BTreeSet::new();
+
+    for shard_dir in shard_dirs {
+        let entries =
+            fs::read_dir(shard_dir).map_err(|_| ShardError::DirReadError(shard_dir.clone()))?;
+        for entry in entries {
+            let entry = entry.map_err(|_| ShardError::DirReadError(shard_dir.clone()))?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if UNIFIED_EXTENSIONS.iter().any(|x| file_name.ends_with(x)) {
+                file_names.insert(file_name);
+            }
+        }
+    }
+
+    Ok(file_names.into_iter().collect())
+}
+
+/// Name a shard in the merged catalogs, see [`tuni::merge_catalogs`].
+pub fn shard_name(shard_dir: &Path) -> Arc<str> {
+    Arc::from(shard_dir.to_string_lossy())
+}
+
+/// Write the unified GTF/GFF `file_name` of every shard into `output_dir`,
+/// translating the unified IDs of each shard into merged unified IDs.
+///
+/// Lines are merged in the order of the input GTF/GFF, using the
+/// [`ShardLines`] footer of each shard, matching a single run across every
+/// contig. Header lines (starting with "#") found in several shards are only
+/// written once. If any shard has no footer, lines are instead written shard
+/// by shard, in the order of `shard_dirs`, with header lines only written
+/// from the first shard containing the file. Shards without the file are
+/// skipped.
+///
+/// `transcript_unifier` links each (shard name, unified ID) to a merged
+/// unified ID, as created by [`tuni::merge_catalogs`].
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](ShardError::FileCreateError) if the merged
+/// GTF/GFF cannot be created.
+///
+/// Returns [`LineReadError`](ShardError::LineReadError) if any line of a shard
+/// cannot be read.
+///
+/// Returns [`ShardLinesError`](ShardError::ShardLinesError) if the footer of a
+/// shard lists more lines than the shard contains.
+///
+/// Returns [`UnknownUnifiedIdError`](ShardError::UnknownUnifiedIdError) if a
+/// unified ID is not in its shard's catalog.
+///
+/// Returns [`FileWriteError`](ShardError::FileWriteError) if any line of the
+/// merged GTF/GFF cannot be written.
+pub fn write_merged_gtf_gff(
+    file_name: &str,
+    shard_dirs: &[PathBuf],
+    output_dir: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), ShardError> {
+    let output_path = output_dir.join(file_name);
+    info!("{}", output_path.display());
+
+    let mut temp_path = output_path.clone();
+    temp_path.as_mut_os_string().push(".tmp");
+    let write_error = |_| ShardError::FileWriteError(temp_path.clone());
+
+    let mut writer = BufWriter::new(
+        File::create(&temp_path).map_err(|_| ShardError::FileCreateError(temp_path.clone()))?,
+    );
+
+    let shard_paths = shard_dirs
+        .iter()
+        .map(|x| (shard_name(x), x.join(file_name)))
+        .filter(|(_, x)| x.is_file())
+        .collect::<Vec<(Arc<str>, PathBuf)>>();
+    let all_shard_lines = shard_paths
+        .iter()
+        .map(|(_, x)| read_shard_lines(x))
+        .collect::<Result<Option<Vec<ShardLines>>, ShardError>>()?;
+
+    let mut write_line = |line: String, shard_name: &Arc<str>| {
+        let line = match line.starts_with('#') {
+            true => line,
+            false => translate_unified_id(line, shard_name, transcript_unifier)?,
+        };
+        writeln!(writer, "{}", line).map_err(write_error)
+    };
+
+    match all_shard_lines {
+        Some(all_shard_lines) => {
+            let mut shards = shard_paths
+                .iter()
+                .zip(&all_shard_lines)
+                .map(|((shard_name, shard_path), shard_lines)| {
+                    ShardGtfGff::open(shard_name, shard_path, shard_lines)
+                })
+                .collect::<Result<Vec<ShardGtfGff>, ShardError>>()?;
+
+            // Take the shard with the earliest next line, preferring earlier
+            // shards, skipping the same header line in other shards.
+            while let Some(i) = (0..shards.len())
+                .filter(|i| shards[*i].next.is_some())
+                .min_by_key(|i| shards[*i].next.as_ref().map(|(x, _)| *x))
+            {
+                let (line_number, line) = shards[i].advance()?.unwrap();
+                if line.starts_with('#') {
+                    for shard in &mut shards {
+                        if shard
+                            .next
+                            .as_ref()
+                            .is_some_and(|(x, y)| *x == line_number && y.starts_with('#'))
+                        {
+                            shard.advance()?;
+                        }
+                    }
+                }
+                write_line(line, shards[i].shard_name)?;
+            }
+        }
+        None => {
+            for (i, (shard_name, shard_path)) in shard_paths.iter().enumerate() {
+                let reader = BufReader::new(
+                    File::open(shard_path)
+                        .map_err(|_| ShardError::LineReadError(shard_path.clone()))?,
+                );
+
+                for line in reader.lines() {
+                    let line = line.map_err(|_| ShardError::LineReadError(shard_path.clone()))?;
+                    if i == 0 || !line.starts_with('#') {
+                        write_line(line, shard_name)?;
+                    }
+                }
+            }
+        }
+    }
+
+    writer.flush().map_err(write_error)?;
+    fs::rename(&temp_path, &output_path)
+        .map_err(|_| ShardError::FileCreateError(output_path.clone()))?;
+
+    Ok(())
+}
+
+/// Lines of a shard's unified GTF/GFF, with their input line numbers.
+struct ShardGtfGff<'a> {
+    /// Name of the shard.
+    shard_name: &'a Arc<str>,
+
+    /// Path of the shard's GTF/GFF.
+    shard_path: &'a Path,
+
+    /// Lines of the shard's GTF/GFF.
+    lines: Lines<BufReader<File>>,
+
+    /// Input line number of each line, ending before the footer.
+    line_numbers: Box<dyn Iterator<Item = usize> + 'a>,
+
+    /// Next line to be merged, if any, with its input line number.
+    next: Option<(usize, String)>,
+}
+
+impl<'a> ShardGtfGff<'a> {
+    /// Open a shard's GTF/GFF, reading its first line.
+    fn open(
+        shard_name: &'a Arc<str>,
+        shard_path: &'a Path,
+        shard_lines: &'a ShardLines,
+    ) -> Result<ShardGtfGff<'a>, ShardError> {
+        let file =
+            File::open(shard_path).map_err(|_| ShardError::LineReadError(shard_path.into()))?;
+        let mut shard_gtf_gff = ShardGtfGff {
+            shard_name,
+            shard_path,
+            lines: BufReader::new(file).lines(),
+            line_numbers: Box::new(shard_lines.line_numbers()),
+            next: None,
+        };
+        shard_gtf_gff.advance()?;
+        Ok(shard_gtf_gff)
+    }
+
+    /// Read the next line, returning the current line.
+    fn advance(&mut self) -> Result<Option<(usize, String)>, ShardError> {
+        let next = match self.line_numbers.next() {
+            Some(line_number) => {
+                let line = self
+                    .lines
+                    .next()
+                    .ok_or_else(|| ShardError::ShardLinesError(self.shard_path.into()))?
+                    .map_err(|_| ShardError::LineReadError(self.shard_path.into()))?;
+                Some((line_number, line))
+            }
+            None => None,
+        };
+        Ok(std::mem::replace(&mut self.next, next))
+    }
+}
+
+/// Read the [`ShardLines`] footer of a shard's unified GTF/GFF.
+///
+/// Returns `None` if the last line is not a footer.
+///
+/// # Errors
+///
+/// Returns [`LineReadError`](ShardError::LineReadError) if the shard's GTF/GFF
+/// cannot be read.
+fn read_shard_lines(shard_path: &Path) -> Result<Option<ShardLines>, ShardError> {
+    let read_error = |_| ShardError::LineReadError(shard_path.to_path_buf());
+    let mut file = File::open(shard_path).map_err(read_error)?;
+    let len = file.metadata().map_err(read_error)?.len();
+
+    // Read increasingly large chunks from the end until the last line is found.
+    let mut chunk_len = 4096;
+    loop {
+        let start = len.saturating_sub(chunk_len);
+        file.seek(SeekFrom::Start(start)).map_err(read_error)?;
+        let mut chunk = Vec::new();
+        file.read_to_end(&mut chunk).map_err(read_error)?;
+
+        let chunk = chunk.strip_suffix(b"\n").unwrap_or(&chunk);
+        let last_line = match chunk.iter().rposition(|x| *x == b'\n') {
+            Some(i) => &chunk[i + 1..],
+            None if start == 0 => chunk,
+            None => {
+                chunk_len *= 2;
+                continue;
+            }
+        };
+
+        return Ok(std::str::from_utf8(last_line)
+            .ok()
+            .and_then(ShardLines::from_footer));
+    }
+}
+
+/// Write the redundancy report of every shard into `output_dir`, translating
+/// the unified IDs of each shard into merged unified IDs.
+///
+/// Rows are sorted by sample then transcript IDs, matching a single run across
+/// every contig. Nothing is written if no shard contains a redundancy report.
+///
+/// # Errors
+///
+/// See [`write_merged_unified_table`].
+pub fn write_merged_redundancy_report(
+    shard_dirs: &[PathBuf],
+    output_dir: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), ShardError> {
+    let Some((header, mut rows)) =
+        read_shard_tables(REDUNDANCY_FILE_NAME, shard_dirs, 1, transcript_unifier)?
+    else {
+        return Ok(());
+    };
+
+    rows.sort_by_cached_key(|row| {
+        let transcript_ids = row
+            .get(3)
+            .map(|x| x.split(',').map(String::from).collect::<Vec<String>>());
+        (row[0].clone(), transcript_ids)
+    });
+
+    write_table(&output_dir.join(REDUNDANCY_FILE_NAME), &header, &rows)
+}
+
+/// Write the table `file_name` of every shard into `output_dir`, where each
+/// row starts with a unified ID (e.g. the expression matrix), translating the
+/// unified IDs of each shard into merged unified IDs.
+///
+/// Rows follow the order of the merged catalog, matching a single run across
+/// every contig. Nothing is written if no shard contains the table.
+///
+/// `transcript_unifier` links each (shard name, unified ID) to a merged
+/// unified ID, as created by [`tuni::merge_catalogs`].
+///
+/// # Errors
+///
+/// Returns [`MissingOutputError`](ShardError::MissingOutputError) if the table
+/// is only found in some shards.
+///
+/// Returns [`HeaderMismatchError`](ShardError::HeaderMismatchError) if the
+/// header of a shard's table differs from the first shard, e.g. the shards
+/// were run over different samples.
+///
+/// Returns [`LineReadError`](ShardError::LineReadError) if any line of a shard
+/// cannot be read.
+///
+/// Returns [`UnknownUnifiedIdError`](ShardError::UnknownUnifiedIdError) if a
+/// unified ID is not in its shard's catalog.
+///
+/// Returns [`FileCreateError`](ShardError::FileCreateError) or
+/// [`FileWriteError`](ShardError::FileWriteError) if the merged table cannot
+/// be written.
+pub fn write_merged_unified_table(
+    file_name: &str,
+    shard_dirs: &[PathBuf],
+    output_dir: &Path,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<(), ShardError> {
+    let Some((header, rows)) = read_shard_tables(file_name, shard_dirs, 0, transcript_unifier)?
+    else {
+        return Ok(());
+    };
+
+    let mut unified_rows = rows
+        .into_iter()
+        .map(|row| (row[0].clone(), row))
+        .collect::<HashMap<String, Vec<String>>>();
+    let rows = transcript_unifier
+        .unified_signatures()
+        .values()
+        .filter_map(|x| unified_rows.remove(x.as_ref()))
+        .collect::<Vec<Vec<String>>>();
+
+    write_table(&output_dir.join(file_name), &header, &rows)
+}
+
+/// Write the tx2gene table of sample transcripts of every shard into
+/// `output_dir`, translating the unified IDs of each shard into merged unified
+/// IDs.
+///
+/// Rows are grouped by sample, in the order of `samples`, then sorted,
+/// matching a single run across every contig. Nothing is written if no shard
+/// contains the table.
+///
+/// # Errors
+///
+/// See [`write_merged_unified_table`].
+pub fn write_merged_tx2gene_samples(
+    shard_dirs: &[PathBuf],
+    output_dir: &Path,
+    transcript_unifier: &TranscriptUnifier,
+    samples: &[&str],
+) -> Result<(), ShardError> {
+    let Some((header, mut rows)) =
+        read_shard_tables(TX2GENE_SAMPLES_FILE_NAME, shard_dirs, 2, transcript_unifier)?
+    else {
+        return Ok(());
+    };
+
+    let sample_index = |sample: &str| samples.iter().position(|x| *x == sample);
+    rows.sort_by(|a, b| (sample_index(&a[0]), a).cmp(&(sample_index(&b[0]), b)));
+
+    write_table(&output_dir.join(TX2GENE_SAMPLES_FILE_NAME), &header, &rows)
+}
+
+/// Read the tab-separated table `file_name` of every shard, translating the
+/// unified IDs in `column` into merged unified IDs. Unified IDs of "." (e.g.
+/// filtered transcripts) are kept. Genes generated from the unified ID (see
+/// [`tuni::unified_gene_id`]) are generated again from the merged unified
+/// ID.
+///
+/// Returns the header and the rows of every shard, in the order of
+/// `shard_dirs`, or `None` if no shard contains the table.
+///
+/// # Errors
+///
+/// See [`write_merged_unified_table`].
+fn read_shard_tables(
+    file_name: &str,
+    shard_dirs: &[PathBuf],
+    column: usize,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<Option<ShardTable>, ShardError> {
+    let shard_paths = shard_dirs
+        .iter()
+        .map(|x| x.join(file_name))
+        .collect::<Vec<PathBuf>>();
+    if shard_paths.iter().all(|x| !x.is_file()) {
+        return Ok(None);
+    }
+
+    let mut header = None;
+    let mut rows = Vec::new();
+
+    for (shard_dir, shard_path) in shard_dirs.iter().zip(shard_paths) {
+        if !shard_path.is_file() {
+            return Err(ShardError::MissingOutputError(shard_path));
+        }
+
+        let shard_name = shard_name(shard_dir);
+        let reader = BufReader::new(
+            File::open(&shard_path).map_err(|_| ShardError::LineReadError(shard_path.clone()))?,
+        );
+        let mut lines = reader.lines();
+
+        let shard_header = lines
+            .next()
+            .transpose()
+            .map_err(|_| ShardError::LineReadError(shard_path.clone()))?
+            .unwrap_or_default();
+        match &header {
+            Some(header) if *header != shard_header => {
+                return Err(ShardError::HeaderMismatchError(shard_path))
+            }
+            Some(_) => (),
+            None => header = Some(shard_header),
+        }
+
+        for line in lines {
+            let line = line.map_err(|_| ShardError::LineReadError(shard_path.clone()))?;
+            let mut row = line.split('\t').map(String::from).collect::<Vec<String>>();
+
+            let unified_id = row
+                .get_mut(column)
+                .ok_or_else(|| ShardError::MalformedLineError(line.clone()))?;
+            if unified_id != "." {
+                let merged_id = transcript_unifier
+                    .get_unified_id(&shard_name, unified_id)
+                    .ok_or_else(|| ShardError::UnknownUnifiedIdError(line.clone()))?;
+                let unified_gene_id = tuni::unified_gene_id(unified_id);
+                *unified_id = merged_id.to_string();
+
+                for value in row.iter_mut().filter(|x| **x == unified_gene_id) {
+                    *value = tuni::unified_gene_id(merged_id);
+                }
+            }
+
+            rows.push(row);
+        }
+    }
+
+    Ok(header.map(|header| (header, rows)))
+}
+
+/// Write a tab-separated table.
+fn write_table(output_path: &Path, header: &str, rows: &[Vec<String>]) -> Result<(), ShardError> {
+    info!("{}", output_path.display());
+
+    let write_error = |_| ShardError::FileWriteError(output_path.to_path_buf());
+    let mut writer = BufWriter::new(
+        File::create(output_path)
+            .map_err(|_| ShardError::FileCreateError(output_path.to_path_buf()))?,
+    );
+
+    writeln!(writer, "{}", header).map_err(write_error)?;
+    for row in rows {
+        writeln!(writer, "{}", row.join("\t")).map_err(write_error)?;
+    }
+
+    writer.flush().map_err(write_error)
+}
+
+/// Replace the unified ID of a line, if any, with its merged unified ID.
+fn translate_unified_id(
+    mut line: String,
+    shard_name: &Arc<str>,
+    transcript_unifier: &TranscriptUnifier,
+) -> Result<String, ShardError> {
+    let Some(start) = UNIFIED_ID_PREFIXES
+        .iter()
+        .find_map(|x| line.find(x).map(|i| i + x.len()))
+    else {
+        return Ok(line);
+    };
+    let end = line[start..]
+        .find(['"', ';'])
+        .map_or(line.len(), |i| start + i);
+
+    let merged_id = transcript_unifier
+        .get_unified_id(shard_name, &line[start..end])
+        .ok_or_else(|| ShardError::UnknownUnifiedIdError(line.clone()))?;
+
+    line.replace_range(start..end, merged_id);
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+    use tuni::{ReadOptions, RegionFilter, TranscriptSignature};
+
+    #[rstest]
+    #[case(
+        "chr2\tt\texon\t1\t10\t.\t+\t.\ttranscript_id \"A\"; tuni_id \"tuni_0\";",
+        "chr2\tt\texon\t1\t10\t.\t+\t.\ttranscript_id \"A\"; tuni_id \"tuni_1\";"
+    )]
+    #[case(
+        "chr2\tt\texon\t1\t10\t.\t+\t.\ttranscript_id=A; tuni_id=tuni_0;",
+        "chr2\tt\texon\t1\t10\t.\t+\t.\ttranscript_id=A; tuni_id=tuni_1;"
+    )]
+    #[case(
+        "chr2\tt\tgene\t1\t10\t.\t+\t.\tgene_id=G;",
+        "chr2\tt\tgene\t1\t10\t.\t+\t.\tgene_id=G;"
+    )]
+    fn test_translate_unified_id(#[case] line: &str, #[case] expected: &str) {
+        // Each shard gives its only transcript "tuni_0".
+        let catalogs = ["chr1", "chr2"].map(|chr| {
+            (
+                Arc::from(format!("shard_{}", chr)),
+                BTreeMap::from([(
+                    TranscriptSignature::from(Arc::from(chr), Arc::from("+"), [1, 10], []),
+                    Arc::from("tuni_0"),
+                )]),
+            )
+        });
+        let transcript_unifier = tuni::merge_catalogs(&catalogs);
+
+        let shard_name = Arc::from("shard_chr2");
+        assert_eq!(
+            translate_unified_id(line.to_string(), &shard_name, &transcript_unifier).unwrap(),
+            expected
+        );
+
+        let unknown_line = line.replace("tuni_0", "tuni_5");
+        let result = translate_unified_id(unknown_line.clone(), &shard_name, &transcript_unifier);
+        match line.contains("tuni_id") {
+            true => assert!(result.is_err_and(|e| e.to_string().contains("not found"))),
+            false => assert_eq!(result.unwrap(), unknown_line),
+        }
+    }
+
+    // Test that tables are only merged if found in every shard, with the same
+    // header, translating their unified IDs and generated genes.
+    #[test]
+    fn test_write_merged_unified_table() {
+        // Each shard gives its only transcript "tuni_0".
+        let shard_temp_dirs = [tempdir().unwrap(), tempdir().unwrap()];
+        let shard_dirs = shard_temp_dirs
+            .iter()
+            .map(|x| x.path().to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        let catalogs = ["chr1", "chr2"]
+            .iter()
+            .zip(&shard_dirs)
+            .map(|(chr, shard_dir)| {
+                (
+                    shard_name(shard_dir),
+                    BTreeMap::from([(
+                        TranscriptSignature::from(Arc::from(*chr), Arc::from("+"), [1, 10], []),
+                        Arc::from("tuni_0"),
+                    )]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let transcript_unifier = tuni::merge_catalogs(&catalogs);
+        let output_dir = tempdir().unwrap();
+        let merge = || {
+            write_merged_unified_table(
+                "table.tsv",
+                &shard_dirs,
+                output_dir.path(),
+                &transcript_unifier,
+            )
+        };
+
+        // Nothing is written without any tables.
+        merge().unwrap();
+        assert!(!output_dir.path().join("table.tsv").exists());
+
+        fs::write(shard_dirs[0].join("table.tsv"), "tuni_id\ts1\ntuni_0\t1\n").unwrap();
+        assert!(matches!(merge(), Err(ShardError::MissingOutputError(_))));
+
+        fs::write(shard_dirs[1].join("table.tsv"), "tuni_id\ts2\ntuni_0\t2\n").unwrap();
+        assert!(matches!(merge(), Err(ShardError::HeaderMismatchError(_))));
+
+        // The second shard's transcript is on chr2, so comes last, with its
+        // generated gene following its merged unified ID.
+        fs::write(
+            shard_dirs[1].join("table.tsv"),
+            "tuni_id\ts1\ntuni_0\ttuni_gene_0\n",
+        )
+        .unwrap();
+        merge().unwrap();
+        assert_eq!(
+            fs::read_to_string(output_dir.path().join("table.tsv")).unwrap(),
+            "tuni_id\ts1\ntuni_0\t1\ntuni_1\ttuni_gene_1\n"
+        );
+    }
+
+    // Test that unifying each chromosome as a separate shard, then merging,
+    // gives the same unified IDs and lines as unifying every chromosome at
+    // once, with or without a region filter header.
+    #[rstest]
+    #[case(&[])]
+    #[case(&["chrM"])]
+    fn test_write_merged_gtf_gff(#[case] exclude_contigs: &[&str]) {
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/integration/sample_1.gtf"),
+            PathBuf::from("tests/data/integration/sample_2.gtf"),
+        ];
+
+        let unify = |contigs: Option<&[&str]>, output_dir: &Path| {
+            let read_options = ReadOptions {
+                contigs: contigs.map(|x| x.iter().map(|x| Arc::from(*x)).collect()),
+                region_filter: RegionFilter::new()
+                    .with_exclude_contigs(exclude_contigs.iter().map(|x| Arc::from(*x))),
+                ..Default::default()
+            };
+
+            let mut transcript_unifier = TranscriptUnifier::new();
+            for gtf_gff_path in &gtf_gff_paths {
+                let mut gtf_gff_transcripts =
+                    tuni::read_gtf_gff(gtf_gff_path, &read_options).unwrap();
+                transcript_unifier.group_transcripts(
+                    tuni::extract_file_name(gtf_gff_path).unwrap(),
+                    &mut gtf_gff_transcripts,
+                );
+            }
+            transcript_unifier.unify_transcripts();
+
+            tuni::write_unified_gtf_gffs(
+                "gtf",
+                &gtf_gff_paths,
+                output_dir,
+                &transcript_unifier,
+                &read_options,
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+            transcript_unifier
+        };
+
+        let joint_dir = tempdir().unwrap();
+        let joint_unifier = unify(None, joint_dir.path());
+
+        // Shards are deliberately not in chromosome order.
+        let shards: [&[&str]; 3] = [&["chr3", "chr5"], &["chr1"], &["chr2", "chr4"]];
+        let shard_temp_dirs = shards.map(|_| tempdir().unwrap());
+        let shard_dirs = shard_temp_dirs
+            .iter()
+            .map(|x| x.path().to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        let catalogs = shards
+            .iter()
+            .zip(&shard_dirs)
+            .map(|(contigs, shard_dir)| {
+                let shard_unifier = unify(Some(contigs), shard_dir);
+                (
+                    shard_name(shard_dir),
+                    shard_unifier.unified_signatures().clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let merged_unifier = tuni::merge_catalogs(&catalogs);
+        assert_eq!(
+            merged_unifier.unified_signatures(),
+            joint_unifier.unified_signatures()
+        );
+
+        let file_names = find_unified_gtf_gffs(&shard_dirs).unwrap();
+        assert_eq!(file_names, ["sample_1.tuni.gtf", "sample_2.tuni.gtf"]);
+
+        let merged_dir = tempdir().unwrap();
+        for file_name in &file_names {
+            write_merged_gtf_gff(file_name, &shard_dirs, merged_dir.path(), &merged_unifier)
+                .unwrap();
+
+            assert_eq!(
+                fs::read_to_string(merged_dir.path().join(file_name)).unwrap(),
+                fs::read_to_string(joint_dir.path().join(file_name)).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Summarise each run of tuni, written as JSON and printed as a table.
+
+use crate::cli_error::SummaryError;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+use tuni::{TranscriptUnifier, Warnings};
+
+/// File name of the run summary stored alongside the unified GTF/GFFs.
+pub const SUMMARY_FILE_NAME: &str = "tuni_summary.json";
+
+/// Number of transcripts read from a sample.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SampleSummary {
+    /// Sample (GTF/GFF file name).
+    pub sample: String,
+
+    /// Number of transcripts with at least one exon/CDS line.
+    pub n_transcripts: usize,
+}
+
+/// Summary of a run of tuni.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// Transcripts read from each sample, in the order samples were read.
+    pub samples: Vec<SampleSummary>,
+
+    /// Number of unified IDs assigned to transcripts in this run.
+    pub n_unified_ids: usize,
+
+    /// Number of unified IDs found in more than one sample.
+    pub n_shared: usize,
+
+    /// Number of unified IDs found in only one sample.
+    pub n_private: usize,
+
+    /// Fraction of unified IDs with any CDS regions.
+    pub cds_fraction: f64,
+
+    /// Number of warnings of each category.
+    pub warnings: BTreeMap<String, usize>,
+
+    /// Number of sample transcripts not unified, by filter reason.
+    pub skipped: BTreeMap<String, usize>,
+
+    /// Seconds from the start to the end of the run.
+    pub wall_time_secs: f64,
+
+    /// Peak resident memory in bytes, if available on this platform.
+    pub peak_memory_bytes: Option<u64>,
+
+    /// Time the run started.
+    #[serde(skip, default = "Instant::now")]
+    start: Instant,
+}
+
+impl Default for RunSummary {
+    fn default() -> RunSummary {
+        RunSummary::new()
+    }
+}
+
+impl RunSummary {
+    /// Initialise `RunSummary`, starting the wall time.
+    pub fn new() -> RunSummary {
+        RunSummary {
+            samples: Vec::new(),
+            n_unified_ids: 0,
+            n_shared: 0,
+            n_private: 0,
+            cds_fraction: 0.0,
+            warnings: BTreeMap::new(),
+            skipped: BTreeMap::new(),
+            wall_time_secs: 0.0,
+            peak_memory_bytes: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Add the number of transcripts read from a sample.
+    pub fn add_sample(&mut self, sample: &str, n_transcripts: usize) {
+        self.samples.push(SampleSummary {
+            sample: sample.to_string(),
+            n_transcripts,
+        });
+    }
+
+    /// Add the number of warnings of each category.
+    pub fn add_warnings(&mut self, warnings: &Warnings) {
+        for (category, count) in warnings.category_counts() {
+            *self
+                .warnings
+                .entry(category.as_str().to_string())
+                .or_default() += count;
+        }
+    }
+
+    /// Summarise the unified IDs and filtered transcripts counted by
+    /// `transcript_unifier`.
+    pub fn add_unifier(&mut self, transcript_unifier: &TranscriptUnifier) {
+        let unify_counts = transcript_unifier.unify_counts();
+
+        self.n_unified_ids = unify_counts.n_unified_ids;
+        self.n_shared = unify_counts.n_shared;
+        self.n_private = self.n_unified_ids - self.n_shared;
+        self.cds_fraction = match self.n_unified_ids {
+            0 => 0.0,
+            n => unify_counts.n_cds as f64 / n as f64,
+        };
+
+        for (filter_reason, count) in &unify_counts.n_filtered {
+            *self.skipped.entry(filter_reason.to_string()).or_default() += count;
+        }
+    }
+
+    /// Add the summary of a run over other contigs, see
+    /// [`shard`](crate::shard).
+    ///
+    /// Counts are added together, so the merged summary matches a single run
+    /// across every contig. The wall time is the total of every shard, and the
+    /// peak memory is the largest of any shard.
+    pub fn add_shard(&mut self, shard_summary: &RunSummary) {
+        for shard_sample in &shard_summary.samples {
+            match self
+                .samples
+                .iter_mut()
+                .find(|x| x.sample == shard_sample.sample)
+            {
+                Some(sample) => sample.n_transcripts += shard_sample.n_transcripts,
+                None => self.add_sample(&shard_sample.sample, shard_sample.n_transcripts),
+            }
+        }
+
+        let n_cds = |x: &RunSummary| (x.cds_fraction * x.n_unified_ids as f64).round();
+        let n_cds = n_cds(self) + n_cds(shard_summary);
+        self.n_unified_ids += shard_summary.n_unified_ids;
+        self.n_shared += shard_summary.n_shared;
+        self.n_private += shard_summary.n_private;
+        self.cds_fraction = match self.n_unified_ids {
+            0 => 0.0,
+            n => n_cds / n as f64,
+        };
+
+        for (counts, shard_counts) in [
+            (&mut self.warnings, &shard_summary.warnings),
+            (&mut self.skipped, &shard_summary.skipped),
+        ] {
+            for (category, count) in shard_counts {
+                *counts.entry(category.clone()).or_default() += count;
+            }
+        }
+
+        self.wall_time_secs += shard_summary.wall_time_secs;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(shard_summary.peak_memory_bytes);
+    }
+
+    /// Stop the wall time and record the peak memory.
+    pub fn finish(&mut self) {
+        self.wall_time_secs = self.start.elapsed().as_secs_f64();
+        self.peak_memory_bytes = peak_memory_bytes();
+    }
+}
+
+/// Obtain the peak resident memory of this process in bytes.
+///
+/// Returns `None` if unavailable, e.g. on platforms without "/proc".
+fn peak_memory_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|x| x.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kilobytes * 1024)
+}
+
+/// Read a run summary written by [`write_summary`].
+///
+/// # Errors
+///
+/// Returns [`FileReadError`](SummaryError::FileReadError) if the summary
+/// cannot be read or is not a valid run summary.
+pub fn read_summary(summary_path: &Path) -> Result<RunSummary, SummaryError> {
+    info!("{}", summary_path.display());
+
+    let summary = File::open(summary_path)
+        .map_err(|_| SummaryError::FileReadError(summary_path.to_path_buf()))?;
+
+    serde_json::from_reader(BufReader::new(summary))
+        .map_err(|_| SummaryError::FileReadError(summary_path.to_path_buf()))
+}
+
+/// Write the run summary as JSON.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](SummaryError::FileCreateError) if the summary
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](SummaryError::FileWriteError) if the summary
+/// cannot be written.
+pub fn write_summary(summary_path: &Path, run_summary: &RunSummary) -> Result<(), SummaryError> {
+    info!("{}", summary_path.display());
+
+    let summary = File::create(summary_path)
+        .map_err(|_| SummaryError::FileCreateError(summary_path.to_path_buf()))?;
+    let mut writer = BufWriter::new(summary);
+    let write_error = |_| SummaryError::FileWriteError(summary_path.to_path_buf());
+
+    serde_json::to_writer_pretty(&mut writer, run_summary)
+        .map_err(|_| SummaryError::FileWriteError(summary_path.to_path_buf()))?;
+    writeln!(writer).map_err(write_error)?;
+    writer.flush().map_err(write_error)
+}
+
+/// Print the run summary as a human-readable table.
+///
+/// # Errors
+///
+/// Returns [`OutputWriteError`](SummaryError::OutputWriteError) if the table
+/// cannot be written.
+pub fn print_summary(
+    writer: &mut impl Write,
+    run_summary: &RunSummary,
+) -> Result<(), SummaryError> {
+    let mut rows = vec![("Sample".to_string(), "Transcripts".to_string())];
+    rows.extend(
+        run_summary
+            .samples
+            .iter()
+            .map(|x| (x.sample.clone(), x.n_transcripts.to_string())),
+    );
+    rows.push((String::new(), String::new()));
+    rows.push((
+        "Unified IDs".to_string(),
+        run_summary.n_unified_ids.to_string(),
+    ));
+    rows.push(("  Shared".to_string(), run_summary.n_shared.to_string()));
+    rows.push(("  Private".to_string(), run_summary.n_private.to_string()));
+    rows.push((
+        "CDS fraction".to_string(),
+        format!("{:.3}", run_summary.cds_fraction),
+    ));
+    for (title, counts) in [
+        ("Warnings", &run_summary.warnings),
+        ("Skipped", &run_summary.skipped),
+    ] {
+        rows.push((
+            title.to_string(),
+            counts.values().sum::<usize>().to_string(),
+        ));
+        rows.extend(
+            counts
+                .iter()
+                .map(|(category, n)| (format!("  {}", category), n.to_string())),
+        );
+    }
+    rows.push((
+        "Wall time (s)".to_string(),
+        format!("{:.2}", run_summary.wall_time_secs),
+    ));
+    rows.push((
+        "Peak memory (MB)".to_string(),
+        run_summary
+            .peak_memory_bytes
+            .map_or("NA".to_string(), |x| format!("{:.1}", x as f64 / 1e6)),
+    ));
+
+    let width = rows.iter().map(|(x, _)| x.len()).max().unwrap_or_default();
+    for (name, value) in rows {
+        let row = match name.is_empty() {
+            true => String::new(),
+            false => format!("{:width$}  {}", name, value, width = width),
+        };
+        writeln!(writer, "{}", row).map_err(|_| SummaryError::OutputWriteError)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+    use tuni::{ReadOptions, RecurrenceFilter, WarningCategory};
+
+    // Test that a written summary is read back, and that shards are added.
+    #[test]
+    fn test_read_summary_add_shard() {
+        let mut shard_summary = RunSummary::new();
+        shard_summary.add_sample("sample_1.gtf", 3);
+        shard_summary.n_unified_ids = 4;
+        shard_summary.n_shared = 1;
+        shard_summary.n_private = 3;
+        shard_summary.cds_fraction = 0.25;
+        shard_summary.skipped.insert("region".to_string(), 2);
+        shard_summary.peak_memory_bytes = Some(10);
+
+        let temp_dir = tempdir().unwrap();
+        let summary_path = temp_dir.path().join(SUMMARY_FILE_NAME);
+        write_summary(&summary_path, &shard_summary).unwrap();
+
+        let mut run_summary = RunSummary::new();
+        for _ in 0..2 {
+            run_summary.add_shard(&read_summary(&summary_path).unwrap());
+        }
+
+        assert_eq!(
+            run_summary.samples,
+            [SampleSummary {
+                sample: "sample_1.gtf".to_string(),
+                n_transcripts: 6
+            }]
+        );
+        assert_eq!(run_summary.n_unified_ids, 8);
+        assert_eq!(run_summary.n_shared, 2);
+        assert_eq!(run_summary.n_private, 6);
+        assert_eq!(run_summary.cds_fraction, 0.25);
+        assert_eq!(run_summary.skipped["region"], 4);
+        assert_eq!(run_summary.peak_memory_bytes, Some(10));
+    }
+
+    #[test]
+    fn test_run_summary() {
+        let mut run_summary = RunSummary::new();
+        let mut transcript_unifier =
+            TranscriptUnifier::new().with_recurrence_filter(RecurrenceFilter {
+                min_samples: 2,
+                ..Default::default()
+            });
+
+        for gtf_gff_path in [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+        ] {
+            let mut gtf_gff_transcripts =
+                tuni::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = tuni::extract_file_name(&gtf_gff_path).unwrap();
+            run_summary.add_sample(&gtf_file_name, gtf_gff_transcripts.signatures.len());
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+        transcript_unifier.unify_transcripts();
+
+        run_summary.add_unifier(&transcript_unifier);
+        let mut warnings = Warnings::default();
+        warnings.add(
+            WarningCategory::UnrecognisedTranscript,
+            "sample_1.gtf",
+            "D".to_string(),
+        );
+        run_summary.add_warnings(&warnings);
+        run_summary.finish();
+
+        // Only transcript A, without CDS regions, is found in both samples.
+        assert_eq!(
+            run_summary.samples,
+            [
+                SampleSummary {
+                    sample: "sample_1.gtf".to_string(),
+                    n_transcripts: 2
+                },
+                SampleSummary {
+                    sample: "sample_2.gtf".to_string(),
+                    n_transcripts: 2
+                }
+            ]
+        );
+        assert_eq!(run_summary.n_unified_ids, 1);
+        assert_eq!(run_summary.n_shared, 1);
+        assert_eq!(run_summary.n_private, 0);
+        assert_eq!(run_summary.cds_fraction, 0.0);
+        assert_eq!(run_summary.skipped["recurrence"], 2);
+        assert_eq!(run_summary.warnings["unrecognised_transcript"], 1);
+
+        let temp_dir = tempdir().unwrap();
+        let summary_path = temp_dir.path().join(SUMMARY_FILE_NAME);
+        write_summary(&summary_path, &run_summary).unwrap();
+        let summary: serde_json::Value =
+            serde_json::from_str(&read_to_string(summary_path).unwrap()).unwrap();
+
+        assert_eq!(summary["n_unified_ids"], 1);
+        assert_eq!(summary["samples"][1]["sample"], "sample_2.gtf");
+
+        let mut table = Vec::new();
+        print_summary(&mut table, &run_summary).unwrap();
+        let table = String::from_utf8(table).unwrap();
+
+        let has_row = |row: [&str; 2]| table.lines().any(|x| x.split_whitespace().eq(row));
+
+        assert!(has_row(["sample_1.gtf", "2"]));
+        assert!(has_row(["recurrence", "2"]));
+    }
+}
@@ -0,0 +1,293 @@
+//! Build transcript to gene tables keyed by unified ID, in the layout expected
+//! by tximport/tximeta.
+
+use crate::error::Tx2GeneError;
+use crate::gtf_gff::{self, TranscriptId};
+use crate::unify::{TranscriptUnifier, UnifiedId, UNIFIED_ID_PREFIX};
+use log::info;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+/// File name of the unified ID to gene table.
+pub const TX2GENE_FILE_NAME: &str = "tuni_tx2gene.tsv";
+
+/// File name of the table that also includes the original sample transcript IDs.
+pub const TX2GENE_SAMPLES_FILE_NAME: &str = "tuni_tx2gene_samples.tsv";
+
+/// Forms the gene of a unified ID without a gene, along with the integer of
+/// the unified ID e.g. "tuni_gene_1".
+const UNIFIED_GENE_ID_PREFIX: &str = "tuni_gene_";
+
+/// Links each unified ID to a gene.
+///
+/// Transcripts sharing a unified ID can be assigned different genes across
+/// samples (e.g. assembler generated gene IDs). Therefore, the gene found in
+/// the most samples is used, with ties broken by choosing the first gene
+/// alphabetically. Unified IDs without a gene in any sample are given a
+/// generated gene, see [`unified_gene_id`].
+pub struct Tx2Gene {
+    /// Link each unified ID to the number of samples each gene was found in.
+    gene_counts: HashMap<UnifiedId, HashMap<Arc<str>, usize>>,
+
+    /// Sample, transcript name, unified ID and gene of each sample transcript.
+    sample_rows: Vec<[Arc<str>; 4]>,
+}
+
+impl Default for Tx2Gene {
+    fn default() -> Tx2Gene {
+        Tx2Gene::new()
+    }
+}
+
+impl Tx2Gene {
+    /// Initialise `Tx2Gene`.
+    pub fn new() -> Tx2Gene {
+        Tx2Gene {
+            gene_counts: HashMap::new(),
+            sample_rows: Vec::new(),
+        }
+    }
+
+    /// Add the genes of a sample, keyed by the sample's own transcript IDs.
+    ///
+    /// Transcripts without a unified ID (e.g. filtered transcripts) are
+    /// skipped. Each gene is counted once per sample, even if found for
+    /// several transcripts sharing a unified ID.
+    pub fn add_sample(
+        &mut self,
+        sample: Arc<str>,
+        transcript_genes: HashMap<TranscriptId, Arc<str>>,
+        transcript_unifier: &TranscriptUnifier,
+    ) {
+        let mut sample_rows = Vec::new();
+        let mut sample_genes = HashSet::new();
+
+        for (transcript_id, gene) in transcript_genes {
+            let Some(unified_id) = transcript_unifier.get_unified_id(&sample, &transcript_id)
+            else {
+                continue;
+            };
+
+            sample_genes.insert((Arc::clone(unified_id), Arc::clone(&gene)));
+            sample_rows.push([
+                Arc::clone(&sample),
+                Arc::from(gtf_gff::extract_transcript_name(&transcript_id)),
+                Arc::clone(unified_id),
+                gene,
+            ]);
+        }
+
+        for (unified_id, gene) in sample_genes {
+            *self
+                .gene_counts
+                .entry(unified_id)
+                .or_default()
+                .entry(gene)
+                .or_default() += 1;
+        }
+
+        // Transcript IDs are stored in a HashMap, sort for a consistent output.
+        sample_rows.sort();
+        self.sample_rows.extend(sample_rows);
+    }
+
+    /// Obtain the gene of a unified ID.
+    ///
+    /// Returns the gene if present, otherwise `None`.
+    fn get_gene(&self, unified_id: &UnifiedId) -> Option<&Arc<str>> {
+        self.gene_counts
+            .get(unified_id)?
+            .iter()
+            .max_by(|(gene_a, count_a), (gene_b, count_b)| {
+                count_a.cmp(count_b).then(gene_b.cmp(gene_a))
+            })
+            .map(|(gene, _)| gene)
+    }
+}
+
+/// Generate the gene of a unified ID without a gene.
+///
+/// "tuni_1" -> "tuni_gene_1"
+pub fn unified_gene_id(unified_id: &str) -> String {
+    let unified_id = unified_id
+        .strip_prefix(UNIFIED_ID_PREFIX)
+        .unwrap_or(unified_id);
+    format!("{}{}", UNIFIED_GENE_ID_PREFIX, unified_id)
+}
+
+/// Write the tx2gene tables.
+///
+/// The unified ID to gene table follows the order of the catalog, including
+/// every unified ID. The table of sample transcripts is only written if
+/// `sample_ids` is true.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](Tx2GeneError::FileCreateError) if a table
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](Tx2GeneError::FileWriteError) if any line in a
+/// table cannot be written.
+pub fn write_tx2gene(
+    output_dir: &Path,
+    tx2gene: &Tx2Gene,
+    transcript_unifier: &TranscriptUnifier,
+    sample_ids: bool,
+) -> Result<(), Tx2GeneError> {
+    let tx2gene_path = output_dir.join(TX2GENE_FILE_NAME);
+    let mut writer = open_tx2gene_writer(&tx2gene_path)?;
+    let write_error = |_| Tx2GeneError::FileWriteError(tx2gene_path.clone());
+
+    writeln!(writer, "tuni_id\tgene_id").map_err(write_error)?;
+
+    for unified_id in transcript_unifier.unified_signatures().values() {
+        match tx2gene.get_gene(unified_id) {
+            Some(gene) => writeln!(writer, "{}\t{}", unified_id, gene),
+            None => writeln!(writer, "{}\t{}", unified_id, unified_gene_id(unified_id)),
+        }
+        .map_err(write_error)?;
+    }
+
+    writer.flush().map_err(write_error)?;
+
+    if !sample_ids {
+        return Ok(());
+    }
+
+    let samples_path = output_dir.join(TX2GENE_SAMPLES_FILE_NAME);
+    let mut writer = open_tx2gene_writer(&samples_path)?;
+    let write_error = |_| Tx2GeneError::FileWriteError(samples_path.clone());
+
+    writeln!(writer, "sample\ttranscript_id\ttuni_id\tgene_id").map_err(write_error)?;
+
+    for sample_row in &tx2gene.sample_rows {
+        writeln!(writer, "{}", sample_row.join("\t")).map_err(write_error)?;
+    }
+
+    writer.flush().map_err(write_error)
+}
+
+/// Open writer that writes a tx2gene table line by line.
+fn open_tx2gene_writer(output_path: &Path) -> Result<BufWriter<File>, Tx2GeneError> {
+    info!("{}", output_path.display());
+
+    let tx2gene = File::create(output_path)
+        .map_err(|_| Tx2GeneError::FileCreateError(output_path.to_path_buf()))?;
+    Ok(BufWriter::new(tx2gene))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gtf_gff::ReadOptions;
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_tx2gene() {
+        let mut transcript_unifier = TranscriptUnifier::new();
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+        ];
+
+        for gtf_gff_path in &gtf_gff_paths {
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(gtf_gff_path).unwrap();
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+        transcript_unifier.unify_transcripts();
+
+        let mut tx2gene = Tx2Gene::new();
+        for gtf_gff_path in &gtf_gff_paths {
+            let transcript_genes =
+                gtf_gff::read_attribute(gtf_gff_path, "gene_id", None, &ReadOptions::default())
+                    .unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(gtf_gff_path).unwrap();
+            tx2gene.add_sample(gtf_file_name, transcript_genes, &transcript_unifier);
+        }
+
+        let temp_dir = tempdir().unwrap();
+        write_tx2gene(temp_dir.path(), &tx2gene, &transcript_unifier, true).unwrap();
+
+        // tuni_0 is found in both samples, with gene "A" and "A_2".
+        assert_eq!(
+            read_to_string(temp_dir.path().join(TX2GENE_FILE_NAME)).unwrap(),
+            "tuni_id\tgene_id\ntuni_0\tA\ntuni_1\tB\ntuni_2\tC\n"
+        );
+        assert_eq!(
+            read_to_string(temp_dir.path().join(TX2GENE_SAMPLES_FILE_NAME))
+                .unwrap()
+                .lines()
+                .collect::<Vec<&str>>(),
+            [
+                "sample\ttranscript_id\ttuni_id\tgene_id",
+                "sample_1.gtf\tA\ttuni_0\tA",
+                "sample_1.gtf\tB\ttuni_1\tB",
+                "sample_2.gtf\tA_2\ttuni_0\tA_2",
+                "sample_2.gtf\tC\ttuni_2\tC",
+            ]
+        );
+    }
+
+    // Test that each gene is counted once per sample, and that unified IDs
+    // without a gene are given a generated gene.
+    #[test]
+    fn test_write_tx2gene_sample_counts() {
+        let mut transcript_unifier = TranscriptUnifier::new();
+        let gtf_gff_paths = [
+            PathBuf::from("tests/data/unit/sample_redundant.gtf"),
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+        ];
+        for gtf_gff_path in &gtf_gff_paths {
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(gtf_gff_path).unwrap();
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+        transcript_unifier.unify_transcripts();
+
+        // Gene "X" is found for two transcripts of one sample, "Y" in two samples.
+        let mut tx2gene = Tx2Gene::new();
+        for (sample, transcript_genes) in [
+            ("sample_redundant.gtf", &[("A", "X"), ("A_dup", "X")][..]),
+            ("sample_1.gtf", &[("A", "Y")]),
+            ("sample_2.gtf", &[("A_2", "Y")]),
+        ] {
+            let transcript_genes = transcript_genes
+                .iter()
+                .map(|(transcript, gene)| {
+                    (
+                        Arc::from(format!("transcript_id \"{}\"", transcript)),
+                        Arc::from(*gene),
+                    )
+                })
+                .collect();
+            tx2gene.add_sample(Arc::from(sample), transcript_genes, &transcript_unifier);
+        }
+
+        let temp_dir = tempdir().unwrap();
+        write_tx2gene(temp_dir.path(), &tx2gene, &transcript_unifier, false).unwrap();
+
+        let output = read_to_string(temp_dir.path().join(TX2GENE_FILE_NAME)).unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines.len(),
+            transcript_unifier.unified_signatures().len() + 1
+        );
+        assert_eq!(lines[1], "tuni_0\tY");
+        for line in &lines[2..] {
+            let (unified_id, gene) = line.split_once('\t').unwrap();
+            assert_eq!(gene, unified_gene_id(unified_id));
+        }
+        assert!(!temp_dir.path().join(TX2GENE_SAMPLES_FILE_NAME).exists());
+    }
+}
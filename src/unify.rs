@@ -1,22 +1,237 @@
 //! Group and unify transcripts across samples.
 
-use crate::gtf_gff::{TranscriptId, TranscriptSignature};
+use crate::attribute_filter::AttributeFilter;
+use crate::chr_alias::ChrAliases;
+use crate::filter::{FilterReason, RecurrenceFilter, StructureFilter};
+use crate::gtf_gff::{
+    GtfGffTranscripts, LocusConflict, ReadOptions, TranscriptId, TranscriptSignature,
+};
+use crate::region::RegionFilter;
+use log::warn;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
-    rc::Rc,
+    mem,
+    path::PathBuf,
+    sync::Arc,
 };
 
 /// Sample grouped with transcript ID.
 ///
 /// Used to uniquely identify transcripts across samples, as separate samples
 /// could use the same ID for different transcripts.
-pub type SampleTranscriptId = [Rc<str>; 2];
+pub type SampleTranscriptId = [Arc<str>; 2];
+
+/// Sample and transcript ID interned by a `TranscriptUnifier`.
+///
+/// Stored in place of a `SampleTranscriptId`, as two integers take a
+/// fraction of the memory of two shared strings.
+type SampleTranscriptKey = [u32; 2];
 
 /// `UnifiedId` will be same for the same transcript across different samples.
-pub type UnifiedId = Rc<str>;
+pub type UnifiedId = Arc<str>;
 
 /// Forms `UnifiedId` along with an integer e.g. "tuni_1".
-const UNIFIED_ID_PREFIX: &str = "tuni_";
+pub(crate) const UNIFIED_ID_PREFIX: &str = "tuni_";
+
+/// How a sample transcript was handled by unification.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Assignment {
+    /// Transcript was given a unified ID.
+    Unified(UnifiedId),
+
+    /// Transcript was filtered.
+    Filtered(FilterReason),
+}
+
+/// Counts of the unified IDs and filtered transcripts of a unification.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UnifyCounts {
+    /// Number of unified IDs assigned to at least one sample transcript.
+    pub n_unified_ids: usize,
+
+    /// Number of unified IDs assigned to transcripts of more than one sample.
+    pub n_shared: usize,
+
+    /// Number of unified IDs with any CDS regions.
+    pub n_cds: usize,
+
+    /// Number of filtered sample transcripts, by filter reason.
+    pub n_filtered: BTreeMap<&'static str, usize>,
+}
+
+/// Transcripts within one sample that share a `TranscriptSignature`.
+///
+/// These usually indicate duplicates emitted by the assembler or an upstream
+/// merge step.
+#[derive(Debug, PartialEq)]
+pub struct RedundantGroup {
+    /// Sample the transcripts are found in.
+    pub sample: Arc<str>,
+
+    /// Unified ID of the transcripts, `None` if they were filtered.
+    pub unified_id: Option<UnifiedId>,
+
+    /// Sorted transcript IDs, the first of which is the representative.
+    pub transcript_ids: Vec<TranscriptId>,
+}
+
+/// Give each distinct name an integer ID, e.g. the sample or transcript ID of
+/// every transcript unified.
+#[derive(Debug, Default)]
+struct IdInterner {
+    /// Link each name to its ID.
+    ids: HashMap<Arc<str>, u32>,
+
+    /// Name of each ID.
+    names: Vec<Arc<str>>,
+}
+
+impl IdInterner {
+    /// Obtain the ID of `name`, creating it if `name` has not been seen before.
+    fn intern(&mut self, name: &Arc<str>) -> u32 {
+        match self.ids.get(name) {
+            Some(id) => *id,
+            None => {
+                let id = u32::try_from(self.names.len()).expect("number of IDs should fit in u32");
+                self.ids.insert(Arc::clone(name), id);
+                self.names.push(Arc::clone(name));
+                id
+            }
+        }
+    }
+
+    /// Obtain the ID of `name`, `None` if it has not been interned.
+    fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    /// Obtain the name of `id`.
+    fn name(&self, id: u32) -> &Arc<str> {
+        &self.names[id as usize]
+    }
+}
+
+/// Options controlling how GTF/GFFs are read and unified.
+///
+/// Created with [`UnifyOptions::builder`], then applied to a
+/// `TranscriptUnifier` with [`TranscriptUnifier::with_options`].
+#[derive(Debug, Default)]
+pub struct UnifyOptions {
+    /// Options used when reading and writing GTF/GFFs.
+    pub read_options: ReadOptions,
+
+    /// Transcripts must be found in enough samples to be given a unified ID.
+    pub recurrence_filter: RecurrenceFilter,
+
+    /// Link each sample to a group (e.g. tissue), used by the recurrence filter.
+    pub sample_groups: HashMap<Arc<str>, Arc<str>>,
+
+    /// Only unify the representative of each `RedundantGroup`.
+    pub collapse_redundant: bool,
+}
+
+impl UnifyOptions {
+    /// Create a `UnifyOptionsBuilder`, starting from the default options.
+    pub fn builder() -> UnifyOptionsBuilder {
+        UnifyOptionsBuilder::default()
+    }
+}
+
+/// Builder for `UnifyOptions`.
+#[derive(Debug, Default)]
+pub struct UnifyOptionsBuilder {
+    options: UnifyOptions,
+}
+
+impl UnifyOptionsBuilder {
+    /// Normalise chromosome names with `chr_aliases`.
+    pub fn chr_aliases(mut self, chr_aliases: ChrAliases) -> UnifyOptionsBuilder {
+        self.options.read_options.chr_aliases = chr_aliases;
+        self
+    }
+
+    /// Handle transcripts on more than one chromosome or strand with
+    /// `locus_conflict`.
+    pub fn locus_conflict(mut self, locus_conflict: LocusConflict) -> UnifyOptionsBuilder {
+        self.options.read_options.locus_conflict = locus_conflict;
+        self
+    }
+
+    /// Only unify transcripts on `contigs`, see [`ReadOptions::contigs`].
+    pub fn contigs(mut self, contigs: Option<HashSet<Arc<str>>>) -> UnifyOptionsBuilder {
+        self.options.read_options.contigs = contigs;
+        self
+    }
+
+    /// Cache the lines of each GTF/GFF in `line_cache` while reading, see
+    /// [`ReadOptions::line_cache`].
+    pub fn line_cache(mut self, line_cache: Option<PathBuf>) -> UnifyOptionsBuilder {
+        self.options.read_options.line_cache = line_cache;
+        self
+    }
+
+    /// Filter transcripts outside of the contigs and regions of
+    /// `region_filter`.
+    pub fn region_filter(mut self, region_filter: RegionFilter) -> UnifyOptionsBuilder {
+        self.options.read_options.region_filter = region_filter;
+        self
+    }
+
+    /// Filter transcripts whose structure fails `structure_filter`.
+    pub fn structure_filter(mut self, structure_filter: StructureFilter) -> UnifyOptionsBuilder {
+        self.options.read_options.structure_filter = structure_filter;
+        self
+    }
+
+    /// Filter transcripts whose attributes fail `attribute_filter`.
+    pub fn attribute_filter(
+        mut self,
+        attribute_filter: Option<AttributeFilter>,
+    ) -> UnifyOptionsBuilder {
+        self.options.read_options.attribute_filter = attribute_filter;
+        self
+    }
+
+    /// Minimum number of samples a transcript must be found in.
+    pub fn min_samples(mut self, min_samples: usize) -> UnifyOptionsBuilder {
+        self.options.recurrence_filter.min_samples = min_samples;
+        self
+    }
+
+    /// Minimum fraction of all samples a transcript must be found in.
+    pub fn min_fraction(mut self, min_fraction: f64) -> UnifyOptionsBuilder {
+        self.options.recurrence_filter.min_fraction = min_fraction;
+        self
+    }
+
+    /// Minimum fraction of the samples within any one group a transcript can
+    /// instead be found in, see [`UnifyOptionsBuilder::sample_groups`].
+    pub fn min_group_fraction(mut self, min_group_fraction: Option<f64>) -> UnifyOptionsBuilder {
+        self.options.recurrence_filter.min_group_fraction = min_group_fraction;
+        self
+    }
+
+    /// Assign samples (GTF/GFF file names) to groups.
+    pub fn sample_groups(
+        mut self,
+        sample_groups: HashMap<Arc<str>, Arc<str>>,
+    ) -> UnifyOptionsBuilder {
+        self.options.sample_groups = sample_groups;
+        self
+    }
+
+    /// Only unify one representative of each set of transcripts within a
+    /// sample that share a `TranscriptSignature`.
+    pub fn collapse_redundant(mut self, collapse_redundant: bool) -> UnifyOptionsBuilder {
+        self.options.collapse_redundant = collapse_redundant;
+        self
+    }
+
+    /// Create the `UnifyOptions`.
+    pub fn build(self) -> UnifyOptions {
+        self.options
+    }
+}
 
 /// Unify transcript IDs across different samples.
 ///
@@ -31,10 +246,58 @@ pub struct TranscriptUnifier {
     /// to retain order of keys, so each transcript ID is given the same
     /// unified ID every time. This is helpful for testing but could be
     /// swapped for a HashMap if performance is key.
-    grouped_transcripts: BTreeMap<TranscriptSignature, HashSet<SampleTranscriptId>>,
+    grouped_transcripts: BTreeMap<TranscriptSignature, Vec<SampleTranscriptKey>>,
+
+    /// Integer ID of each sample (GTF/GFF file name).
+    samples: IdInterner,
+
+    /// Integer ID of each transcript ID, shared across samples.
+    transcript_ids: IdInterner,
+
+    /// Link each sample transcript to a unified ID.
+    unified_transcripts: HashMap<SampleTranscriptKey, UnifiedId>,
 
-    /// Link each sample transcript ID to a unified ID.
-    unified_transcripts: HashMap<SampleTranscriptId, UnifiedId>,
+    /// Link each `TranscriptSignature` to a unified ID.
+    ///
+    /// This persists across runs via the catalog, so that previously unified
+    /// transcripts retain their unified ID.
+    unified_signatures: BTreeMap<TranscriptSignature, UnifiedId>,
+
+    /// Integer used to form the next newly created unified ID.
+    next_unified_id: usize,
+
+    /// Number of samples grouped.
+    n_samples: usize,
+
+    /// Transcripts must be found in enough samples to be given a unified ID.
+    recurrence_filter: RecurrenceFilter,
+
+    /// Link each filtered sample transcript to the reason it was filtered.
+    filtered_transcripts: HashMap<SampleTranscriptKey, FilterReason>,
+
+    /// Sample transcripts found on more than one chromosome or strand.
+    locus_conflicts: HashSet<SampleTranscriptKey>,
+
+    /// Link each sample to a group (e.g. tissue), used by the recurrence filter.
+    sample_groups: HashMap<Arc<str>, Arc<str>>,
+
+    /// Number of samples grouped within each group.
+    group_sizes: HashMap<Arc<str>, usize>,
+
+    /// Transcripts within one sample that share a `TranscriptSignature`.
+    redundant_groups: Vec<RedundantGroup>,
+
+    /// Only unify the representative of each `RedundantGroup`.
+    collapse_redundant: bool,
+
+    /// Counts of the unified IDs and filtered transcripts.
+    unify_counts: UnifyCounts,
+}
+
+impl Default for TranscriptUnifier {
+    fn default() -> TranscriptUnifier {
+        TranscriptUnifier::new()
+    }
 }
 
 impl TranscriptUnifier {
@@ -42,53 +305,551 @@ impl TranscriptUnifier {
     pub fn new() -> TranscriptUnifier {
         TranscriptUnifier {
             grouped_transcripts: BTreeMap::new(),
+            samples: IdInterner::default(),
+            transcript_ids: IdInterner::default(),
             unified_transcripts: HashMap::new(),
+            unified_signatures: BTreeMap::new(),
+            next_unified_id: 0,
+            n_samples: 0,
+            recurrence_filter: RecurrenceFilter::default(),
+            filtered_transcripts: HashMap::new(),
+            locus_conflicts: HashSet::new(),
+            sample_groups: HashMap::new(),
+            group_sizes: HashMap::new(),
+            redundant_groups: Vec::new(),
+            collapse_redundant: false,
+            unify_counts: UnifyCounts::default(),
         }
     }
 
+    /// Initialise `TranscriptUnifier` from previously unified transcripts.
+    ///
+    /// Transcripts matching a known `TranscriptSignature` will be assigned the
+    /// existing unified ID. New unified IDs continue from the largest existing
+    /// unified ID.
+    pub fn from_catalog(
+        unified_signatures: BTreeMap<TranscriptSignature, UnifiedId>,
+    ) -> TranscriptUnifier {
+        let next_unified_id = unified_signatures
+            .values()
+            .filter_map(|x| x.strip_prefix(UNIFIED_ID_PREFIX)?.parse::<usize>().ok())
+            .max()
+            .map_or(0, |x| x + 1);
+
+        TranscriptUnifier {
+            unified_signatures,
+            next_unified_id,
+            ..TranscriptUnifier::new()
+        }
+    }
+
+    /// Only unify transcripts that pass `recurrence_filter`.
+    ///
+    /// Transcripts already present in the catalog are always unified.
+    pub fn with_recurrence_filter(
+        mut self,
+        recurrence_filter: RecurrenceFilter,
+    ) -> TranscriptUnifier {
+        self.recurrence_filter = recurrence_filter;
+        self
+    }
+
+    /// Assign samples to groups, allowing transcripts to pass the recurrence
+    /// filter based on the samples within any one group.
+    pub fn with_sample_groups(
+        mut self,
+        sample_groups: HashMap<Arc<str>, Arc<str>>,
+    ) -> TranscriptUnifier {
+        self.sample_groups = sample_groups;
+        self
+    }
+
+    /// Only unify one representative of each set of transcripts within a
+    /// sample that share a `TranscriptSignature`, filtering the rest.
+    pub fn with_collapse_redundant(mut self, collapse_redundant: bool) -> TranscriptUnifier {
+        self.collapse_redundant = collapse_redundant;
+        self
+    }
+
+    /// Apply the recurrence filter, sample groups and redundancy handling of
+    /// `options`.
+    pub fn with_options(self, options: &UnifyOptions) -> TranscriptUnifier {
+        self.with_recurrence_filter(options.recurrence_filter)
+            .with_sample_groups(options.sample_groups.clone())
+            .with_collapse_redundant(options.collapse_redundant)
+    }
+
     /// Group transcripts across different samples under the same
     /// `TranscriptSignature`.
     pub fn group_transcripts(
         &mut self,
-        gtf_gff_file_name: Rc<str>,
-        gtf_gff_transcripts: &mut HashMap<TranscriptId, TranscriptSignature>,
+        gtf_gff_file_name: Arc<str>,
+        gtf_gff_transcripts: &mut GtfGffTranscripts,
     ) {
-        for (transcript_id, transcript_signature) in gtf_gff_transcripts.drain() {
-            let sample_transcript_id = self
-                .grouped_transcripts
+        self.add_sample(
+            Arc::clone(&gtf_gff_file_name),
+            gtf_gff_transcripts.locus_conflicts.drain(),
+        );
+        for (sample_transcript_id, assignment) in
+            self.filter_transcripts(&gtf_gff_file_name, gtf_gff_transcripts.filtered.drain())
+        {
+            self.insert_assignment(sample_transcript_id, assignment);
+        }
+
+        let sample = self.samples.intern(&gtf_gff_file_name);
+        for (transcript_id, transcript_signature) in gtf_gff_transcripts.signatures.drain() {
+            let transcript = self.transcript_ids.intern(&transcript_id);
+            self.grouped_transcripts
                 .entry(transcript_signature)
-                .or_default();
-            sample_transcript_id.insert([Rc::clone(&gtf_gff_file_name), Rc::clone(&transcript_id)]);
+                .or_default()
+                .push([sample, transcript]);
         }
     }
 
+    /// Count a sample towards the recurrence filter and record its transcripts
+    /// found on more than one chromosome or strand, without grouping its
+    /// transcripts.
+    ///
+    /// Used when transcripts are grouped outside of `TranscriptUnifier`, see
+    /// [`TranscriptUnifier::unify_group`].
+    pub(crate) fn add_sample(
+        &mut self,
+        gtf_gff_file_name: Arc<str>,
+        locus_conflicts: impl IntoIterator<Item = TranscriptId>,
+    ) {
+        self.n_samples += 1;
+        match self.sample_groups.get(&gtf_gff_file_name) {
+            Some(group) => *self.group_sizes.entry(Arc::clone(group)).or_default() += 1,
+            None if !self.sample_groups.is_empty() => {
+                warn!("Sample {} not found in manifest", gtf_gff_file_name)
+            }
+            None => (),
+        }
+
+        let sample = self.samples.intern(&gtf_gff_file_name);
+        for transcript_id in locus_conflicts {
+            let transcript = self.transcript_ids.intern(&transcript_id);
+            self.locus_conflicts.insert([sample, transcript]);
+        }
+    }
+
+    /// Count the transcripts of a sample filtered while reading (e.g. outside
+    /// of the included regions), which are not grouped.
+    ///
+    /// Returns the assignment of each filtered transcript without storing it,
+    /// see [`TranscriptUnifier::insert_assignment`].
+    pub(crate) fn filter_transcripts(
+        &mut self,
+        gtf_gff_file_name: &Arc<str>,
+        filtered: impl IntoIterator<Item = (TranscriptId, FilterReason)>,
+    ) -> Vec<(SampleTranscriptId, Assignment)> {
+        let assignments = filtered
+            .into_iter()
+            .map(|(transcript_id, filter_reason)| {
+                (
+                    [Arc::clone(gtf_gff_file_name), transcript_id],
+                    Assignment::Filtered(filter_reason),
+                )
+            })
+            .collect::<Vec<(SampleTranscriptId, Assignment)>>();
+        self.count_filtered(&assignments);
+        assignments
+    }
+
     /// Create a unified ID for each unique `TranscriptSignature`.
+    ///
+    /// `TranscriptSignature`s that have previously been unified keep their
+    /// existing unified ID. New `TranscriptSignature`s that fail the recurrence
+    /// filter are not given a unified ID.
+    ///
+    /// Transcripts within one sample that share a `TranscriptSignature` are
+    /// recorded as a `RedundantGroup`.
     pub fn unify_transcripts(&mut self) {
-        for (i, sample_transcript_ids) in self.grouped_transcripts.values_mut().enumerate() {
-            for sample_transcript_id in sample_transcript_ids.drain() {
-                self.unified_transcripts.insert(
-                    sample_transcript_id,
-                    Rc::from(format!("{}{}", UNIFIED_ID_PREFIX, i)),
-                );
+        let grouped_transcripts = mem::take(&mut self.grouped_transcripts);
+        let transcript_ids = mem::take(&mut self.transcript_ids);
+
+        for (transcript_signature, sample_transcript_keys) in grouped_transcripts {
+            for (sample_transcript_key, assignment) in self.unify_keys(
+                transcript_signature,
+                sample_transcript_keys,
+                &transcript_ids,
+            ) {
+                self.insert_key_assignment(sample_transcript_key, assignment);
             }
         }
+
+        self.transcript_ids = transcript_ids;
+        self.sort_redundant_groups();
+    }
+
+    /// Unify the sample transcripts sharing `transcript_signature`, returning
+    /// how each sample transcript was handled.
+    ///
+    /// Unlike [`TranscriptUnifier::unify_transcripts`], the returned
+    /// assignments are not stored, so they can be kept outside of memory. To
+    /// create the same unified IDs, groups must be unified in
+    /// `TranscriptSignature` order after every sample has been added.
+    pub(crate) fn unify_group(
+        &mut self,
+        transcript_signature: TranscriptSignature,
+        sample_transcript_ids: Vec<SampleTranscriptId>,
+    ) -> Vec<(SampleTranscriptId, Assignment)> {
+        // Transcript IDs are only interned for this group, as its assignments
+        // are not stored.
+        let mut transcript_ids = IdInterner::default();
+        let sample_transcript_keys = sample_transcript_ids
+            .iter()
+            .map(|[sample, transcript_id]| {
+                [
+                    self.samples.intern(sample),
+                    transcript_ids.intern(transcript_id),
+                ]
+            })
+            .collect::<Vec<SampleTranscriptKey>>();
+
+        self.unify_keys(
+            transcript_signature,
+            sample_transcript_keys,
+            &transcript_ids,
+        )
+        .into_iter()
+        .map(|([sample, transcript], assignment)| {
+            (
+                [
+                    Arc::clone(self.samples.name(sample)),
+                    Arc::clone(transcript_ids.name(transcript)),
+                ],
+                assignment,
+            )
+        })
+        .collect()
+    }
+
+    /// Unify the interned sample transcripts sharing `transcript_signature`,
+    /// whose transcript IDs are interned by `transcript_ids`.
+    fn unify_keys(
+        &mut self,
+        transcript_signature: TranscriptSignature,
+        mut sample_transcript_keys: Vec<SampleTranscriptKey>,
+        transcript_ids: &IdInterner,
+    ) -> Vec<(SampleTranscriptKey, Assignment)> {
+        let mut assignments = Vec::new();
+
+        let redundant_keys = Self::find_redundant_keys(&sample_transcript_keys, transcript_ids);
+        if self.collapse_redundant {
+            for sample_transcript_key in redundant_keys.iter().flat_map(|x| &x[1..]) {
+                sample_transcript_keys.retain(|x| x != sample_transcript_key);
+                assignments.push((
+                    *sample_transcript_key,
+                    Assignment::Filtered(FilterReason::Redundant),
+                ));
+            }
+        }
+
+        let has_cds = transcript_signature.has_cds();
+        let unified_id = match self.unified_signatures.get(&transcript_signature) {
+            Some(unified_id) => Arc::clone(unified_id),
+            None if !self.passes_recurrence_filter(&sample_transcript_keys) => {
+                for sample_transcript_key in sample_transcript_keys {
+                    assignments.push((
+                        sample_transcript_key,
+                        Assignment::Filtered(FilterReason::Recurrence),
+                    ));
+                }
+                self.add_redundant_groups(redundant_keys, None, transcript_ids);
+                self.count_filtered(&assignments);
+                return assignments;
+            }
+            None => {
+                let unified_id: UnifiedId =
+                    Arc::from(format!("{}{}", UNIFIED_ID_PREFIX, self.next_unified_id));
+                self.next_unified_id += 1;
+                self.unified_signatures
+                    .insert(transcript_signature, Arc::clone(&unified_id));
+                unified_id
+            }
+        };
+
+        let n_samples = sample_transcript_keys
+            .iter()
+            .map(|[sample, _]| sample)
+            .collect::<HashSet<&u32>>()
+            .len();
+        if n_samples > 0 {
+            self.unify_counts.n_unified_ids += 1;
+            self.unify_counts.n_shared += usize::from(n_samples > 1);
+            self.unify_counts.n_cds += usize::from(has_cds);
+        }
+
+        for sample_transcript_key in sample_transcript_keys {
+            assignments.push((
+                sample_transcript_key,
+                Assignment::Unified(Arc::clone(&unified_id)),
+            ));
+        }
+
+        self.add_redundant_groups(redundant_keys, Some(unified_id), transcript_ids);
+        self.count_filtered(&assignments);
+
+        assignments
+    }
+
+    /// Count the filtered sample transcripts within `assignments`.
+    fn count_filtered<T>(&mut self, assignments: &[(T, Assignment)]) {
+        for (_, assignment) in assignments {
+            if let Assignment::Filtered(filter_reason) = assignment {
+                *self
+                    .unify_counts
+                    .n_filtered
+                    .entry(filter_reason.as_str())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    /// Store how a sample transcript was handled, so that it can be looked up
+    /// by [`TranscriptUnifier::get_unified_id`] and
+    /// [`TranscriptUnifier::get_filter_reason`].
+    pub(crate) fn insert_assignment(
+        &mut self,
+        sample_transcript_id: SampleTranscriptId,
+        assignment: Assignment,
+    ) {
+        let [sample, transcript_id] = sample_transcript_id;
+        let sample_transcript_key = [
+            self.samples.intern(&sample),
+            self.transcript_ids.intern(&transcript_id),
+        ];
+        self.insert_key_assignment(sample_transcript_key, assignment);
+    }
+
+    /// Store how an interned sample transcript was handled.
+    fn insert_key_assignment(
+        &mut self,
+        sample_transcript_key: SampleTranscriptKey,
+        assignment: Assignment,
+    ) {
+        match assignment {
+            Assignment::Unified(unified_id) => {
+                self.unified_transcripts
+                    .insert(sample_transcript_key, unified_id);
+            }
+            Assignment::Filtered(filter_reason) => {
+                self.filtered_transcripts
+                    .insert(sample_transcript_key, filter_reason);
+            }
+        }
+    }
+
+    /// Remove every stored assignment, keeping the unified
+    /// `TranscriptSignature`s.
+    ///
+    /// Transcript IDs only interned for the removed assignments are released,
+    /// so this must only be called once transcripts are unified.
+    pub fn clear_assignments(&mut self) {
+        self.unified_transcripts.clear();
+        self.filtered_transcripts.clear();
+
+        let transcript_ids = mem::take(&mut self.transcript_ids);
+        self.locus_conflicts = mem::take(&mut self.locus_conflicts)
+            .into_iter()
+            .map(|[sample, transcript]| {
+                [
+                    sample,
+                    self.transcript_ids.intern(transcript_ids.name(transcript)),
+                ]
+            })
+            .collect();
+    }
+
+    /// Order redundant transcripts sample by sample.
+    pub(crate) fn sort_redundant_groups(&mut self) {
+        self.redundant_groups
+            .sort_by(|a, b| (&a.sample, &a.transcript_ids).cmp(&(&b.sample, &b.transcript_ids)));
+    }
+
+    /// Find transcripts within the same sample that share a
+    /// `TranscriptSignature`, each sorted by transcript ID.
+    fn find_redundant_keys(
+        sample_transcript_keys: &[SampleTranscriptKey],
+        transcript_ids: &IdInterner,
+    ) -> Vec<Vec<SampleTranscriptKey>> {
+        let mut sample_transcripts: BTreeMap<u32, Vec<SampleTranscriptKey>> = BTreeMap::new();
+        for sample_transcript_key in sample_transcript_keys {
+            sample_transcripts
+                .entry(sample_transcript_key[0])
+                .or_default()
+                .push(*sample_transcript_key);
+        }
+
+        sample_transcripts
+            .into_values()
+            .filter(|x| x.len() > 1)
+            .map(|mut redundant_keys| {
+                redundant_keys.sort_by_key(|[_, transcript]| transcript_ids.name(*transcript));
+                redundant_keys
+            })
+            .collect()
+    }
+
+    /// Record each set of redundant sample transcripts as a `RedundantGroup`.
+    fn add_redundant_groups(
+        &mut self,
+        redundant_keys: Vec<Vec<SampleTranscriptKey>>,
+        unified_id: Option<UnifiedId>,
+        transcript_ids: &IdInterner,
+    ) {
+        for redundant_keys in redundant_keys {
+            self.redundant_groups.push(RedundantGroup {
+                sample: Arc::clone(self.samples.name(redundant_keys[0][0])),
+                unified_id: unified_id.clone(),
+                transcript_ids: redundant_keys
+                    .iter()
+                    .map(|[_, transcript]| Arc::clone(transcript_ids.name(*transcript)))
+                    .collect(),
+            });
+        }
+    }
+
+    /// Obtain the interned (sample, transcript ID), `None` if either has not
+    /// been interned.
+    fn find_key(&self, sample: &str, transcript_id: &str) -> Option<SampleTranscriptKey> {
+        Some([
+            self.samples.get(sample)?,
+            self.transcript_ids.get(transcript_id)?,
+        ])
+    }
+
+    /// Obtain the (sample, transcript ID) of an interned sample transcript.
+    fn sample_transcript_id(
+        &self,
+        sample_transcript_key: SampleTranscriptKey,
+    ) -> SampleTranscriptId {
+        let [sample, transcript] = sample_transcript_key;
+        [
+            Arc::clone(self.samples.name(sample)),
+            Arc::clone(self.transcript_ids.name(transcript)),
+        ]
     }
 
     /// Obtain unified ID based on (sample, transcript ID).
     ///
     /// Returns unified ID if present, otherwise `None`.
-    pub fn get_unified_id(&self, sample_transcript_id: &SampleTranscriptId) -> Option<&Rc<str>> {
-        self.unified_transcripts.get(sample_transcript_id)
+    pub fn get_unified_id(&self, sample: &str, transcript_id: &str) -> Option<&UnifiedId> {
+        self.unified_transcripts
+            .get(&self.find_key(sample, transcript_id)?)
+    }
+
+    /// Obtain the reason a transcript was filtered based on (sample, transcript ID).
+    ///
+    /// Returns the filter reason if the transcript was filtered, otherwise `None`.
+    pub fn get_filter_reason(&self, sample: &str, transcript_id: &str) -> Option<FilterReason> {
+        self.filtered_transcripts
+            .get(&self.find_key(sample, transcript_id)?)
+            .copied()
+    }
+
+    /// Returns true if the transcript was unified or filtered, otherwise false.
+    pub fn is_known(&self, sample: &str, transcript_id: &str) -> bool {
+        self.find_key(sample, transcript_id).is_some_and(|x| {
+            self.unified_transcripts.contains_key(&x) || self.filtered_transcripts.contains_key(&x)
+        })
+    }
+
+    /// Returns true if the transcript was found on more than one chromosome or
+    /// strand, otherwise false.
+    pub fn has_locus_conflict(&self, sample: &str, transcript_id: &str) -> bool {
+        self.find_key(sample, transcript_id)
+            .is_some_and(|x| self.locus_conflicts.contains(&x))
+    }
+
+    /// Returns true if the transcripts sharing a `TranscriptSignature` are found
+    /// in enough samples, otherwise false.
+    ///
+    /// Multiple transcripts from the same sample only count once. When a group
+    /// minimum is set, transcripts found in enough samples of any one group
+    /// also pass.
+    fn passes_recurrence_filter(&self, sample_transcript_keys: &[SampleTranscriptKey]) -> bool {
+        let samples = sample_transcript_keys
+            .iter()
+            .map(|[sample, _]| *sample)
+            .collect::<HashSet<u32>>();
+        let passes_global = self.recurrence_filter.passes(samples.len(), self.n_samples);
+
+        if self.recurrence_filter.min_group_fraction.is_none() {
+            return passes_global;
+        }
+        // Without global minimums, only the group minimum applies.
+        if passes_global && self.recurrence_filter.has_global_minimum() {
+            return true;
+        }
+
+        let mut n_group_samples: HashMap<&Arc<str>, usize> = HashMap::new();
+        for group in samples
+            .iter()
+            .filter_map(|x| self.sample_groups.get(self.samples.name(*x)))
+        {
+            *n_group_samples.entry(group).or_default() += 1;
+        }
+
+        n_group_samples.iter().any(|(group, n)| {
+            self.recurrence_filter
+                .passes_group(*n, self.group_sizes[*group])
+        })
+    }
+
+    /// Obtain the unified ID of every unified sample transcript ID.
+    pub fn unified_transcripts(&self) -> impl Iterator<Item = (SampleTranscriptId, &UnifiedId)> {
+        self.unified_transcripts
+            .iter()
+            .map(|(key, unified_id)| (self.sample_transcript_id(*key), unified_id))
+    }
+
+    /// Obtain the filter reason of every filtered sample transcript ID.
+    pub fn filtered_transcripts(
+        &self,
+    ) -> impl Iterator<Item = (SampleTranscriptId, FilterReason)> + '_ {
+        self.filtered_transcripts
+            .iter()
+            .map(|(key, filter_reason)| (self.sample_transcript_id(*key), *filter_reason))
+    }
+
+    /// Obtain every set of transcripts within one sample that share a
+    /// `TranscriptSignature`, ordered by sample.
+    pub fn redundant_groups(&self) -> &[RedundantGroup] {
+        &self.redundant_groups
+    }
+
+    /// Obtain every unified `TranscriptSignature` along with its unified ID.
+    pub fn unified_signatures(&self) -> &BTreeMap<TranscriptSignature, UnifiedId> {
+        &self.unified_signatures
+    }
+
+    /// Obtain the counts of unified IDs and filtered transcripts.
+    pub fn unify_counts(&self) -> &UnifyCounts {
+        &self.unify_counts
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::gtf_gff;
-    use std::collections::BTreeSet;
+    use crate::gtf_gff::{self, ReadOptions};
+    use rstest::rstest;
     use std::path::PathBuf;
 
+    /// Obtain the unified ID of every unified sample transcript ID.
+    fn unified_transcripts(
+        transcript_unifier: &TranscriptUnifier,
+    ) -> HashMap<SampleTranscriptId, UnifiedId> {
+        transcript_unifier
+            .unified_transcripts()
+            .map(|(sample_transcript_id, unified_id)| {
+                (sample_transcript_id, Arc::clone(unified_id))
+            })
+            .collect()
+    }
+
     #[test]
     fn test_transcript_unifier() {
         let mut transcript_unifier = TranscriptUnifier::new();
@@ -100,70 +861,258 @@ mod tests {
             PathBuf::from("tests/data/unit/sample_2.gtf"),
         ];
         for gtf_gff_path in gtf_gff_paths {
-            let mut gtf_gff_transcripts = gtf_gff::read_gtf_gff(&gtf_gff_path).unwrap();
-            let gtf_file_name = gtf_gff::extract_file_name(&gtf_gff_path);
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(&gtf_gff_path).unwrap();
             transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
         }
 
         let expected_transcripts = BTreeMap::from([
             (
-                TranscriptSignature::from(
-                    Rc::from("chr1"),
-                    Rc::from("-"),
-                    BTreeSet::from([Rc::from("1"), Rc::from("11"), Rc::from("12"), Rc::from("2")]),
-                    BTreeSet::new(),
-                ),
-                HashSet::from([
-                    [Rc::from("sample_1.gtf"), Rc::from("transcript_id \"A\"")],
-                    [Rc::from("sample_2.gtf"), Rc::from("transcript_id \"A_2\"")],
+                TranscriptSignature::from(Arc::from("chr1"), Arc::from("-"), [1, 11, 12, 2], []),
+                Vec::from([
+                    [Arc::from("sample_1.gtf"), Arc::from("transcript_id \"A\"")],
+                    [
+                        Arc::from("sample_2.gtf"),
+                        Arc::from("transcript_id \"A_2\""),
+                    ],
                 ]),
             ),
             (
-                TranscriptSignature::from(
-                    Rc::from("chr2"),
-                    Rc::from("+"),
-                    BTreeSet::from([Rc::from("20"), Rc::from("30")]),
-                    BTreeSet::from([Rc::from("25"), Rc::from("29")]),
-                ),
-                HashSet::from([[Rc::from("sample_1.gtf"), Rc::from("transcript_id \"B\"")]]),
+                TranscriptSignature::from(Arc::from("chr2"), Arc::from("+"), [20, 30], [25, 29]),
+                Vec::from([[Arc::from("sample_1.gtf"), Arc::from("transcript_id \"B\"")]]),
             ),
             (
-                TranscriptSignature::from(
-                    Rc::from("chr2"),
-                    Rc::from("+"),
-                    BTreeSet::from([Rc::from("20"), Rc::from("30")]),
-                    BTreeSet::from([Rc::from("26"), Rc::from("28")]),
-                ),
-                HashSet::from([[Rc::from("sample_2.gtf"), Rc::from("transcript_id \"C\"")]]),
+                TranscriptSignature::from(Arc::from("chr2"), Arc::from("+"), [20, 30], [26, 28]),
+                Vec::from([[Arc::from("sample_2.gtf"), Arc::from("transcript_id \"C\"")]]),
             ),
         ]);
 
-        assert_eq!(transcript_unifier.grouped_transcripts, expected_transcripts);
+        let grouped_transcripts = transcript_unifier
+            .grouped_transcripts
+            .iter()
+            .map(|(transcript_signature, sample_transcript_keys)| {
+                (
+                    transcript_signature.clone(),
+                    sample_transcript_keys
+                        .iter()
+                        .map(|x| transcript_unifier.sample_transcript_id(*x))
+                        .collect::<Vec<SampleTranscriptId>>(),
+                )
+            })
+            .collect::<BTreeMap<TranscriptSignature, Vec<SampleTranscriptId>>>();
+        assert_eq!(grouped_transcripts, expected_transcripts);
 
         transcript_unifier.unify_transcripts();
 
         let expected_unified_transcripts = HashMap::from([
             (
-                [Rc::from("sample_1.gtf"), Rc::from("transcript_id \"A\"")],
-                Rc::from("tuni_0"),
+                [Arc::from("sample_1.gtf"), Arc::from("transcript_id \"A\"")],
+                Arc::from("tuni_0"),
+            ),
+            (
+                [Arc::from("sample_1.gtf"), Arc::from("transcript_id \"B\"")],
+                Arc::from("tuni_1"),
+            ),
+            (
+                [
+                    Arc::from("sample_2.gtf"),
+                    Arc::from("transcript_id \"A_2\""),
+                ],
+                Arc::from("tuni_0"),
             ),
             (
-                [Rc::from("sample_1.gtf"), Rc::from("transcript_id \"B\"")],
-                Rc::from("tuni_1"),
+                [Arc::from("sample_2.gtf"), Arc::from("transcript_id \"C\"")],
+                Arc::from("tuni_2"),
             ),
+        ]);
+
+        assert_eq!(
+            unified_transcripts(&transcript_unifier),
+            expected_unified_transcripts
+        );
+    }
+
+    #[test]
+    fn test_transcript_unifier_recurrence_filter() {
+        let mut transcript_unifier =
+            TranscriptUnifier::new().with_recurrence_filter(RecurrenceFilter {
+                min_samples: 2,
+                ..Default::default()
+            });
+
+        for gtf_gff_path in [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+        ] {
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(&gtf_gff_path).unwrap();
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+
+        transcript_unifier.unify_transcripts();
+
+        // Only transcript A is found in both samples.
+        let expected_unified_transcripts = HashMap::from([
+            (
+                [Arc::from("sample_1.gtf"), Arc::from("transcript_id \"A\"")],
+                Arc::from("tuni_0"),
+            ),
+            (
+                [
+                    Arc::from("sample_2.gtf"),
+                    Arc::from("transcript_id \"A_2\""),
+                ],
+                Arc::from("tuni_0"),
+            ),
+        ]);
+
+        assert_eq!(
+            unified_transcripts(&transcript_unifier),
+            expected_unified_transcripts
+        );
+        assert_eq!(transcript_unifier.unified_signatures().len(), 1);
+        assert_eq!(
+            transcript_unifier.get_filter_reason("sample_2.gtf", "transcript_id \"C\""),
+            Some(FilterReason::Recurrence)
+        );
+    }
+
+    // Transcripts B and C are each found in only one sample. They pass only if
+    // sample 1 and sample 2 belong to different groups.
+    #[rstest]
+    #[case(["a", "b"], 3)]
+    #[case(["a", "a"], 1)]
+    fn test_transcript_unifier_group_recurrence_filter(
+        #[case] groups: [&str; 2],
+        #[case] expected_n_unified: usize,
+    ) {
+        let sample_groups = HashMap::from([
+            (Arc::from("sample_1.gtf"), Arc::from(groups[0])),
+            (Arc::from("sample_2.gtf"), Arc::from(groups[1])),
+        ]);
+        let options = UnifyOptions::builder()
+            .min_group_fraction(Some(1.0))
+            .sample_groups(sample_groups)
+            .build();
+        let mut transcript_unifier = TranscriptUnifier::new().with_options(&options);
+
+        for gtf_gff_path in [
+            PathBuf::from("tests/data/unit/sample_1.gtf"),
+            PathBuf::from("tests/data/unit/sample_2.gtf"),
+        ] {
+            let mut gtf_gff_transcripts =
+                gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+            let gtf_file_name = gtf_gff::extract_file_name(&gtf_gff_path).unwrap();
+            transcript_unifier.group_transcripts(gtf_file_name, &mut gtf_gff_transcripts);
+        }
+
+        transcript_unifier.unify_transcripts();
+
+        assert_eq!(
+            transcript_unifier.unified_signatures().len(),
+            expected_n_unified
+        );
+    }
+
+    #[rstest]
+    #[case(false, None)]
+    #[case(true, Some(FilterReason::Redundant))]
+    fn test_transcript_unifier_redundant(
+        #[case] collapse_redundant: bool,
+        #[case] expected_filter_reason: Option<FilterReason>,
+    ) {
+        let mut transcript_unifier =
+            TranscriptUnifier::new().with_collapse_redundant(collapse_redundant);
+
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_redundant.gtf");
+        let mut gtf_gff_transcripts =
+            gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+        transcript_unifier
+            .group_transcripts(Arc::from("sample_redundant.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        assert_eq!(transcript_unifier.redundant_groups().len(), 2);
+        assert_eq!(
+            transcript_unifier.redundant_groups()[0],
+            RedundantGroup {
+                sample: Arc::from("sample_redundant.gtf"),
+                unified_id: Some(Arc::from("tuni_0")),
+                transcript_ids: vec![
+                    Arc::from("transcript_id \"A\""),
+                    Arc::from("transcript_id \"A_dup\"")
+                ],
+            }
+        );
+
+        // The representative is always unified.
+        assert!(transcript_unifier
+            .get_unified_id("sample_redundant.gtf", "transcript_id \"A\"")
+            .is_some());
+        assert_eq!(
+            transcript_unifier.get_filter_reason("sample_redundant.gtf", "transcript_id \"A_dup\""),
+            expected_filter_reason
+        );
+    }
+
+    // Clearing assignments releases their transcript IDs, but keeps locus
+    // conflicts.
+    #[test]
+    fn test_transcript_unifier_clear_assignments() {
+        let mut transcript_unifier = TranscriptUnifier::new();
+        transcript_unifier.add_sample(
+            Arc::from("sample_1.gtf"),
+            [Arc::from("transcript_id \"B\"")],
+        );
+        transcript_unifier.insert_assignment(
+            [Arc::from("sample_1.gtf"), Arc::from("transcript_id \"A\"")],
+            Assignment::Unified(Arc::from("tuni_0")),
+        );
+        assert!(transcript_unifier.is_known("sample_1.gtf", "transcript_id \"A\""));
+
+        transcript_unifier.clear_assignments();
+
+        assert!(!transcript_unifier.is_known("sample_1.gtf", "transcript_id \"A\""));
+        assert_eq!(transcript_unifier.transcript_ids.names.len(), 1);
+        assert!(transcript_unifier.has_locus_conflict("sample_1.gtf", "transcript_id \"B\""));
+    }
+
+    #[test]
+    fn test_transcript_unifier_from_catalog() {
+        let known_signature =
+            TranscriptSignature::from(Arc::from("chr1"), Arc::from("-"), [1, 11, 12, 2], []);
+        let mut transcript_unifier = TranscriptUnifier::from_catalog(BTreeMap::from([(
+            known_signature,
+            Arc::from("tuni_7"),
+        )]));
+
+        let gtf_gff_path = PathBuf::from("tests/data/unit/sample_2.gtf");
+        let mut gtf_gff_transcripts =
+            gtf_gff::read_gtf_gff(&gtf_gff_path, &ReadOptions::default()).unwrap();
+        transcript_unifier.group_transcripts(Arc::from("sample_2.gtf"), &mut gtf_gff_transcripts);
+        transcript_unifier.unify_transcripts();
+
+        // Known transcripts keep their unified ID, new transcripts continue
+        // from the largest unified ID.
+        let expected_unified_transcripts = HashMap::from([
             (
-                [Rc::from("sample_2.gtf"), Rc::from("transcript_id \"A_2\"")],
-                Rc::from("tuni_0"),
+                [
+                    Arc::from("sample_2.gtf"),
+                    Arc::from("transcript_id \"A_2\""),
+                ],
+                Arc::from("tuni_7"),
             ),
             (
-                [Rc::from("sample_2.gtf"), Rc::from("transcript_id \"C\"")],
-                Rc::from("tuni_2"),
+                [Arc::from("sample_2.gtf"), Arc::from("transcript_id \"C\"")],
+                Arc::from("tuni_8"),
             ),
         ]);
 
         assert_eq!(
-            transcript_unifier.unified_transcripts,
+            unified_transcripts(&transcript_unifier),
             expected_unified_transcripts
         );
+        assert_eq!(transcript_unifier.unified_signatures().len(), 2);
     }
 }
@@ -0,0 +1,449 @@
+//! Check GTF/GFFs for problems before unifying.
+
+use crate::cli_error::ValidateError;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+/// Number of tab-separated columns in a GTF/GFF line.
+const N_COLUMNS: usize = 9;
+
+/// How seriously a problem affects unifying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The transcript may be unified incorrectly.
+    Warning,
+
+    /// The GTF/GFF cannot be unified.
+    Error,
+}
+
+impl Severity {
+    /// Exit code of `tuni validate` when this is the most severe problem found.
+    ///
+    /// 1 is reserved for errors that stop `tuni validate` itself.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Severity::Warning => 2,
+            Severity::Error => 3,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Check that found a problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// Line does not have 9 tab-separated columns.
+    ColumnCount,
+
+    /// Start or end is not a positive integer, or start is after end.
+    Coordinates,
+
+    /// Exon/CDS line does not contain a "transcript_id" attribute.
+    MissingTranscriptId,
+
+    /// Transcript has exon/CDS lines on more than one chromosome or strand.
+    LocusConflict,
+
+    /// Transcript has a CDS region not contained within any of its exons.
+    CdsOutsideExons,
+
+    /// Transcript has exons that overlap each other.
+    OverlappingExons,
+}
+
+impl Check {
+    /// Name of the check, as written in the validation results.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::ColumnCount => "column_count",
+            Check::Coordinates => "coordinates",
+            Check::MissingTranscriptId => "missing_transcript_id",
+            Check::LocusConflict => "locus_conflict",
+            Check::CdsOutsideExons => "cds_outside_exons",
+            Check::OverlappingExons => "overlapping_exons",
+        }
+    }
+
+    /// Severity of problems found by the check.
+    fn severity(&self) -> Severity {
+        match self {
+            Check::ColumnCount | Check::Coordinates | Check::MissingTranscriptId => Severity::Error,
+            Check::LocusConflict | Check::CdsOutsideExons | Check::OverlappingExons => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+/// Problem found in a GTF/GFF.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Issue {
+    /// How seriously the problem affects unifying.
+    pub severity: Severity,
+
+    /// Check that found the problem.
+    pub check: Check,
+
+    /// Line number (1-based) of line-level problems.
+    pub line: Option<usize>,
+
+    /// Transcript name of transcript-level problems.
+    pub transcript: Option<String>,
+
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl Issue {
+    fn from(check: Check, line: Option<usize>, transcript: Option<&str>, message: String) -> Issue {
+        Issue {
+            severity: check.severity(),
+            check,
+            line,
+            transcript: transcript.map(String::from),
+            message,
+        }
+    }
+}
+
+/// Result of validating a GTF/GFF.
+#[derive(Debug, Serialize)]
+pub struct Validation {
+    /// Path of the GTF/GFF.
+    pub path: String,
+
+    /// Number of transcripts with at least one exon/CDS line.
+    pub n_transcripts: usize,
+
+    /// Problems found, line-level problems first.
+    pub issues: Vec<Issue>,
+}
+
+impl Validation {
+    /// Obtain the most severe problem found, `None` if no problems were found.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.issues.iter().map(|x| x.severity).max()
+    }
+
+    /// Count the problems of a given severity.
+    fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|x| x.severity == severity)
+            .count()
+    }
+}
+
+/// Format in which validation results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ValidateFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// JSON, for use in pipelines.
+    Json,
+}
+
+/// Exons, CDS regions and locus of a transcript.
+#[derive(Default)]
+struct TranscriptRegions {
+    /// Chromosome and strand of the first exon/CDS line.
+    locus: Option<(String, String)>,
+
+    /// Whether exon/CDS lines are on more than one chromosome or strand.
+    has_locus_conflict: bool,
+
+    /// Start and end of each exon.
+    exons: Vec<(u64, u64)>,
+
+    /// Start and end of each CDS region.
+    cds: Vec<(u64, u64)>,
+}
+
+/// Check every line and transcript of a GTF/GFF, without unifying.
+///
+/// Unlike [`tuni::read_gtf_gff`], every problem is reported rather than
+/// stopping at the first.
+///
+/// # Errors
+///
+/// Returns [`FileReadError`](ValidateError::FileReadError) if the GTF/GFF
+/// cannot be opened or any line cannot be read.
+pub fn validate_gtf_gff(gtf_gff_path: &Path) -> Result<Validation, ValidateError> {
+    let read_error = |_| ValidateError::FileReadError(gtf_gff_path.to_path_buf());
+    let reader = BufReader::new(File::open(gtf_gff_path).map_err(read_error)?);
+
+    let mut issues = Vec::new();
+    let mut transcripts: BTreeMap<String, TranscriptRegions> = BTreeMap::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(read_error)?;
+        let line_number = Some(i + 1);
+
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+
+        let line_split = line.split('\t').collect::<Vec<&str>>();
+
+        if line_split.len() != N_COLUMNS {
+            issues.push(Issue::from(
+                Check::ColumnCount,
+                line_number,
+                None,
+                format!(
+                    "Expected {} tab-separated columns, found {}",
+                    N_COLUMNS,
+                    line_split.len()
+                ),
+            ));
+            continue;
+        }
+
+        let (start, end) = match (line_split[3].parse::<u64>(), line_split[4].parse::<u64>()) {
+            (Ok(start), Ok(end)) if 0 < start && start <= end => (start, end),
+            _ => {
+                issues.push(Issue::from(
+                    Check::Coordinates,
+                    line_number,
+                    None,
+                    format!(
+                        "Expected positive integer start <= end, found {}-{}",
+                        line_split[3], line_split[4]
+                    ),
+                ));
+                continue;
+            }
+        };
+
+        let feature = line_split[2];
+        if feature != "exon" && feature != "CDS" {
+            continue;
+        }
+
+        let Some(transcript_id) = get_transcript_id(&line_split) else {
+            issues.push(Issue::from(
+                Check::MissingTranscriptId,
+                line_number,
+                None,
+                format!("No transcript_id found in {} line", feature),
+            ));
+            continue;
+        };
+
+        let transcript = transcripts
+            .entry(tuni::extract_transcript_name(transcript_id).to_string())
+            .or_default();
+        let locus = (line_split[0].to_string(), line_split[6].to_string());
+
+        match &transcript.locus {
+            None => transcript.locus = Some(locus),
+            Some(existing) if *existing != locus => transcript.has_locus_conflict = true,
+            Some(_) => (),
+        }
+
+        match feature {
+            "exon" => transcript.exons.push((start, end)),
+            _ => transcript.cds.push((start, end)),
+        }
+    }
+
+    for (transcript_name, transcript) in &mut transcripts {
+        issues.extend(check_transcript(transcript_name, transcript));
+    }
+
+    Ok(Validation {
+        path: gtf_gff_path.display().to_string(),
+        n_transcripts: transcripts.len(),
+        issues,
+    })
+}
+
+/// Obtain the transcript ID of a GTF/GFF line split by tabs, as read by
+/// [`tuni::read_gtf_gff`].
+///
+/// Returns `None` if the line does not contain a "transcript_id" attribute.
+fn get_transcript_id<'a>(line_split: &[&'a str]) -> Option<&'a str> {
+    line_split[8]
+        .split(';')
+        .find(|x| x.trim().starts_with("transcript_id"))
+}
+
+/// Check the exons and CDS regions of a transcript are consistent.
+fn check_transcript(transcript_name: &str, transcript: &mut TranscriptRegions) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let transcript_issue =
+        |check: Check, message: String| Issue::from(check, None, Some(transcript_name), message);
+
+    if transcript.has_locus_conflict {
+        issues.push(transcript_issue(
+            Check::LocusConflict,
+            "Exon/CDS lines found on more than one chromosome or strand".to_string(),
+        ));
+    }
+
+    transcript.exons.sort();
+
+    if let Some((a, b)) = transcript
+        .exons
+        .windows(2)
+        .map(|x| (x[0], x[1]))
+        .find(|(a, b)| b.0 <= a.1)
+    {
+        issues.push(transcript_issue(
+            Check::OverlappingExons,
+            format!("Exon {}-{} overlaps exon {}-{}", a.0, a.1, b.0, b.1),
+        ));
+    }
+
+    // Transcripts without exons (e.g. CDS-only GFFs) cannot be checked.
+    if !transcript.exons.is_empty() {
+        if let Some(cds) = transcript.cds.iter().find(|cds| {
+            !transcript
+                .exons
+                .iter()
+                .any(|exon| exon.0 <= cds.0 && cds.1 <= exon.1)
+        }) {
+            issues.push(transcript_issue(
+                Check::CdsOutsideExons,
+                format!("CDS {}-{} is not within any exon", cds.0, cds.1),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Print validation results in the chosen format.
+///
+/// # Errors
+///
+/// Returns [`OutputWriteError`](ValidateError::OutputWriteError) if the
+/// results cannot be written.
+pub fn write_validations(
+    writer: &mut impl Write,
+    validations: &[Validation],
+    format: ValidateFormat,
+) -> Result<(), ValidateError> {
+    let write_error = |_| ValidateError::OutputWriteError;
+
+    match format {
+        ValidateFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, validations)
+                .map_err(|_| ValidateError::OutputWriteError)?;
+            writeln!(writer).map_err(write_error)?;
+        }
+        ValidateFormat::Text => {
+            for validation in validations {
+                writeln!(
+                    writer,
+                    "{}: {} transcripts, {} errors, {} warnings",
+                    validation.path,
+                    validation.n_transcripts,
+                    validation.count(Severity::Error),
+                    validation.count(Severity::Warning)
+                )
+                .map_err(write_error)?;
+
+                for issue in &validation.issues {
+                    let location = match (issue.line, &issue.transcript) {
+                        (Some(line), _) => format!("line {}", line),
+                        (None, Some(transcript)) => format!("transcript {}", transcript),
+                        (None, None) => String::new(),
+                    };
+                    writeln!(
+                        writer,
+                        "  {} [{}] {}: {}",
+                        issue.severity,
+                        issue.check.as_str(),
+                        location,
+                        issue.message
+                    )
+                    .map_err(write_error)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_gtf_gff_valid() {
+        let validation = validate_gtf_gff(&PathBuf::from("tests/data/unit/sample_1.gtf")).unwrap();
+
+        assert_eq!(validation.n_transcripts, 2);
+        assert!(validation.issues.is_empty());
+        assert_eq!(validation.max_severity(), None);
+    }
+
+    #[test]
+    fn test_validate_gtf_gff_invalid() {
+        let validation =
+            validate_gtf_gff(&PathBuf::from("tests/data/unit/sample_invalid.gtf")).unwrap();
+
+        assert_eq!(
+            validation
+                .issues
+                .iter()
+                .map(|x| (x.check, x.line, x.transcript.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                (Check::ColumnCount, Some(2), None),
+                (Check::Coordinates, Some(3), None),
+                (Check::Coordinates, Some(4), None),
+                (Check::MissingTranscriptId, Some(5), None),
+                (Check::LocusConflict, None, Some("B")),
+                (Check::OverlappingExons, None, Some("C")),
+                (Check::CdsOutsideExons, None, Some("D")),
+            ]
+        );
+        assert_eq!(validation.max_severity(), Some(Severity::Error));
+    }
+
+    #[test]
+    fn test_write_validations() {
+        let validations =
+            [validate_gtf_gff(&PathBuf::from("tests/data/unit/sample_invalid.gtf")).unwrap()];
+
+        let mut text = Vec::new();
+        write_validations(&mut text, &validations, ValidateFormat::Text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+
+        assert!(text.starts_with(
+            "tests/data/unit/sample_invalid.gtf: 4 transcripts, 4 errors, 3 warnings\n"
+        ));
+        assert!(text.contains("  warning [overlapping_exons] transcript C: "));
+
+        let mut json = Vec::new();
+        write_validations(&mut json, &validations, ValidateFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json[0]["issues"][0]["check"], "column_count");
+        assert_eq!(json[0]["issues"][0]["severity"], "error");
+    }
+}
@@ -0,0 +1,271 @@
+//! Collect warnings by category and sample, rather than logging every
+//! occurrence.
+
+use crate::error::WarningError;
+use clap::ValueEnum;
+use log::{info, warn};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Number of examples logged for each category and sample.
+const N_EXAMPLES: usize = 3;
+
+/// Category of a warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum WarningCategory {
+    /// GTF/GFF line with a transcript ID that has no exon/CDS lines.
+    UnrecognisedTranscript,
+
+    /// Transcript with exon/CDS lines on more than one chromosome or strand.
+    LocusConflict,
+
+    /// Transcripts within one sample that share the same structure.
+    RedundantTranscripts,
+
+    /// Transcript attribute compared with a number by `--filter` that is not
+    /// numeric, e.g. `cov "NA"`.
+    NonNumericAttribute,
+}
+
+impl WarningCategory {
+    /// Name of the category, as written in the run summary and warnings file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningCategory::UnrecognisedTranscript => "unrecognised_transcript",
+            WarningCategory::LocusConflict => "locus_conflict",
+            WarningCategory::RedundantTranscripts => "redundant_transcripts",
+            WarningCategory::NonNumericAttribute => "non_numeric_attribute",
+        }
+    }
+
+    /// Description of the category used when logging.
+    fn description(&self) -> &'static str {
+        match self {
+            WarningCategory::UnrecognisedTranscript => "Unrecognised transcript ID found",
+            WarningCategory::LocusConflict => {
+                "Transcript on more than one chromosome or strand found"
+            }
+            WarningCategory::RedundantTranscripts => "Transcripts sharing the same structure found",
+            WarningCategory::NonNumericAttribute => {
+                "Non-numeric attribute compared with a number found"
+            }
+        }
+    }
+}
+
+/// Warning found while reading a GTF/GFF, before the sample is known.
+#[derive(Debug, PartialEq)]
+pub struct Warning {
+    /// Category of the warning.
+    pub category: WarningCategory,
+
+    /// Details of the warning, e.g. the transcript ID.
+    pub message: String,
+}
+
+/// Warnings of one category and sample.
+#[derive(Debug, Default)]
+struct WarningGroup {
+    /// Number of warnings.
+    count: usize,
+
+    /// The first warning messages, up to `N_EXAMPLES`, or all warning messages
+    /// if every warning is kept.
+    messages: Vec<String>,
+}
+
+/// Warnings collected by category and sample.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    /// Warnings of each category and sample.
+    groups: BTreeMap<(WarningCategory, String), WarningGroup>,
+
+    /// Whether every warning message is kept, rather than only examples.
+    keep_all: bool,
+}
+
+impl Warnings {
+    /// Initialise `Warnings`, keeping every warning message if `keep_all` is
+    /// true, otherwise only examples.
+    pub fn new(keep_all: bool) -> Warnings {
+        Warnings {
+            groups: BTreeMap::new(),
+            keep_all,
+        }
+    }
+
+    /// Add a warning of a sample.
+    pub fn add(&mut self, category: WarningCategory, sample: &str, message: String) {
+        let group = self
+            .groups
+            .entry((category, sample.to_string()))
+            .or_default();
+
+        group.count += 1;
+        if self.keep_all || group.messages.len() < N_EXAMPLES {
+            group.messages.push(message);
+        }
+    }
+
+    /// Returns true if no warnings were found, otherwise false.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Add warnings found while reading a sample.
+    pub fn extend(&mut self, sample: &str, warnings: impl IntoIterator<Item = Warning>) {
+        for warning in warnings {
+            self.add(warning.category, sample, warning.message);
+        }
+    }
+
+    /// Count warnings of each category, across samples.
+    pub fn category_counts(&self) -> BTreeMap<WarningCategory, usize> {
+        let mut category_counts = BTreeMap::new();
+        for ((category, _), group) in &self.groups {
+            *category_counts.entry(*category).or_default() += group.count;
+        }
+        category_counts
+    }
+
+    /// Log the number of warnings of each category and sample, with examples.
+    pub fn log(&self) {
+        for ((category, sample), group) in &self.groups {
+            warn!(
+                "{} {} time(s) in {}, e.g. {}",
+                category.description(),
+                group.count,
+                sample,
+                group.messages[..group.messages.len().min(N_EXAMPLES)].join("; ")
+            );
+        }
+    }
+
+    /// Check that no warnings were found of the `strict` categories. An empty
+    /// `strict` applies to every category.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StrictError`](WarningError::StrictError) with the first
+    /// category in `strict` that has warnings.
+    pub fn check_strict(&self, strict: &[WarningCategory]) -> Result<(), WarningError> {
+        match self
+            .category_counts()
+            .into_iter()
+            .find(|(category, count)| {
+                *count > 0 && (strict.is_empty() || strict.contains(category))
+            }) {
+            Some((category, count)) => Err(WarningError::StrictError(category.as_str(), count)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Write every warning, one per line with its category and sample.
+///
+/// Only examples are written unless `warnings` keeps every warning message.
+/// Without any warnings, only the header is written.
+///
+/// # Errors
+///
+/// Returns [`FileCreateError`](WarningError::FileCreateError) if the file
+/// cannot be created.
+///
+/// Returns [`FileWriteError`](WarningError::FileWriteError) if any line in the
+/// file cannot be written.
+pub fn write_warnings(warnings_path: &Path, warnings: &Warnings) -> Result<(), WarningError> {
+    info!("{}", warnings_path.display());
+
+    let file = File::create(warnings_path)
+        .map_err(|_| WarningError::FileCreateError(warnings_path.to_path_buf()))?;
+    let mut writer = BufWriter::new(file);
+    let write_error = |_| WarningError::FileWriteError(warnings_path.to_path_buf());
+
+    writeln!(writer, "category\tsample\tmessage").map_err(write_error)?;
+
+    for ((category, sample), group) in &warnings.groups {
+        for message in &group.messages {
+            writeln!(writer, "{}\t{}\t{}", category.as_str(), sample, message)
+                .map_err(write_error)?;
+        }
+    }
+
+    writer.flush().map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::fs::read_to_string;
+    use tempfile::tempdir;
+
+    fn add_warnings(warnings: &mut Warnings) {
+        for i in 0..5 {
+            warnings.add(
+                WarningCategory::UnrecognisedTranscript,
+                "sample_1.gtf",
+                format!("T{}", i),
+            );
+        }
+        warnings.extend(
+            "sample_2.gtf",
+            [Warning {
+                category: WarningCategory::LocusConflict,
+                message: "A".to_string(),
+            }],
+        );
+    }
+
+    #[rstest]
+    #[case(false, 3)]
+    #[case(true, 5)]
+    fn test_write_warnings(#[case] keep_all: bool, #[case] expected_n_unrecognised: usize) {
+        let mut warnings = Warnings::new(keep_all);
+        add_warnings(&mut warnings);
+
+        assert_eq!(
+            warnings.category_counts(),
+            BTreeMap::from([
+                (WarningCategory::UnrecognisedTranscript, 5),
+                (WarningCategory::LocusConflict, 1)
+            ])
+        );
+
+        let temp_dir = tempdir().unwrap();
+        let warnings_path = temp_dir.path().join("warnings.tsv");
+        write_warnings(&warnings_path, &warnings).unwrap();
+        let lines = read_to_string(warnings_path)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(lines[0], "category\tsample\tmessage");
+        assert_eq!(lines[1], "unrecognised_transcript\tsample_1.gtf\tT0");
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|x| x.starts_with("unrecognised_transcript"))
+                .count(),
+            expected_n_unrecognised
+        );
+        assert_eq!(lines.last().unwrap(), "locus_conflict\tsample_2.gtf\tA");
+    }
+
+    #[rstest]
+    #[case(&[], true)]
+    #[case(&[WarningCategory::LocusConflict], true)]
+    #[case(&[WarningCategory::RedundantTranscripts], false)]
+    fn test_check_strict(#[case] strict: &[WarningCategory], #[case] expected_error: bool) {
+        let mut warnings = Warnings::new(false);
+        add_warnings(&mut warnings);
+
+        assert_eq!(warnings.check_strict(strict).is_err(), expected_error);
+    }
+}
@@ -1,9 +1,25 @@
 use assert_cmd::Command;
 use predicates::prelude::predicate;
 use rstest::rstest;
-use std::fs::read_to_string;
+use std::fs::{create_dir, read_dir, read_to_string, remove_dir, write};
+use std::path::Path;
 use tempfile::tempdir;
 
+/// Assert the unified GTFs written to `output_dir` match the expected unified
+/// GTFs of the integration samples.
+fn assert_matches_expected(output_dir: &Path) {
+    for sample in ["sample_1", "sample_2"] {
+        assert_eq!(
+            read_to_string(format!(
+                "tests/data/integration/expected_{}.tuni.gtf",
+                sample
+            ))
+            .unwrap(),
+            read_to_string(output_dir.join(format!("{}.tuni.gtf", sample))).unwrap(),
+        );
+    }
+}
+
 #[rstest]
 #[case("tests/data/integration/gtf_paths.txt", "gtf")]
 #[case("tests/data/integration/gff_paths.txt", "gff")]
@@ -51,3 +67,717 @@ fn test_tuni(#[case] gtf_gff_path: &str, #[case] gtf_gff_extension: &str) {
         .unwrap(),
     );
 }
+
+// Test that annotating a sample against the catalog of a previous run assigns
+// the same unified IDs and leaves the catalog unchanged.
+#[test]
+fn test_tuni_annotate() {
+    let temp_dir = tempdir().unwrap();
+    let annotate_dir = tempdir().unwrap();
+    let catalog_path = temp_dir.path().join("tuni_catalog.tsv");
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    let catalog = read_to_string(&catalog_path).unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("annotate")
+        .arg("--catalog")
+        .arg(&catalog_path)
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths_sample_2.txt")
+        .arg("--output-dir")
+        .arg(annotate_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string("tests/data/integration/expected_sample_2.tuni.gtf").unwrap(),
+        read_to_string(annotate_dir.path().join("sample_2.tuni.gtf")).unwrap(),
+    );
+    assert_eq!(catalog, read_to_string(&catalog_path).unwrap());
+}
+
+// Test that annotating writes no outputs if the updated catalog cannot be
+// written, and leaves the catalog unchanged if the outputs cannot be written.
+#[test]
+fn test_tuni_annotate_failed_write() {
+    let temp_dir = tempdir().unwrap();
+    let annotate_dir = tempdir().unwrap();
+    let catalog_path = temp_dir.path().join("tuni_catalog.tsv");
+    let temp_catalog_path = temp_dir.path().join("tuni_catalog.tsv.tmp");
+    let gtf_gff_path = annotate_dir.path().join("sample_2.tuni.gtf");
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths_sample_1.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    let catalog = read_to_string(&catalog_path).unwrap();
+    let annotate = || {
+        Command::cargo_bin(env!("CARGO_PKG_NAME"))
+            .unwrap()
+            .arg("annotate")
+            .arg("--catalog")
+            .arg(&catalog_path)
+            .arg("--gtf-gff-path")
+            .arg("tests/data/integration/gtf_paths_sample_2.txt")
+            .arg("--output-dir")
+            .arg(annotate_dir.path())
+            .assert()
+            .failure();
+    };
+
+    create_dir(&temp_catalog_path).unwrap();
+    annotate();
+    assert!(!gtf_gff_path.exists());
+    remove_dir(&temp_catalog_path).unwrap();
+
+    create_dir(&gtf_gff_path).unwrap();
+    annotate();
+    assert!(!temp_catalog_path.exists());
+
+    assert_eq!(catalog, read_to_string(&catalog_path).unwrap());
+}
+
+// Test that merging the catalogs of separate runs gives the same catalog as a
+// single run across all samples.
+#[test]
+fn test_tuni_merge_catalogs() {
+    let joint_dir = tempdir().unwrap();
+    let merged_dir = tempdir().unwrap();
+    let sample_dirs = [tempdir().unwrap(), tempdir().unwrap()];
+
+    let run_tuni = |gtf_gff_path: &str, output_dir: &Path| {
+        Command::cargo_bin(env!("CARGO_PKG_NAME"))
+            .unwrap()
+            .arg("--gtf-gff-path")
+            .arg(gtf_gff_path)
+            .arg("--output-dir")
+            .arg(output_dir)
+            .assert()
+            .success();
+    };
+
+    run_tuni("tests/data/integration/gtf_paths.txt", joint_dir.path());
+    run_tuni(
+        "tests/data/integration/gtf_paths_sample_1.txt",
+        sample_dirs[0].path(),
+    );
+    run_tuni(
+        "tests/data/integration/gtf_paths_sample_2.txt",
+        sample_dirs[1].path(),
+    );
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("merge-catalogs")
+        .arg("--catalogs")
+        .arg(sample_dirs[0].path().join("tuni_catalog.tsv"))
+        .arg(sample_dirs[1].path().join("tuni_catalog.tsv"))
+        .arg("--output-dir")
+        .arg(merged_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(joint_dir.path().join("tuni_catalog.tsv")).unwrap(),
+        read_to_string(merged_dir.path().join("tuni_catalog.tsv")).unwrap(),
+    );
+    // 4 transcripts in sample 1, 4 transcripts in sample 2, plus a header.
+    assert_eq!(
+        read_to_string(merged_dir.path().join("tuni_translation.tsv"))
+            .unwrap()
+            .lines()
+            .count(),
+        9
+    );
+}
+
+// Test that runs over disjoint contigs merge into the same unified IDs and
+// outputs as a single run across every contig.
+#[test]
+fn test_tuni_merge_shards() {
+    let input_dir = tempdir().unwrap();
+    let joint_dir = tempdir().unwrap();
+    let merged_dir = tempdir().unwrap();
+    let shard_dirs = [tempdir().unwrap(), tempdir().unwrap()];
+
+    // Add expression values and a redundant transcript to each sample.
+    let mut gtf_paths = Vec::new();
+    for sample in ["sample_1", "sample_2"] {
+        let gtf = read_to_string(format!("tests/data/integration/{}.gtf", sample)).unwrap();
+        let mut lines = Vec::new();
+        for (i, line) in gtf.lines().enumerate() {
+            match line.split('\t').nth(2) {
+                Some("transcript") => lines.push(format!("{} TPM \"{}\";", line, i)),
+                _ => lines.push(line.to_string()),
+            }
+        }
+        lines.extend(
+            lines
+                .iter()
+                .filter(|x| x.contains("transcript_id \"A\""))
+                .map(|x| x.replace("transcript_id \"A\"", "transcript_id \"A_dup\""))
+                .collect::<Vec<String>>(),
+        );
+
+        let gtf_path = input_dir.path().join(format!("{}.gtf", sample));
+        write(&gtf_path, lines.join("\n")).unwrap();
+        gtf_paths.push(gtf_path.to_string_lossy().to_string());
+    }
+    let gtf_paths_path = input_dir.path().join("gtf_paths.txt");
+    write(&gtf_paths_path, gtf_paths.join("\n")).unwrap();
+
+    let run_tuni = |output_dir: &Path, contigs: Option<&str>| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.arg("--gtf-gff-path")
+            .arg(&gtf_paths_path)
+            .arg("--output-dir")
+            .arg(output_dir)
+            .arg("--expression-attribute")
+            .arg("TPM")
+            .arg("--tx2gene")
+            .arg("--tx2gene-sample-ids")
+            .arg("--gene-attribute")
+            .arg("test_case");
+        if let Some(contigs) = contigs {
+            cmd.arg("--shard-contigs").arg(contigs);
+        }
+        cmd.assert().success();
+    };
+
+    run_tuni(joint_dir.path(), None);
+    run_tuni(shard_dirs[0].path(), Some("chr1,chr3,chr5"));
+    run_tuni(shard_dirs[1].path(), Some("chr2,chr4"));
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("merge-shards")
+        .arg("--shards")
+        .arg(shard_dirs[0].path())
+        .arg(shard_dirs[1].path())
+        .arg("--output-dir")
+        .arg(merged_dir.path())
+        .assert()
+        .success();
+
+    for file_name in [
+        "tuni_catalog.tsv",
+        "tuni_redundancy.tsv",
+        "tuni_expression.tsv",
+        "tuni_tx2gene.tsv",
+        "tuni_tx2gene_samples.tsv",
+    ] {
+        let joint_output = read_to_string(joint_dir.path().join(file_name)).unwrap();
+        assert!(joint_output.lines().count() > 1);
+        assert_eq!(
+            joint_output,
+            read_to_string(merged_dir.path().join(file_name)).unwrap(),
+        );
+    }
+
+    // Timings differ between runs.
+    let read_summary = |dir: &Path| {
+        let mut summary: serde_json::Value =
+            serde_json::from_str(&read_to_string(dir.join("tuni_summary.json")).unwrap()).unwrap();
+        let summary = summary.as_object_mut().unwrap();
+        summary.remove("wall_time_secs");
+        summary.remove("peak_memory_bytes");
+        summary.clone()
+    };
+    assert_eq!(
+        read_summary(joint_dir.path()),
+        read_summary(merged_dir.path())
+    );
+
+    // Lines are merged in the order of the input GTF/GFFs.
+    for sample in ["sample_1", "sample_2"] {
+        let file_name = format!("{}.tuni.gtf", sample);
+        assert_eq!(
+            read_to_string(joint_dir.path().join(&file_name)).unwrap(),
+            read_to_string(merged_dir.path().join(&file_name)).unwrap()
+        );
+    }
+}
+
+// Test that transcripts not found in enough samples are dropped from the
+// outputs and the catalog.
+#[test]
+fn test_tuni_recurrence_filter() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .arg("--min-samples")
+        .arg("2")
+        .arg("--filter-action")
+        .arg("drop")
+        .assert()
+        .success();
+
+    // Only transcript A is found in both samples.
+    let unified_sample_2 = read_to_string(temp_dir.path().join("sample_2.tuni.gtf")).unwrap();
+    assert!(unified_sample_2
+        .lines()
+        .all(|x| x.contains(r#"tuni_id "tuni_0";"#) || x.contains("\tgene\t")));
+    assert_eq!(
+        read_to_string(temp_dir.path().join("tuni_catalog.tsv"))
+            .unwrap()
+            .lines()
+            .count(),
+        2
+    );
+}
+
+// Test that salmon and kallisto quantifications are translated to unified IDs.
+#[test]
+fn test_tuni_quant() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("quant")
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--quant-path")
+        .arg("tests/data/integration/quant/quant_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string("tests/data/integration/quant/expected_tuni_counts.tsv").unwrap(),
+        read_to_string(temp_dir.path().join("tuni_counts.tsv")).unwrap(),
+    );
+}
+
+// Test that transcripts are unified across GTFs using different chromosome
+// naming conventions, while the original chromosome names are kept.
+#[test]
+fn test_tuni_chr_style() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths_ensembl.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .arg("--chr-style")
+        .arg("ucsc")
+        .assert()
+        .success();
+
+    let expected_sample_2 = read_to_string("tests/data/integration/expected_sample_2.tuni.gtf")
+        .unwrap()
+        .lines()
+        .map(|x| x.strip_prefix("chr").unwrap().to_string())
+        .collect::<Vec<String>>();
+
+    assert_eq!(
+        expected_sample_2,
+        read_to_string(temp_dir.path().join("sample_2_ensembl.tuni.gtf"))
+            .unwrap()
+            .lines()
+            .collect::<Vec<&str>>(),
+    );
+}
+
+// Test that validate reports problems without writing outputs, with an exit
+// code summarising the most severe problem.
+#[rstest]
+#[case("text", "[overlapping_exons] transcript A")]
+#[case("json", "\"check\": \"overlapping_exons\"")]
+fn test_tuni_validate(#[case] format: &str, #[case] expected_output: &str) {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("validate")
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--format")
+        .arg(format)
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains(expected_output));
+}
+
+// Test that unified IDs do not depend on the number of threads.
+#[rstest]
+#[case("0")]
+#[case("4")]
+fn test_tuni_threads(#[case] threads: &str) {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--threads")
+        .arg(threads)
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    // Unified IDs are the same regardless of the number of threads.
+    assert_matches_expected(temp_dir.path());
+}
+
+// Test that unifying using temporary files matches unifying in memory, where
+// a limit of 1 byte spills every transcript to its own run.
+#[rstest]
+#[case("1")]
+#[case("1M")]
+fn test_tuni_max_memory(#[case] max_memory: &str) {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--max-memory")
+        .arg(max_memory)
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_matches_expected(temp_dir.path());
+
+    // Temporary files are removed.
+    assert!(read_dir(temp_dir.path()).unwrap().all(|x| !x
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .starts_with(".tuni_tmp_")));
+}
+
+// Test that caching lines while reading matches reading GTF/GFFs twice, and
+// that the cache is removed.
+#[test]
+fn test_tuni_cache_dir() {
+    let temp_dir = tempdir().unwrap();
+    let cache_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--cache-dir")
+        .arg(cache_dir.path())
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_matches_expected(temp_dir.path());
+    assert_eq!(read_dir(cache_dir.path()).unwrap().count(), 0);
+}
+
+// Test that the expression matrix and tx2gene read from the cache match those
+// read from the GTF/GFFs.
+#[test]
+fn test_tuni_cache_dir_outputs() {
+    let output_dirs = [tempdir().unwrap(), tempdir().unwrap()];
+    let cache_dir = tempdir().unwrap();
+    let gtf_paths = output_dirs[0].path().join("gtf_paths.txt");
+    write(&gtf_paths, "tests/data/unit/sample_expression.gtf").unwrap();
+
+    for (output_dir, cache) in output_dirs.iter().zip([false, true]) {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        if cache {
+            cmd.arg("--cache-dir").arg(cache_dir.path());
+        }
+        cmd.arg("--gtf-gff-path")
+            .arg(&gtf_paths)
+            .arg("--output-dir")
+            .arg(output_dir.path())
+            .arg("--expression-attribute")
+            .arg("TPM")
+            .arg("--tx2gene")
+            .arg("--gene-attribute")
+            .arg("TPM")
+            .assert()
+            .success();
+    }
+
+    for file_name in ["tuni_expression.tsv", "tuni_tx2gene.tsv"] {
+        let output = read_to_string(output_dirs[1].path().join(file_name)).unwrap();
+        assert_eq!(
+            read_to_string(output_dirs[0].path().join(file_name)).unwrap(),
+            output
+        );
+        assert!(output.lines().count() > 1);
+    }
+}
+
+// Test that dropping transcripts outside of a region, in memory or not, matches
+// only reading the region's contig, apart from the header describing the filter
+// and the footer listing the shard's line numbers.
+#[rstest]
+#[case(&["--region", "chr1:1-1000"])]
+#[case(&["--include-contigs", "chr1"])]
+#[case(&["--exclude-contigs", "chr2,chr3,chr4,chr5", "--max-memory", "1"])]
+fn test_tuni_region(#[case] args: &[&str]) {
+    let contigs_dir = tempdir().unwrap();
+    let region_dir = tempdir().unwrap();
+
+    for (output_dir, args) in [
+        (&contigs_dir, &["--shard-contigs", "chr1"][..]),
+        (&region_dir, args),
+    ] {
+        Command::cargo_bin(env!("CARGO_PKG_NAME"))
+            .unwrap()
+            .args(args)
+            .arg("--filter-action")
+            .arg("drop")
+            .arg("--gtf-gff-path")
+            .arg("tests/data/integration/gtf_paths.txt")
+            .arg("--output-dir")
+            .arg(output_dir.path())
+            .assert()
+            .success();
+    }
+
+    for sample in ["sample_1", "sample_2"] {
+        let file_name = format!("{}.tuni.gtf", sample);
+        let region_gtf = read_to_string(region_dir.path().join(&file_name)).unwrap();
+        let (header, region_lines) = region_gtf.split_once('\n').unwrap();
+
+        assert!(header.starts_with("#tuni_region_filter: "));
+        assert!(header.ends_with("; out-of-region lines dropped"));
+        let contigs_gtf = read_to_string(contigs_dir.path().join(&file_name)).unwrap();
+        let (contigs_lines, footer) = contigs_gtf.trim_end().rsplit_once('\n').unwrap();
+        assert!(footer.starts_with("#tuni_shard_lines: "));
+        assert_eq!(format!("{}\n", contigs_lines), region_lines);
+    }
+}
+
+// Test that only coding transcripts are unified with --coding-only, in memory
+// or not, with the lines of noncoding transcripts tagged.
+#[rstest]
+#[case(&[])]
+#[case(&["--max-memory", "1"])]
+fn test_tuni_structure_filter(#[case] args: &[&str]) {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(args)
+        .arg("--coding-only")
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+
+    // Every unified transcript has CDS boundaries, in the last column.
+    let catalog = read_to_string(temp_dir.path().join("tuni_catalog.tsv")).unwrap();
+    assert_eq!(catalog.lines().count(), 5);
+    assert!(catalog.lines().all(|x| !x.ends_with('\t')));
+
+    // Transcripts A and B of sample 1 are noncoding.
+    let unified_sample_1 = read_to_string(temp_dir.path().join("sample_1.tuni.gtf")).unwrap();
+    assert_eq!(
+        unified_sample_1
+            .lines()
+            .filter(|x| x.ends_with(r#"tuni_filtered "structure";"#))
+            .count(),
+        6
+    );
+}
+
+// Test that transcripts failing --filter are dropped from the outputs and the
+// catalog, and that comparing a non-numeric attribute with a number fails.
+#[test]
+fn test_tuni_attribute_filter() {
+    let temp_dir = tempdir().unwrap();
+
+    let run_tuni = |filter: &str| {
+        Command::cargo_bin(env!("CARGO_PKG_NAME"))
+            .unwrap()
+            .arg("--filter")
+            .arg(filter)
+            .arg("--filter-action")
+            .arg("drop")
+            .arg("--gtf-gff-path")
+            .arg("tests/data/integration/gtf_paths.txt")
+            .arg("--output-dir")
+            .arg(temp_dir.path())
+            .assert()
+    };
+
+    run_tuni(r#"test_case != "Different strand""#).success();
+
+    // Transcript B of each sample, on chr2, is the "Different strand" case.
+    for sample in ["sample_1", "sample_2"] {
+        let unified_gtf =
+            read_to_string(temp_dir.path().join(format!("{}.tuni.gtf", sample))).unwrap();
+        assert!(!unified_gtf.contains("Different strand"));
+    }
+    assert!(!read_to_string(temp_dir.path().join("tuni_catalog.tsv"))
+        .unwrap()
+        .contains("chr2"));
+
+    // Non-numeric attributes fail the comparison and are warned about.
+    run_tuni("test_case >= 1")
+        .success()
+        .stderr(predicate::str::contains(
+            "Non-numeric attribute compared with a number found",
+        ));
+}
+
+// Test that the unify subcommand matches running tuni without a subcommand.
+#[test]
+fn test_tuni_unify() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("unify")
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .arg("--verbose")
+        .assert()
+        .success();
+
+    assert_matches_expected(temp_dir.path());
+}
+
+// Test that arguments are read from the config, that command line arguments
+// take priority and that the resolved config is written alongside the outputs.
+#[rstest]
+#[case(&[], "min_samples = 2")]
+#[case(&["--min-samples", "1"], "min_samples = 1")]
+fn test_tuni_config(#[case] args: &[&str], #[case] expected_config: &str) {
+    let temp_dir = tempdir().unwrap();
+    let config_path = temp_dir.path().join("tuni.toml");
+    std::fs::write(
+        &config_path,
+        "gtf_gff_path = \"tests/data/integration/gtf_paths.txt\"\nmin_samples = 2\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .args(args)
+        .assert()
+        .success();
+
+    assert!(read_to_string(temp_dir.path().join("tuni_config.toml"))
+        .unwrap()
+        .lines()
+        .any(|x| x == expected_config));
+}
+
+// Test that the run summary is written as JSON, and only printed as a table
+// with --verbose so that stdout is otherwise quiet.
+#[test]
+fn test_tuni_summary() {
+    let temp_dir = tempdir().unwrap();
+
+    let run_tuni = |args: &[&str]| {
+        Command::cargo_bin(env!("CARGO_PKG_NAME"))
+            .unwrap()
+            .args(args)
+            .arg("--gtf-gff-path")
+            .arg("tests/data/integration/gtf_paths.txt")
+            .arg("--output-dir")
+            .arg(temp_dir.path())
+            .assert()
+            .success()
+    };
+
+    run_tuni(&[]).stdout(predicate::str::is_empty());
+    run_tuni(&["--verbose"]).stdout(predicate::str::contains("Unified IDs"));
+
+    let summary = read_to_string(temp_dir.path().join("tuni_summary.json")).unwrap();
+
+    assert!(summary.contains("\"n_unified_ids\": 7"));
+    assert!(summary.contains("\"unrecognised_transcript\": 1"));
+}
+
+// Test that only the header of the warnings file is written when no warnings
+// are found.
+#[test]
+fn test_tuni_no_warnings() {
+    let temp_dir = tempdir().unwrap();
+    let warnings_path = temp_dir.path().join("warnings.tsv");
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths_sample_2.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .arg("--warnings-file")
+        .arg(&warnings_path)
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(warnings_path).unwrap(),
+        "category\tsample\tmessage\n"
+    );
+}
+
+// Test that warnings are aggregated, can be written in full and, with
+// --strict, turn into errors.
+#[rstest]
+#[case(&[], true)]
+#[case(&["--strict"], false)]
+#[case(&["--strict", "unrecognised_transcript"], false)]
+#[case(&["--strict", "locus_conflict"], true)]
+fn test_tuni_warnings(#[case] args: &[&str], #[case] expected_success: bool) {
+    let temp_dir = tempdir().unwrap();
+    let warnings_path = temp_dir.path().join("warnings.tsv");
+
+    let assert = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("--gtf-gff-path")
+        .arg("tests/data/integration/gtf_paths.txt")
+        .arg("--output-dir")
+        .arg(temp_dir.path())
+        .arg("--warnings-file")
+        .arg(&warnings_path)
+        .args(args)
+        .assert()
+        .stderr(predicate::str::contains(
+            "Unrecognised transcript ID found 1 time(s) in sample_1.gtf",
+        ));
+
+    match expected_success {
+        true => assert.success(),
+        false => assert.failure(),
+    };
+
+    assert_eq!(
+        read_to_string(warnings_path).unwrap(),
+        "category\tsample\tmessage\nunrecognised_transcript\tsample_1.gtf\tF\n"
+    );
+}